@@ -27,7 +27,7 @@ use wayland_protocols_wlr::layer_shell::v1::client::{
 };
 
 use super::render;
-use super::shm::ShmBuffer;
+use capit_shm::ShmBuffer;
 
 const BTN_LEFT: u32 = 272;
 const KEY_ESC: u32 = 1;
@@ -109,11 +109,17 @@ pub struct App {
     pub(crate) hover: Option<Choice>,
     pub(crate) selected: Option<Choice>,
     pub(crate) window_supported: bool,
+    /// The last pick failed (daemon unreachable, capture error); drawn as a
+    /// red border until the bar closes again.
+    pub(crate) error: Option<String>,
 
     // Daemon-provided colours (ARGB)
     pub(crate) accent_colour: u32,
     pub(crate) bar_background_colour: u32,
 
+    // Extra margins from config (top, right, bottom, left)
+    pub(crate) margins: [i32; 4],
+
     pub(crate) pending_redraw: bool,
     pub result: Option<Option<Mode>>,
 }
@@ -124,6 +130,8 @@ impl App {
         output_state: OutputState,
         accent_colour: u32,
         bar_background_colour: u32,
+        margins: [i32; 4],
+        window_supported: bool,
     ) -> Self {
         Self {
             registry_state,
@@ -147,11 +155,14 @@ impl App {
 
             hover: None,
             selected: None,
-            window_supported: false,
+            window_supported,
+            error: None,
 
             accent_colour,
             bar_background_colour,
 
+            margins,
+
             pending_redraw: true,
             result: None,
         }
@@ -272,9 +283,11 @@ impl App {
             (),
         );
 
-        // Centered at bottom
+        // Centered at bottom. Margins only apply on anchored edges, so today
+        // `bottom` is what lifts the bar above a panel; the rest pass through.
+        let [top, right, bottom, left] = self.margins;
         layer_surface.set_anchor(Anchor::Bottom);
-        layer_surface.set_margin(0, 0, BAR_MARGIN_BOTTOM, 0);
+        layer_surface.set_margin(top, right, BAR_MARGIN_BOTTOM + bottom, left);
 
         // Keyboard focus so ESC/ENTER/ARROWS works reliably
         layer_surface.set_keyboard_interactivity(KeyboardInteractivity::Exclusive);
@@ -378,17 +391,12 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for App {
                 let w = if width == 0 { BAR_W as u32 } else { width };
                 let h = if height == 0 { BAR_H as u32 } else { height };
 
-                let needs_resize = state
-                    .shm_buf
-                    .as_ref()
-                    .map_or(true, |b| b.width != w as i32 || b.height != h as i32);
-
-                if needs_resize {
-                    if let Some(shm) = state.shm.as_ref() {
-                        if let Ok(new_buf) = ShmBuffer::new(shm, qh, w as i32, h as i32) {
-                            state.shm_buf = Some(new_buf);
-                        }
-                    }
+                let ensured = match state.shm.as_ref() {
+                    Some(shm) => ShmBuffer::ensure(&mut state.shm_buf, shm, qh, w as i32, h as i32),
+                    None => Err("no wl_shm".into()),
+                };
+                if let Err(e) = ensured {
+                    eprintln!("capit-bar: failed to resize buffer: {e}");
                 }
 
                 state.configured = true;
@@ -543,9 +551,7 @@ impl Dispatch<wl_buffer::WlBuffer, ()> for App {
     ) {
         if let wl_buffer::Event::Release = event {
             if let Some(sb) = state.shm_buf.as_mut() {
-                if &sb.buffer == buffer {
-                    sb.busy = false;
-                }
+                sb.release(buffer);
             }
             if state.pending_redraw {
                 state.request_redraw();
@@ -25,13 +25,14 @@ fn clamp_u8(v: i32) -> u8 {
 }
 
 /// Additive lighten/darken by signed delta per channel.
-/// Keeps alpha unchanged.
+/// Keeps alpha unchanged; channels stay <= alpha so a premultiplied base
+/// stays premultiplied.
 #[inline]
 pub(crate) fn shift_rgb(base: u32, delta: i16) -> u32 {
     let aa = a(base);
-    let rr = clamp_u8(r(base) as i32 + delta as i32);
-    let gg = clamp_u8(g(base) as i32 + delta as i32);
-    let bb = clamp_u8(b(base) as i32 + delta as i32);
+    let rr = clamp_u8(r(base) as i32 + delta as i32).min(aa);
+    let gg = clamp_u8(g(base) as i32 + delta as i32).min(aa);
+    let bb = clamp_u8(b(base) as i32 + delta as i32).min(aa);
     argb(aa, rr, gg, bb)
 }
 
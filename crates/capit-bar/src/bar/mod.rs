@@ -7,6 +7,5 @@ pub mod icons;
 pub mod pixels;
 pub mod render;
 pub mod run;
-pub mod shm;
 
 pub use run::run_bar;
@@ -10,6 +10,10 @@ use super::pixels;
 const ICON_TINT_ON: u32 = 0xFFF5_F7FA;
 const ICON_TINT_OFF: u32 = 0xFF6B_7078;
 
+// Error state: border colour and width while `app.error` is set
+const ERROR_BORDER: u32 = 0xFFE5_484D;
+const ERROR_BORDER_W: i32 = 3;
+
 // Layout
 const BTN_PAD: i32 = 10;
 
@@ -33,9 +37,13 @@ pub(crate) fn redraw(app: &mut App) -> Result<(), String> {
     // Derive slot colours from bar background (single config knob stays clean).
     let sc = colour::derive_slot_colours(app.bar_background_colour);
 
-    // Bar background + subtle border derived from bg
+    // Bar background + subtle border derived from bg (red after a failed pick)
     pixels::fill_u32(buf, app.bar_background_colour);
-    pixels::draw_rect_outline(buf, BAR_W, BAR_H, 0, 0, BAR_W, BAR_H, 1, sc.border);
+    if app.error.is_some() {
+        pixels::draw_rect_outline(buf, BAR_W, BAR_H, 0, 0, BAR_W, BAR_H, ERROR_BORDER_W, ERROR_BORDER);
+    } else {
+        pixels::draw_rect_outline(buf, BAR_W, BAR_H, 0, 0, BAR_W, BAR_H, 1, sc.border);
+    }
 
     // Rounded bar shape
     pixels::apply_rounded_mask(buf, BAR_W, BAR_H, RADIUS);
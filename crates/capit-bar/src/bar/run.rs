@@ -2,6 +2,7 @@
 // License: MIT
 
 use capit_core::Mode;
+use capit_shm::premultiply;
 
 use smithay_client_toolkit::{
     output::OutputState,
@@ -22,7 +23,17 @@ use super::app::App;
 const DEFAULT_ACCENT: u32 = 0xFF0A_84FF;
 const DEFAULT_BAR_BG: u32 = 0xFF0F_1115;
 
-pub fn run_bar(accent_colour: u32, bar_background_colour: u32) -> Result<Option<Mode>, String> {
+/// `margins` are extra pixels (top, right, bottom, left) on top of the built-in
+/// offset, e.g. to clear a bottom panel that doesn't reserve an exclusive zone.
+/// `window_supported` comes from the daemon (UiConfig). `error` is why the
+/// previous pick failed, if it did; the bar shows it as an error border.
+pub fn run_bar(
+    accent_colour: u32,
+    bar_background_colour: u32,
+    margins: [i32; 4],
+    window_supported: bool,
+    error: Option<&str>,
+) -> Result<Option<Mode>, String> {
     let conn = Connection::connect_to_env().map_err(|e| format!("wayland connect: {e}"))?;
 
     let (globals, mut queue) =
@@ -32,10 +43,12 @@ pub fn run_bar(accent_colour: u32, bar_background_colour: u32) -> Result<Option<
     let registry_state = RegistryState::new(&globals);
     let output_state = OutputState::new(&globals, &qh);
 
-    let accent = if accent_colour == 0 { DEFAULT_ACCENT } else { accent_colour };
-    let bg = if bar_background_colour == 0 { DEFAULT_BAR_BG } else { bar_background_colour };
+    // Config colours are straight alpha; the buffer wants premultiplied.
+    let accent = premultiply(if accent_colour == 0 { DEFAULT_ACCENT } else { accent_colour });
+    let bg = premultiply(if bar_background_colour == 0 { DEFAULT_BAR_BG } else { bar_background_colour });
 
-    let mut app = App::new(registry_state, output_state, accent, bg);
+    let mut app = App::new(registry_state, output_state, accent, bg, margins, window_supported);
+    app.error = error.map(str::to_owned);
 
     app.compositor = globals
         .bind::<wl_compositor::WlCompositor, _, _>(&qh, 1..=6, ())
@@ -2,7 +2,7 @@
 // License: MIT
 
 use capit_core::{Mode, Target};
-use capit_ipc::{Event, IpcClient, Request, Response};
+use capit_ipc::{CaptureOptions, Event, IpcClient, Request, Response};
 use eventline::{debug, error, info};
 
 use crate::print;
@@ -18,14 +18,15 @@ pub fn start_capture(
     mode: Mode,
     target: Option<Target>,
     with_ui: bool,
+    options: CaptureOptions,
 ) -> Result<CaptureOutcome, String> {
     debug!(
-        "start_capture: mode={:?}, target={:?}, with_ui={}",
-        mode, target, with_ui
+        "start_capture: mode={:?}, target={:?}, with_ui={}, options={:?}",
+        mode, target, with_ui, options
     );
 
     let resp = client
-        .call(Request::StartCapture { mode, target, with_ui })
+        .call(Request::StartCapture { mode, target, with_ui, options })
         .map_err(|e| format!("{e}"))?;
 
     match resp {
@@ -41,7 +42,7 @@ pub fn start_capture(
         debug!("event: {:?}", ev);
 
         match ev {
-            Event::CaptureFinished { path } => {
+            Event::CaptureFinished { path, .. } => {
                 info!("capture finished: {}", path);
                 return Ok(CaptureOutcome::Finished { path });
             }
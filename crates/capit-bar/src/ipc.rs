@@ -3,6 +3,7 @@
 
 use std::path::Path;
 use std::fmt;
+use std::time::Duration;
 
 use capit_ipc::IpcClient;
 use eventline::error;
@@ -18,8 +19,12 @@ impl fmt::Display for ConnectError {
 
 impl std::error::Error for ConnectError {}
 
+/// Long enough to ride out a daemon restart (~2s).
+const CONNECT_ATTEMPTS: u32 = 10;
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(200);
+
 pub fn connect(socket: &Path) -> Result<IpcClient, ConnectError> {
-    IpcClient::connect(socket).map_err(|e| {
+    IpcClient::connect_with_retry(socket, CONNECT_ATTEMPTS, CONNECT_RETRY_DELAY).map_err(|e| {
         error!("failed to connect to daemon: {e}");
 
         ConnectError(format!(
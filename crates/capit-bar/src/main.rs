@@ -8,11 +8,10 @@ mod print;
 
 use std::path::{Path, PathBuf};
 
-use capit_core::{Mode, Target};
 use capit_ipc::{Request, Response};
 use capit_ipc::protocol::UiConfig;
 
-use eventline::{debug, info};
+use eventline::{debug, info, warn};
 
 #[derive(Clone)]
 struct CliError(String);
@@ -49,10 +48,7 @@ fn default_socket_path() -> PathBuf {
     if let Ok(p) = std::env::var("CAPIT_SOCKET") {
         return PathBuf::from(p);
     }
-    if let Ok(rt) = std::env::var("XDG_RUNTIME_DIR") {
-        return PathBuf::from(rt).join("capit").join("capit.sock");
-    }
-    PathBuf::from("/tmp").join("capit").join("capit.sock")
+    capit_ipc::paths::default_socket_path()
 }
 
 /// Ask daemon for UI config (theme/accent) so bar can match.
@@ -94,8 +90,18 @@ fn main() -> Result<(), CliError> {
         ui.accent_colour, ui.bar_background_colour
     );
 
+    // Why the previous pick failed; the bar comes back with an error border
+    // instead of exiting, so a daemon restart doesn't cost the user the bar.
+    let mut last_error: Option<String> = None;
+
     loop {
-        let picked = bar::run_bar(ui.accent_colour, ui.bar_background_colour)?;
+        let picked = bar::run_bar(
+            ui.accent_colour,
+            ui.bar_background_colour,
+            ui.bar_margins,
+            ui.window_supported,
+            last_error.as_deref(),
+        )?;
         let Some(mode) = picked else {
             info!("bar cancelled -> exit");
             std::process::exit(2);
@@ -103,20 +109,32 @@ fn main() -> Result<(), CliError> {
 
         info!("bar selected mode: {:?}", mode);
 
-        let mut client = ipc::connect(&socket)?;
-
-        let target = match mode {
-            Mode::Screen => Some(Target::AllScreens),
-            _ => None,
+        let mut client = match ipc::connect(&socket) {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!("{e}");
+                last_error = Some(e.to_string());
+                continue;
+            }
         };
 
-        match capture::start_capture(&mut client, mode, target, false)? {
-            capture::CaptureOutcome::Finished { path } => {
+        // No explicit target: the daemon applies capit.screen_default for Screen.
+        let target = None;
+
+        match capture::start_capture(&mut client, mode, target, false, Default::default()) {
+            Ok(capture::CaptureOutcome::Finished { path }) => {
                 println!("saved to: {path}");
                 return Ok(());
             }
-            capture::CaptureOutcome::Cancelled => {
+            Ok(capture::CaptureOutcome::Cancelled) => {
                 info!("capture cancelled -> back to bar");
+                last_error = None;
+                continue;
+            }
+            Err(e) => {
+                warn!("capture failed -> back to bar: {e}");
+                eprintln!("capit-bar: {e}");
+                last_error = Some(e);
                 continue;
             }
         }
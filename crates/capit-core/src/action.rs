@@ -0,0 +1,40 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use serde::{Deserialize, Serialize};
+
+/// What a capture ends up as: `capit.default_action`, or per capture with
+/// `--save` / `--copy` / `--copy-only` / `--edit`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PostAction {
+    /// Keep the file.
+    #[default]
+    Save,
+    /// Copy to the clipboard only; the file is removed once copied.
+    Copy,
+    /// Keep the file, opening it in the editor before anything else.
+    Edit,
+    /// Keep the file and copy it to the clipboard.
+    SaveCopy,
+}
+
+impl PostAction {
+    /// Config spelling: save | copy | edit | save_copy (case-insensitive).
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "save" => Ok(PostAction::Save),
+            "copy" => Ok(PostAction::Copy),
+            "edit" => Ok(PostAction::Edit),
+            "save_copy" | "save-copy" => Ok(PostAction::SaveCopy),
+            other => Err(format!("unknown action {other:?} (expected save | copy | edit | save_copy)")),
+        }
+    }
+
+    pub fn keeps_file(self) -> bool {
+        self != PostAction::Copy
+    }
+
+    pub fn copies(self) -> bool {
+        matches!(self, PostAction::Copy | PostAction::SaveCopy)
+    }
+}
@@ -1,6 +1,9 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,3 +11,53 @@ pub enum CapitError {
     #[error("capture failed")]
     CaptureFailed,
 }
+
+/// Machine-readable reason carried by `Response::Error`, next to the
+/// human-readable message. Scripts should branch on this, not the text.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The compositor reported no outputs.
+    NoOutputs,
+    /// The requested output name, id or index doesn't exist.
+    UnknownOutput,
+    /// The screenshot portal refused the request.
+    PortalDenied,
+    /// The user cancelled a portal dialog.
+    Cancelled,
+    /// The mode or feature isn't available yet.
+    NotImplemented,
+    /// The output location is missing, full, read-only or not writable.
+    Storage,
+    /// Another capture is in progress (e.g. a countdown is running).
+    Busy,
+    /// The request doesn't make sense in the current state or has bad arguments.
+    InvalidRequest,
+    /// Grabbing or saving the image failed for another reason.
+    CaptureFailed,
+    /// Reading or writing the clipboard failed.
+    Clipboard,
+}
+
+impl ErrorCode {
+    /// Stable snake_case name, as printed by the CLI.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NoOutputs => "no_outputs",
+            ErrorCode::UnknownOutput => "unknown_output",
+            ErrorCode::PortalDenied => "portal_denied",
+            ErrorCode::Cancelled => "cancelled",
+            ErrorCode::NotImplemented => "not_implemented",
+            ErrorCode::Storage => "storage",
+            ErrorCode::Busy => "busy",
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::CaptureFailed => "capture_failed",
+            ErrorCode::Clipboard => "clipboard",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
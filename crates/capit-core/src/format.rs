@@ -0,0 +1,49 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "clap")]
+use clap::ValueEnum;
+
+/// Encoded image format for a saved capture.
+#[cfg_attr(feature = "clap", derive(ValueEnum))]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    #[cfg_attr(feature = "clap", value(alias = "jpg"))]
+    Jpeg,
+    Webp,
+    /// Uncompressed; large files, but almost no encode time.
+    Bmp,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 4] = [
+        ImageFormat::Png,
+        ImageFormat::Jpeg,
+        ImageFormat::Webp,
+        ImageFormat::Bmp,
+    ];
+
+    /// File extension used when saving (no dot).
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Bmp => "bmp",
+        }
+    }
+
+    /// Case-insensitive; accepts both "jpg" and "jpeg".
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "webp" => Some(ImageFormat::Webp),
+            "bmp" => Some(ImageFormat::Bmp),
+            _ => None,
+        }
+    }
+}
@@ -1,16 +1,20 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+pub mod action;
 pub mod error;
+pub mod format;
 pub mod job;
 pub mod mode;
 pub mod output;
 pub mod rect;
 pub mod target;
 
-pub use error::CapitError;
+pub use action::PostAction;
+pub use error::{CapitError, ErrorCode};
+pub use format::ImageFormat;
 pub use job::CaptureJob;
 pub use mode::Mode;
-pub use output::OutputInfo;
-pub use rect::Rect;
+pub use output::{desktop_bounds, OutputInfo};
+pub use rect::{DeviceRect, Rect};
 pub use target::Target;
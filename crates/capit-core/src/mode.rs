@@ -13,4 +13,6 @@ pub enum Mode {
     Screen,
     Window,
     Record, // future
+    /// Colour picker: one pixel's colour instead of an image.
+    Pick,
 }
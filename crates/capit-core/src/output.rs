@@ -3,7 +3,9 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::rect::{DeviceRect, Rect};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OutputInfo {
     /// Compositor-provided name when available (wlroots often has this).
     pub name: Option<String>,
@@ -18,4 +20,77 @@ pub struct OutputInfo {
 
     /// Scale factor (e.g. 1, 2). Keep as i32 for simplicity.
     pub scale: i32,
+
+    /// Physical pixels per logical pixel when it isn't a whole number
+    /// (e.g. 1.25, 1.5): the current mode's size over the logical size.
+    /// None when only the integer `scale` is known.
+    pub fractional_scale: Option<f64>,
+
+    /// Stable handle derived from make/model (else the name), e.g.
+    /// "dell-u2720q". Unlike the index it survives restarts and monitors
+    /// being switched off; accepted wherever an output name is.
+    pub id: Option<String>,
+
+    /// Physical size of the panel in millimetres, when the compositor
+    /// reports one (projectors and virtual outputs often report 0).
+    pub physical_width_mm: Option<i32>,
+    pub physical_height_mm: Option<i32>,
+
+    /// Refresh rate of the current mode in mHz (60 Hz = 60000).
+    pub refresh_mhz: Option<i32>,
+
+    /// Manufacturer and model strings from wl_output, e.g. "Dell Inc." and
+    /// "DELL U2720Q".
+    pub make: Option<String>,
+    pub model: Option<String>,
+}
+
+impl OutputInfo {
+    /// Physical pixels per logical pixel: `fractional_scale` if known,
+    /// else the integer `scale` (at least 1).
+    pub fn effective_scale(&self) -> f64 {
+        self.fractional_scale
+            .filter(|s| *s > 0.0)
+            .unwrap_or(self.scale.max(1) as f64)
+    }
+
+    /// Position and logical size as a global-coordinate rect.
+    pub fn logical_rect(&self) -> Rect {
+        Rect { x: self.x, y: self.y, w: self.width, h: self.height }
+    }
+
+    /// This output at its own scale: its native pixels, placed as if every
+    /// output shared that scale. A full-desktop screenshot of a mixed-DPI
+    /// layout uses one scale for all outputs, so crop from it with the
+    /// desktop's scale instead.
+    pub fn device_rect(&self) -> DeviceRect {
+        self.logical_rect().to_device(self.effective_scale())
+    }
+
+    /// Whether `name_or_id` names this output, by connector name or stable id.
+    pub fn matches(&self, name_or_id: &str) -> bool {
+        self.name.as_deref() == Some(name_or_id) || self.id.as_deref() == Some(name_or_id)
+    }
+}
+
+/// Smallest rect covering every output (global coords).
+/// Empty input gives a zero-sized rect at the origin.
+pub fn desktop_bounds(outputs: &[OutputInfo]) -> Rect {
+    if outputs.is_empty() {
+        return Rect { x: 0, y: 0, w: 0, h: 0 };
+    }
+
+    let (min_x, min_y, max_x, max_y) = outputs.iter().fold(
+        (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+        |(min_x, min_y, max_x, max_y), o| {
+            (
+                min_x.min(o.x),
+                min_y.min(o.y),
+                max_x.max(o.x + o.width),
+                max_y.max(o.y + o.height),
+            )
+        },
+    );
+
+    Rect { x: min_x, y: min_y, w: max_x - min_x, h: max_y - min_y }
 }
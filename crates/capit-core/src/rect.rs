@@ -3,10 +3,42 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// An area in logical pixels, in global desktop coordinates: what the
+/// compositor reports, the overlays draw in and the protocol carries.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Rect {
     pub x: i32,
     pub y: i32,
     pub w: i32,
     pub h: i32,
 }
+
+/// An area in device pixels of a captured image. Made from a logical `Rect`
+/// with `Rect::to_device`, so a forgotten scale doesn't type-check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceRect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Rect {
+    /// The same area in device pixels at `scale` device pixels per logical
+    /// pixel. Edges are scaled and rounded separately, so fractional scales
+    /// land on whole pixels and adjacent rects still share an edge.
+    pub fn to_device(&self, scale: f64) -> DeviceRect {
+        let px = |v: i32| (v as f64 * scale).round() as i32;
+        let (x0, y0) = (px(self.x), px(self.y));
+        let (x1, y1) = (px(self.x + self.w), px(self.y + self.h));
+        DeviceRect { x: x0, y: y0, w: x1 - x0, h: y1 - y0 }
+    }
+
+    /// Whether the two areas share at least one pixel.
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+}
@@ -3,6 +3,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::Rect;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Target {
     /// Whole desktop / all outputs (combined space).
@@ -11,9 +13,21 @@ pub enum Target {
     /// Prefer stable output name if available (e.g. "DP-1", "HDMI-A-1").
     OutputName(String),
 
-    /// Fallback when name isn't known; index is whatever the daemon reports.
+    /// Position in the daemon's output list, as numbered by `capit outputs`
+    /// (sorted top to bottom, then left to right).
     OutputIndex(u32),
 
+    /// The output that currently has focus. Wayland doesn't expose it: the
+    /// screen picker preselects the output under the pointer instead, and
+    /// other modes refuse it.
+    FocusedOutput,
+
+    /// The primary output: the one at the desktop origin, else the first reported.
+    PrimaryOutput,
+
+    /// An explicit area in global desktop coordinates (region without the overlay).
+    Rect(Rect),
+
     /// The currently active/focused toplevel window.
     ///
     /// Notes:
@@ -2,44 +2,104 @@
 // License: MIT
 
 use std::collections::VecDeque;
+use std::io;
 use std::os::unix::net::UnixStream;
 use std::path::Path;
+use std::time::Duration;
 
 use crate::error::{IpcError, Result};
-use crate::framing::{read_frame, write_frame};
+use crate::framing::{read_frame, write_frame, DEFAULT_MAX_FRAME};
 use crate::protocol::{Event, IpcHello, Request, Response, Wire, IPC_VERSION};
 
+/// How long a call waits for each reply frame before giving up.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Reply budget for StartCapture (plus its `--delay`): the daemon only
+/// answers once the user is done with the overlay or portal dialog.
+pub const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
 pub struct IpcClient {
     stream: UnixStream,
     max_frame: usize,
     pending_events: VecDeque<Event>,
+    /// None = wait forever.
+    timeout: Option<Duration>,
 }
 
 impl IpcClient {
     pub fn connect(socket_path: impl AsRef<Path>) -> Result<Self> {
+        Self::connect_timeout(socket_path, DEFAULT_TIMEOUT)
+    }
+
+    /// `connect` with `timeout` (see `set_timeout`) instead of the default,
+    /// the hello included: a busy daemon still accepts connections.
+    pub fn connect_timeout(socket_path: impl AsRef<Path>, timeout: Duration) -> Result<Self> {
         let stream = UnixStream::connect(socket_path)?;
+        stream.set_write_timeout(Some(timeout))?;
         let mut this = Self {
             stream,
-            max_frame: 1024 * 1024,
+            max_frame: DEFAULT_MAX_FRAME,
             pending_events: VecDeque::new(),
+            timeout: Some(timeout),
         };
 
         let resp = this.call(Request::Hello(IpcHello { version: IPC_VERSION }))?;
         match resp {
             Response::Ok => Ok(this),
-            Response::Error { message } => Err(IpcError::Remote(message)),
+            Response::Error { message, .. } => Err(IpcError::Remote(message)),
             _ => Err(IpcError::Remote("unexpected hello response".into())),
         }
     }
 
+    /// `connect`, retrying up to `attempts` times with `delay` in between
+    /// while the daemon is still starting up: the socket doesn't exist yet
+    /// (NotFound) or isn't accepting yet (ConnectionRefused). Any other
+    /// error, and the last attempt's error, is returned as-is.
+    pub fn connect_with_retry(
+        socket_path: impl AsRef<Path>,
+        attempts: u32,
+        delay: Duration,
+    ) -> Result<Self> {
+        let socket_path = socket_path.as_ref();
+        let mut attempt = 1;
+        loop {
+            match Self::connect(socket_path) {
+                Err(IpcError::Io(e)) if attempt < attempts && daemon_not_ready(&e) => {
+                    attempt += 1;
+                    std::thread::sleep(delay);
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// Largest frame this client will accept from the daemon.
+    pub fn set_max_frame(&mut self, max_frame: usize) {
+        self.max_frame = max_frame;
+    }
+
+    /// How long to wait for each reply frame; None waits forever.
+    /// StartCapture calls get at least `CAPTURE_TIMEOUT` regardless.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
     pub fn call(&mut self, req: Request) -> Result<Response> {
+        self.call_with_events(req, |_| {})
+    }
+
+    /// Like `call`, but `on_event` also sees each event as it arrives, for
+    /// live progress (e.g. countdowns). Events are still queued for `next_event`.
+    pub fn call_with_events(&mut self, req: Request, mut on_event: impl FnMut(&Event)) -> Result<Response> {
+        let timeout = self.timeout_for(&req);
         let bytes = postcard::to_allocvec(&req)?;
         write_frame(&mut self.stream, &bytes)?;
 
         loop {
-            match self.recv_wire()? {
+            match self.recv_wire(timeout)? {
                 Wire::Response(resp) => return Ok(resp),
                 Wire::Event(ev) => {
+                    on_event(&ev);
                     self.pending_events.push_back(ev);
                     continue;
                 }
@@ -47,13 +107,18 @@ impl IpcClient {
         }
     }
 
+    /// Drop queued events, e.g. the leftovers of a call that ended early.
+    pub fn clear_events(&mut self) {
+        self.pending_events.clear();
+    }
+
     pub fn next_event(&mut self) -> Result<Event> {
         if let Some(ev) = self.pending_events.pop_front() {
             return Ok(ev);
         }
 
         loop {
-            match self.recv_wire()? {
+            match self.recv_wire(self.timeout)? {
                 Wire::Event(ev) => return Ok(ev),
                 Wire::Response(_) => {
                     continue;
@@ -62,9 +127,36 @@ impl IpcClient {
         }
     }
 
-    fn recv_wire(&mut self) -> Result<Wire> {
-        let bytes = read_frame(&mut self.stream, self.max_frame)?;
-        let msg: Wire = postcard::from_bytes(&bytes)?;
-        Ok(msg)
+    fn timeout_for(&self, req: &Request) -> Option<Duration> {
+        match req {
+            Request::StartCapture { options, .. } => {
+                let capture = CAPTURE_TIMEOUT + Duration::from_secs(options.delay_secs as u64);
+                self.timeout.map(|t| t.max(capture))
+            }
+            _ => self.timeout,
+        }
+    }
+
+    fn recv_wire(&mut self, timeout: Option<Duration>) -> Result<Wire> {
+        self.stream.set_read_timeout(timeout)?;
+        let bytes = match read_frame(&mut self.stream, self.max_frame) {
+            Err(IpcError::Io(e)) if is_timeout(&e) => {
+                return Err(IpcError::Timeout { after: timeout.unwrap_or_default() });
+            }
+            other => other?,
+        };
+        postcard::from_bytes(&bytes).map_err(|source| IpcError::Decode {
+            peer: "daemon",
+            len: bytes.len(),
+            source,
+        })
     }
 }
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+fn daemon_not_ready(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused)
+}
@@ -13,12 +13,29 @@ pub enum IpcError {
     #[error("serialization error: {0}")]
     Ser(#[from] postcard::Error),
 
+    /// A frame arrived intact but doesn't match our message types: almost
+    /// always capit and capitd built from different versions.
+    #[error(
+        "failed to decode {len}-byte frame from {peer} ({source}); \
+         client/daemon protocol mismatch, restart the daemon after upgrading"
+    )]
+    Decode {
+        peer: &'static str,
+        len: usize,
+        #[source]
+        source: postcard::Error,
+    },
+
     #[error("frame too large")]
     FrameTooLarge,
 
     #[error("version mismatch (client {client}, server {server})")]
     VersionMismatch { client: u32, server: u32 },
 
+    /// No reply within the client's timeout: the daemon is stuck or gone.
+    #[error("daemon stopped responding (no reply within {}s)", after.as_secs())]
+    Timeout { after: std::time::Duration },
+
     #[error("daemon returned error: {0}")]
     Remote(String),
 }
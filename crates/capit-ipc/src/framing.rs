@@ -5,6 +5,9 @@ use std::io::{Read, Write};
 
 use crate::error::{IpcError, Result};
 
+/// Default upper bound for a single frame, both directions.
+pub const DEFAULT_MAX_FRAME: usize = 1024 * 1024;
+
 pub fn write_frame<W: Write>(mut w: W, bytes: &[u8]) -> Result<()> {
     let len: u32 = bytes
         .len()
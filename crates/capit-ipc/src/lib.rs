@@ -6,8 +6,11 @@ pub mod framing;
 pub mod client;
 pub mod server;
 pub mod error;
+pub mod paths;
 
-pub use protocol::{Request, Response, Event, IpcHello, IPC_VERSION};
+pub use protocol::{
+    Request, Response, Event, IpcHello, CaptureOptions, CaptureSpec, ScheduledCapture, IPC_VERSION,
+};
 pub use client::IpcClient;
 pub use server::{IpcServer, ClientConn};
 pub use error::{IpcError, Result};
@@ -0,0 +1,80 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Socket and lock locations shared by capitd, capit and capit-bar so they
+// always agree.
+
+use std::path::{Path, PathBuf};
+
+/// Runtime dir for IPC files (socket + lock).
+/// Prefers XDG_RUNTIME_DIR, falls back to /tmp.
+pub fn runtime_ipc_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("capit")
+}
+
+/// Default socket path for the current Wayland session:
+///   $XDG_RUNTIME_DIR/capit/<session>/capit.sock
+///
+/// `<session>` is derived from $WAYLAND_DISPLAY, so nested compositors or
+/// multi-seat setups each get their own daemon (and lock) instead of one
+/// daemon answering clients from another session. Without WAYLAND_DISPLAY
+/// this is just $XDG_RUNTIME_DIR/capit/capit.sock.
+pub fn default_socket_path() -> PathBuf {
+    let dir = runtime_ipc_dir();
+    match session_key() {
+        Some(key) => dir.join(key).join("capit.sock"),
+        None => dir.join("capit.sock"),
+    }
+}
+
+/// capitd's single-instance lock, next to its socket.
+pub fn lock_path_for_socket(sock: &Path) -> Option<PathBuf> {
+    sock.parent().map(|dir| dir.join("capitd.lock"))
+}
+
+/// The PID capitd wrote into its lock file (`pid=N`).
+pub fn lock_pid(lock_path: &Path) -> Option<u32> {
+    let s = std::fs::read_to_string(lock_path).ok()?;
+    s.lines()
+        .find_map(|l| l.strip_prefix("pid="))
+        .and_then(|v| v.trim().parse::<u32>().ok())
+}
+
+/// Whether `pid` is a running process. None without /proc, where we can't
+/// check reliably.
+pub fn pid_alive(pid: u32) -> Option<bool> {
+    let proc = Path::new("/proc");
+    if !proc.exists() {
+        return None;
+    }
+    Some(proc.join(pid.to_string()).exists())
+}
+
+/// True when the lock names a PID that is no longer running. Unreadable
+/// locks, and systems without /proc, count as not stale.
+pub fn is_lock_stale(lock_path: &Path) -> bool {
+    lock_pid(lock_path).and_then(pid_alive) == Some(false)
+}
+
+/// Short, stable key for the current Wayland display (e.g. "wl-3f2a9c10").
+pub fn session_key() -> Option<String> {
+    let display = std::env::var("WAYLAND_DISPLAY").ok()?;
+    if display.is_empty() {
+        return None;
+    }
+    Some(format!("wl-{:08x}", fnv1a(display.as_bytes())))
+}
+
+// FNV-1a: tiny and, unlike std's hasher, stable across builds/Rust versions,
+// which matters because daemon and clients may be built separately.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut h: u32 = 0x811c_9dc5;
+    for b in bytes {
+        h ^= *b as u32;
+        h = h.wrapping_mul(0x0100_0193);
+    }
+    h
+}
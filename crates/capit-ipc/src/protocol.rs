@@ -1,11 +1,13 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
-use capit_core::{Mode, OutputInfo, Rect, Target};
+use capit_core::{ErrorCode, ImageFormat, Mode, OutputInfo, PostAction, Rect, Target};
 
-pub const IPC_VERSION: u32 = 3;
+pub const IPC_VERSION: u32 = 39;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Wire {
@@ -25,6 +27,104 @@ pub struct UiConfig {
     pub accent_colour: u32,
 
     pub bar_background_colour: u32,
+
+    /// Extra bar margins in pixels: top, right, bottom, left.
+    pub bar_margins: [i32; 4],
+
+    /// Whether the daemon can capture windows; the bar disables the slot otherwise.
+    pub window_supported: bool,
+}
+
+/// Per-capture overrides sent with StartCapture (defaults defer to daemon config).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CaptureOptions {
+    /// What to do with the capture, over `capit.default_action`.
+    pub action: Option<PostAction>,
+
+    /// Save into this (absolute) directory for this capture only, over config/env.
+    pub output_dir: Option<PathBuf>,
+
+    /// Save to exactly this (absolute) path, replacing any file there; the
+    /// extension picks the format. An existing directory acts as `output_dir`.
+    pub output_file: Option<PathBuf>,
+
+    /// Send the image back as `CaptureChunk` events instead of keeping a
+    /// file (`--output-file -`). Single format; no clipboard, sidecar,
+    /// wallpaper or notification.
+    pub to_stdout: bool,
+
+    /// Save the same capture once per format (same name, different extension).
+    /// The first is the primary file (notifications, clipboard).
+    /// Empty = the daemon's `capit.screenshot_format` (PNG by default).
+    pub formats: Vec<ImageFormat>,
+
+    /// Wait this many seconds between selection and the screenshot (0 = none).
+    pub delay_secs: u32,
+
+    /// Region only: capture this rect instead of showing the overlay.
+    /// Relative to the target output's origin when a target is given,
+    /// global desktop coordinates otherwise.
+    pub geometry: Option<Rect>,
+
+    /// Region only: capture the last region confirmed in the overlay again,
+    /// without showing it.
+    pub repeat_last: bool,
+
+    /// Overlay accent colour (ARGB) for this capture, over the daemon config.
+    pub accent_colour: Option<u32>,
+
+    /// Overlay dim alpha (0 = no dimming, 255 = black) for this capture.
+    pub dim_alpha: Option<u8>,
+
+    /// Set the saved capture as the desktop wallpaper (wallpaper portal).
+    pub as_wallpaper: bool,
+
+    /// Screen only: save every output as a labelled grid of thumbnails
+    /// instead of showing the picker.
+    pub contact_sheet: bool,
+
+    /// Screen only: skip capit's picker and take an interactive portal
+    /// screenshot, saving the area the user chooses in the portal's own UI
+    /// (for desktops without wlr-layer-shell, e.g. GNOME).
+    pub portal_interactive: bool,
+
+    /// Screen only: save each (non-excluded) output to its own file, named
+    /// after the output, instead of showing the picker.
+    pub per_output: bool,
+}
+
+impl CaptureOptions {
+    /// Requested formats with duplicates removed, defaulting to PNG.
+    /// The daemon sends one `CaptureFinished` per entry, in this order
+    /// (with no formats requested it sends one, in its configured format).
+    pub fn resolved_formats(&self) -> Vec<ImageFormat> {
+        let mut out: Vec<ImageFormat> = Vec::new();
+        for f in &self.formats {
+            if !out.contains(f) {
+                out.push(*f);
+            }
+        }
+        if out.is_empty() {
+            out.push(ImageFormat::Png);
+        }
+        out
+    }
+}
+
+/// What to capture, detached from when (used by scheduled captures).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureSpec {
+    pub mode: Mode,
+    pub target: Option<Target>,
+    pub options: CaptureOptions,
+}
+
+/// A capture the daemon will start on its own at `at_unix` (seconds, UTC).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledCapture {
+    pub id: u64,
+    pub at_unix: i64,
+    pub spec: CaptureSpec,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +138,12 @@ pub enum Request {
     /// CLI/bar uses this to decide bar styling.
     GetUiConfig,
 
+    /// Ask for the daemon's build version and protocol version.
+    GetVersion,
+
+    /// Ask where the daemon listens, logs and saves captures.
+    GetPaths,
+
     StartCapture {
         mode: Mode,
 
@@ -46,6 +152,8 @@ pub enum Request {
 
         /// Lets daemon know if an interactive UI session is active.
         with_ui: bool,
+
+        options: CaptureOptions,
     },
 
     /// UI → daemon: send the currently selected rectangle (global coords).
@@ -57,14 +165,40 @@ pub enum Request {
 
     Cancel,
     Status,
+
+    /// Liveness check: answered straight away with `Pong`.
+    Ping,
+
+    /// Debugging: daemon returns `payload` unchanged (round-trip timing, framing).
+    Echo { payload: Vec<u8> },
+
+    /// Have the daemon start `spec` at `at_unix`, without a client attached.
+    ScheduleCapture { at_unix: i64, spec: CaptureSpec },
+
+    /// List pending scheduled captures (soonest first).
+    ListScheduled,
+
+    /// Drop a pending scheduled capture.
+    CancelScheduled { id: u64 },
+
+    /// Write the image currently on the clipboard to `path` (absolute).
+    SaveClipboard { path: PathBuf },
+
+    /// Ask which capture modes and output formats this daemon can actually serve.
+    GetCapabilities,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Response {
     Ok,
 
-    /// Response to ListOutputs.
-    Outputs { outputs: Vec<OutputInfo> },
+    /// Response to ListOutputs. `desktop_bounds` covers all outputs (global coords).
+    Outputs {
+        outputs: Vec<OutputInfo>,
+        desktop_bounds: Rect,
+        /// Names skipped by all-screens captures (`capit.exclude_outputs`).
+        excluded: Vec<String>,
+    },
 
     /// Response to GetUiConfig.
     UiConfig { cfg: UiConfig },
@@ -72,20 +206,105 @@ pub enum Response {
     Status {
         running: bool,
         active_job: Option<Mode>,
+        /// Path of the most recent successful capture.
+        last_saved: Option<String>,
+        /// Message of the most recent failed capture (cancels don't count).
+        last_error: Option<String>,
+        captures_this_session: u64,
+    },
+
+    /// Response to Ping.
+    Pong { uptime_secs: u64 },
+
+    /// Response to a `Mode::Pick` StartCapture: the picked pixel as
+    /// 0xRRGGBB and as "#RRGGBB".
+    Colour { rgb: u32, hex: String },
+
+    /// `code` is for scripts, `message` for humans.
+    Error { code: ErrorCode, message: String },
+
+    /// Response to GetVersion.
+    Version {
+        daemon_version: String,
+        ipc_version: u32,
     },
 
-    Error { message: String },
+    /// Response to GetPaths. `output_dir` is where a capture without
+    /// `--output-directory` would be saved right now ($CAPIT_DIR, config, ...).
+    Paths {
+        socket: String,
+        log: String,
+        output_dir: String,
+    },
+
+    /// Response to Echo.
+    Echo { payload: Vec<u8> },
+
+    /// Response to ScheduleCapture.
+    Scheduled { id: u64, at_unix: i64 },
+
+    /// Response to ListScheduled.
+    ScheduledList { jobs: Vec<ScheduledCapture> },
+
+    /// Response to SaveClipboard: what was written, as offered by the clipboard owner.
+    ClipboardSaved { path: String, mime: String, bytes: u64 },
+
+    /// Response to GetCapabilities.
+    Capabilities {
+        window_capture: bool,
+        record: bool,
+        /// File extensions the daemon can encode (e.g. "png").
+        formats: Vec<String>,
+    },
 }
 
 /// Daemon → client async notifications.
 /// CLI can mostly ignore these; UI will use them heavily.
+///
+/// Events can arrive at any time, also between requests; `IpcClient`
+/// queues them for `next_event`. Clients should skip the ones they don't
+/// handle rather than treat them as errors.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Event {
     CaptureStarted { mode: Mode },
-    CaptureFinished { path: String },
+    /// The capture was written to `path`. Dimensions are in image pixels,
+    /// `bytes` is the encoded file size and `format` its extension (e.g. "png").
+    /// `on_disk` is false when the action was `Copy`: the file is gone and
+    /// the image is on the clipboard.
+    CaptureFinished {
+        path: String,
+        width: u32,
+        height: u32,
+        bytes: u64,
+        mode: Mode,
+        format: String,
+        on_disk: bool,
+    },
     CaptureFailed { message: String },
 
+    /// `--as-wallpaper`: the portal accepted `path` as the new wallpaper.
+    /// Sent before the capture's `CaptureFinished` events.
+    WallpaperSet { path: String },
+
+    /// `to_stdout`: the next piece of the encoded image, in order. Followed by
+    /// a `CaptureFinished` whose `path` is "-". Chunks stay well under the
+    /// frame limit, so images of any size fit.
+    CaptureChunk { data: Vec<u8> },
+
+    /// Sent once per second while a `delay_secs` countdown runs (`remaining` > 0).
+    CaptureCountdown { remaining: u32 },
+
     /// Daemon → UI: preview rectangle accepted/normalized (or echoed back).
     /// Useful if daemon snaps/clamps to outputs.
     SelectionPreview { rect: Rect },
+
+    /// The output layout changed (monitor plugged, unplugged or
+    /// reconfigured): the full new list, as `ListOutputs` reports it.
+    /// Pushed unprompted to the connected client, never mid-request.
+    OutputsChanged { outputs: Vec<OutputInfo> },
+
+    /// `per_output`: every output's `CaptureFinished` events have been
+    /// sent. Their number depends on the outputs, so clients wait for this
+    /// instead of counting.
+    CaptureBatchFinished,
 }
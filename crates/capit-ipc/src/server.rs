@@ -2,11 +2,15 @@
 // License: MIT
 
 use std::fs;
+use std::io::{self, Read};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use capit_core::ErrorCode;
 
 use crate::error::{IpcError, Result};
-use crate::framing::{read_frame, write_frame};
+use crate::framing::{read_frame, write_frame, DEFAULT_MAX_FRAME};
 use crate::protocol::{Event, Request, Response, Wire, IPC_VERSION};
 
 pub struct IpcServer {
@@ -31,7 +35,7 @@ impl IpcServer {
         Ok(Self {
             listener,
             socket_path,
-            max_frame: 1024 * 1024,
+            max_frame: DEFAULT_MAX_FRAME,
         })
     }
 
@@ -43,6 +47,11 @@ impl IpcServer {
         })
     }
 
+    /// Largest request frame accepted from clients (applies to new connections).
+    pub fn set_max_frame(&mut self, max_frame: usize) {
+        self.max_frame = max_frame;
+    }
+
     pub fn socket_path(&self) -> &Path {
         &self.socket_path
     }
@@ -53,11 +62,40 @@ impl IpcServer {
     }
 }
 
+fn decode_request(bytes: &[u8]) -> Result<Request> {
+    postcard::from_bytes(bytes).map_err(|source| IpcError::Decode {
+        peer: "client",
+        len: bytes.len(),
+        source,
+    })
+}
+
 impl ClientConn {
     pub fn recv(&mut self) -> Result<Request> {
         let bytes = read_frame(&mut self.stream, self.max_frame)?;
-        let req: Request = postcard::from_bytes(&bytes)?;
-        Ok(req)
+        decode_request(&bytes)
+    }
+
+    /// Wait up to `timeout` for the next request; `Ok(None)` if none arrived.
+    /// A closed connection is an error, as with `recv`.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<Request>> {
+        // Only the first byte is read under the timeout; once a frame has
+        // started, the rest is read blocking so it can't be left half-read.
+        self.stream
+            .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        let mut first = [0u8; 1];
+        let got = self.stream.read(&mut first);
+        self.stream.set_read_timeout(None)?;
+
+        match got {
+            Ok(0) => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(_) => {
+                let bytes = read_frame(first.as_slice().chain(&mut self.stream), self.max_frame)?;
+                decode_request(&bytes).map(Some)
+            }
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
     pub fn send(&mut self, resp: Response) -> Result<()> {
@@ -80,6 +118,7 @@ impl ClientConn {
                 server: IPC_VERSION,
             }),
             _ => self.send(Response::Error {
+                code: ErrorCode::InvalidRequest,
                 message: "expected hello".into(),
             }),
         }
@@ -0,0 +1,46 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// List outputs, then capture the first one by name without blocking main.
+//
+//     cargo run -p capit-lib --example outputs
+
+use std::time::Duration;
+
+use capit_lib::{Capit, CaptureOptions, Mode, PostAction, Target};
+
+fn main() -> Result<(), capit_lib::CaptureError> {
+    let mut capit = Capit::connect()?;
+
+    let outputs = capit.list_outputs()?;
+    for o in &outputs {
+        let name = o.name.as_deref().unwrap_or("?");
+        println!("{name}: {}x{} at {},{}", o.width, o.height, o.x, o.y);
+    }
+
+    let target = match outputs.first() {
+        Some(o) => match &o.name {
+            Some(name) => Target::OutputName(name.clone()),
+            None => Target::OutputIndex(0),
+        },
+        None => {
+            println!("no outputs");
+            return Ok(());
+        }
+    };
+
+    let handle = capit.spawn_capture(
+        Mode::Screen,
+        Some(target),
+        CaptureOptions { action: Some(PostAction::SaveCopy), ..Default::default() },
+    );
+
+    while !handle.is_finished() {
+        // ...your app keeps doing its own work here...
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let shot = handle.wait()?;
+    println!("saved {} and copied it to the clipboard", shot.path.display());
+    Ok(())
+}
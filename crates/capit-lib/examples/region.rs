@@ -0,0 +1,23 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Capture a region and print where it went.
+//
+//     cargo run -p capit-lib --example region
+
+fn main() {
+    match capit_lib::capture_region() {
+        Ok(shot) => println!(
+            "saved {} ({}x{}, {} bytes)",
+            shot.path.display(),
+            shot.width,
+            shot.height,
+            shot.bytes
+        ),
+        Err(e) if e.is_cancelled() => println!("cancelled"),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+
+use capit_core::{Mode, OutputInfo, Target};
+use capit_ipc::paths::default_socket_path;
+use capit_ipc::{CaptureOptions, Event, IpcClient, Request, Response};
+
+use crate::error::{CaptureError, Result};
+use crate::result::CaptureResult;
+
+/// A connection to capitd.
+///
+/// Captures block until the daemon reports the result; use
+/// [`Capit::spawn_capture`] to keep your own thread (or event loop) free.
+pub struct Capit {
+    client: IpcClient,
+    socket: PathBuf,
+}
+
+impl Capit {
+    /// Connect to the daemon for the current Wayland session.
+    pub fn connect() -> Result<Self> {
+        Self::connect_to(default_socket_path())
+    }
+
+    /// Connect to the daemon listening on `socket`.
+    pub fn connect_to(socket: impl AsRef<Path>) -> Result<Self> {
+        let socket = socket.as_ref().to_path_buf();
+        let client = IpcClient::connect(&socket).map_err(|source| CaptureError::Connect {
+            path: socket.clone(),
+            source,
+        })?;
+        Ok(Self { client, socket })
+    }
+
+    /// Socket this connection was opened on.
+    pub fn socket_path(&self) -> &Path {
+        &self.socket
+    }
+
+    pub fn list_outputs(&mut self) -> Result<Vec<OutputInfo>> {
+        match self.client.call(Request::ListOutputs)? {
+            Response::Outputs { outputs, .. } => Ok(outputs),
+            Response::Error { code, message } => Err(CaptureError::from_response(code, message)),
+            other => Err(CaptureError::Unexpected(format!("{other:?}"))),
+        }
+    }
+
+    pub fn capture_region(&mut self) -> Result<CaptureResult> {
+        self.capture(Mode::Region, None, CaptureOptions::default())
+    }
+
+    /// `None` uses the daemon's `capit.screen_default`.
+    pub fn capture_screen(&mut self, target: Option<Target>) -> Result<CaptureResult> {
+        self.capture(Mode::Screen, target, CaptureOptions::default())
+    }
+
+    pub fn capture_window(&mut self) -> Result<CaptureResult> {
+        self.capture(Mode::Window, None, CaptureOptions::default())
+    }
+
+    /// Start a capture and wait for it to finish, fail or be cancelled.
+    /// With `per_output`, the first output's file is the result and the
+    /// rest follow in `others`.
+    pub fn capture(
+        &mut self,
+        mode: Mode,
+        target: Option<Target>,
+        options: CaptureOptions,
+    ) -> Result<CaptureResult> {
+        if options.to_stdout {
+            return Err(CaptureError::Unsupported("to_stdout; use output_file instead".into()));
+        }
+
+        // per_output: one file per output, ended by CaptureBatchFinished.
+        let expected = if options.per_output { usize::MAX } else { options.resolved_formats().len() };
+        let mut files: Vec<CaptureResult> = Vec::new();
+
+        // Events left over from an earlier call that ended early belong to it.
+        self.client.clear_events();
+        let resp = self.client.call(Request::StartCapture {
+            mode,
+            target,
+            with_ui: false,
+            options,
+        })?;
+
+        let result = self.await_capture(resp, expected, &mut files);
+        if result.is_err() {
+            self.client.clear_events();
+        }
+        result
+    }
+
+    fn await_capture(
+        &mut self,
+        resp: Response,
+        expected: usize,
+        files: &mut Vec<CaptureResult>,
+    ) -> Result<CaptureResult> {
+        match resp {
+            Response::Ok => {}
+            Response::Error { code, message } => return Err(CaptureError::from_response(code, message)),
+            other => return Err(CaptureError::Unexpected(format!("{other:?}"))),
+        }
+
+        loop {
+            match self.client.next_event()? {
+                Event::CaptureFinished { path, width, height, bytes, mode, format, on_disk } => {
+                    files.push(CaptureResult {
+                        path: PathBuf::from(path),
+                        width,
+                        height,
+                        bytes,
+                        mode,
+                        format,
+                        on_disk,
+                        others: Vec::new(),
+                    });
+                    // One event per format, primary first.
+                    if files.len() >= expected {
+                        return Ok(primary_with_others(std::mem::take(files)));
+                    }
+                }
+                Event::CaptureBatchFinished if !files.is_empty() => {
+                    return Ok(primary_with_others(std::mem::take(files)));
+                }
+                Event::CaptureBatchFinished => {
+                    return Err(CaptureError::Failed { code: None, message: "no output was captured".into() });
+                }
+                // The daemon reports user cancels as a failure with this message.
+                Event::CaptureFailed { message } if message == "cancelled" => {
+                    return Err(CaptureError::Cancelled);
+                }
+                Event::CaptureFailed { message } => return Err(CaptureError::Failed { code: None, message }),
+                _ => {}
+            }
+        }
+    }
+
+    /// Run a capture on a background thread over a fresh connection.
+    pub fn spawn_capture(
+        &self,
+        mode: Mode,
+        target: Option<Target>,
+        options: CaptureOptions,
+    ) -> CaptureHandle {
+        let socket = self.socket.clone();
+        let join = std::thread::spawn(move || {
+            Capit::connect_to(&socket)?.capture(mode, target, options)
+        });
+        CaptureHandle { join }
+    }
+}
+
+/// The first file, carrying the rest in `others`.
+fn primary_with_others(mut files: Vec<CaptureResult>) -> CaptureResult {
+    let mut primary = files.remove(0);
+    primary.others = files;
+    primary
+}
+
+/// A capture running on a background thread (see [`Capit::spawn_capture`]).
+pub struct CaptureHandle {
+    join: JoinHandle<Result<CaptureResult>>,
+}
+
+impl CaptureHandle {
+    /// True once the result is ready; `wait` won't block after this.
+    pub fn is_finished(&self) -> bool {
+        self.join.is_finished()
+    }
+
+    pub fn wait(self) -> Result<CaptureResult> {
+        match self.join.join() {
+            Ok(res) => res,
+            Err(_) => Err(CaptureError::Failed { code: None, message: "capture thread panicked".into() }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use capit_core::ErrorCode;
+    use capit_ipc::IpcServer;
+
+    /// A daemon that fails the first capture (event, then error reply) and
+    /// completes the second.
+    fn fake_daemon(socket: &Path) -> std::thread::JoinHandle<()> {
+        let server = IpcServer::bind(socket).unwrap();
+        std::thread::spawn(move || {
+            let mut conn = server.accept().unwrap();
+            let hello = conn.recv().unwrap();
+            conn.handle_hello(&hello).unwrap();
+
+            assert!(matches!(conn.recv().unwrap(), Request::StartCapture { .. }));
+            conn.send_event(Event::CaptureFailed { message: "disk full".into() }).unwrap();
+            conn.send(Response::Error { code: ErrorCode::Storage, message: "disk full".into() }).unwrap();
+
+            assert!(matches!(conn.recv().unwrap(), Request::StartCapture { .. }));
+            conn.send_event(Event::CaptureFinished {
+                path: "/tmp/capit-1.png".into(),
+                width: 4,
+                height: 3,
+                bytes: 42,
+                mode: Mode::Screen,
+                format: "png".into(),
+                on_disk: true,
+            })
+            .unwrap();
+            conn.send(Response::Ok).unwrap();
+        })
+    }
+
+    #[test]
+    fn a_failed_capture_does_not_leak_into_the_next() {
+        let socket = std::env::temp_dir().join(format!("capit-lib-test-{}.sock", std::process::id()));
+        let daemon = fake_daemon(&socket);
+        let mut capit = Capit::connect_to(&socket).unwrap();
+
+        let err = capit.capture_screen(None).unwrap_err();
+        assert!(matches!(err, CaptureError::Failed { code: Some(ErrorCode::Storage), .. }), "{err:?}");
+
+        let shot = capit.capture_screen(None).unwrap();
+        assert_eq!(shot.path, PathBuf::from("/tmp/capit-1.png"));
+        assert_eq!((shot.width, shot.height), (4, 3));
+
+        daemon.join().unwrap();
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[test]
+    fn to_stdout_is_refused_up_front() {
+        let options = CaptureOptions { to_stdout: true, ..CaptureOptions::default() };
+        // Refused before StartCapture is sent: the daemon only ever sees the hello.
+        let socket = std::env::temp_dir().join(format!("capit-lib-test-stdout-{}.sock", std::process::id()));
+        let server = IpcServer::bind(&socket).unwrap();
+        let accept = std::thread::spawn(move || {
+            let mut conn = server.accept().unwrap();
+            let hello = conn.recv().unwrap();
+            conn.handle_hello(&hello).unwrap();
+        });
+        let mut capit = Capit::connect_to(&socket).unwrap();
+        let err = capit.capture(Mode::Screen, None, options).unwrap_err();
+        assert!(matches!(err, CaptureError::Unsupported(_)), "{err:?}");
+
+        accept.join().unwrap();
+        let _ = std::fs::remove_file(&socket);
+    }
+}
@@ -0,0 +1,75 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use std::path::PathBuf;
+
+use capit_core::ErrorCode;
+use capit_ipc::IpcError;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, CaptureError>;
+
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    /// capitd isn't running (or isn't listening where we looked).
+    #[error("cannot connect to capitd at {}: {source}", path.display())]
+    Connect {
+        path: PathBuf,
+        #[source]
+        source: IpcError,
+    },
+
+    /// The connection broke or a message couldn't be encoded/decoded.
+    #[error(transparent)]
+    Ipc(#[from] IpcError),
+
+    /// The user dismissed the overlay (Esc, right click, focus loss).
+    #[error("capture cancelled")]
+    Cancelled,
+
+    /// The capture couldn't be completed (no outputs, portal denied, disk
+    /// full...). `code` is None when the daemon reported it mid-capture.
+    #[error("capture failed: {message}")]
+    Failed { code: Option<ErrorCode>, message: String },
+
+    /// The daemon refused the request itself: busy, bad arguments, unknown
+    /// output or an unsupported mode.
+    #[error("daemon refused request: {message}")]
+    Rejected { code: ErrorCode, message: String },
+
+    /// The options ask for something this library can't hand back
+    /// (e.g. `to_stdout`, whose image only the CLI streams).
+    #[error("not supported by capit-lib: {0}")]
+    Unsupported(String),
+
+    #[error("unexpected response from daemon: {0}")]
+    Unexpected(String),
+}
+
+impl CaptureError {
+    /// True when nothing went wrong; the user just backed out.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, CaptureError::Cancelled)
+    }
+
+    /// The daemon's machine-readable reason, when it gave one.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            CaptureError::Cancelled => Some(ErrorCode::Cancelled),
+            CaptureError::Failed { code, .. } => *code,
+            CaptureError::Rejected { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// A `Response::Error`, by its code.
+    pub(crate) fn from_response(code: ErrorCode, message: String) -> Self {
+        match code {
+            ErrorCode::Cancelled => CaptureError::Cancelled,
+            ErrorCode::Busy | ErrorCode::InvalidRequest | ErrorCode::UnknownOutput | ErrorCode::NotImplemented => {
+                CaptureError::Rejected { code, message }
+            }
+            _ => CaptureError::Failed { code: Some(code), message },
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Embeddable capit: drive a running capitd from another Rust application.
+//
+//     let shot = capit_lib::capture_region()?;
+//     println!("saved {} ({}x{})", shot.path.display(), shot.width, shot.height);
+//
+// The free functions connect to the daemon of the current Wayland session
+// each time; keep a `Capit` around to reuse one connection.
+
+mod client;
+mod error;
+mod result;
+
+pub use capit_core::{desktop_bounds, ErrorCode, ImageFormat, Mode, OutputInfo, PostAction, Rect, Target};
+pub use capit_ipc::CaptureOptions;
+
+pub use client::{Capit, CaptureHandle};
+pub use error::{CaptureError, Result};
+pub use result::CaptureResult;
+
+/// Let the user drag out a region and capture it.
+pub fn capture_region() -> Result<CaptureResult> {
+    Capit::connect()?.capture_region()
+}
+
+/// Capture a whole screen. `None` uses the daemon's `capit.screen_default`.
+pub fn capture_screen(target: Option<Target>) -> Result<CaptureResult> {
+    Capit::connect()?.capture_screen(target)
+}
+
+/// Let the user pick a window and capture it.
+pub fn capture_window() -> Result<CaptureResult> {
+    Capit::connect()?.capture_window()
+}
+
+/// Outputs (monitors) as the daemon sees them, in global coordinates.
+pub fn list_outputs() -> Result<Vec<OutputInfo>> {
+    Capit::connect()?.list_outputs()
+}
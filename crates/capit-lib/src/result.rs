@@ -0,0 +1,25 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use std::path::PathBuf;
+
+use capit_core::Mode;
+
+/// A capture the daemon has written to disk (or, with `PostAction::Copy`,
+/// to the clipboard: then `on_disk` is false and `path` no longer exists).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureResult {
+    pub path: PathBuf,
+    /// Image size in pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Encoded file size.
+    pub bytes: u64,
+    pub mode: Mode,
+    /// File extension of the encoded image (e.g. "png").
+    pub format: String,
+    pub on_disk: bool,
+    /// The same capture saved in the other requested formats
+    /// (`CaptureOptions::formats` after the first).
+    pub others: Vec<CaptureResult>,
+}
@@ -0,0 +1,246 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// The wl_shm buffer shared by capit-bar and capitd's overlays.
+
+use std::fs::File;
+use std::os::fd::AsFd;
+
+use memmap2::MmapMut;
+use tempfile::tempfile;
+
+use wayland_client::protocol::{wl_buffer, wl_shm, wl_shm_pool};
+use wayland_client::{Dispatch, QueueHandle};
+
+/// Pool memory for one buffer: the file, its mapping and the wl_shm_pool.
+struct Pool {
+    file: File,
+    mmap: MmapMut,
+    pool: wl_shm_pool::WlShmPool,
+    /// Bytes backing the pool (only grows).
+    capacity: usize,
+}
+
+impl Pool {
+    fn new<D>(shm: &wl_shm::WlShm, qh: &QueueHandle<D>, size: usize) -> Result<Self, String>
+    where
+        D: Dispatch<wl_shm_pool::WlShmPool, ()> + 'static,
+    {
+        let file = tempfile().map_err(|e| format!("tempfile: {e}"))?;
+        file.set_len(size as u64).map_err(|e| format!("set_len: {e}"))?;
+
+        // SAFETY: the file is private to this process and only ever grown.
+        let mmap = unsafe { MmapMut::map_mut(&file).map_err(|e| format!("mmap: {e}"))? };
+
+        let pool = shm.create_pool(file.as_fd(), size as i32, qh, ());
+        Ok(Self { file, mmap, pool, capacity: size })
+    }
+
+    fn grow(&mut self, size: usize) -> Result<(), String> {
+        let Some(size) = grown_capacity(self.capacity, size) else {
+            return Ok(());
+        };
+        self.file.set_len(size as u64).map_err(|e| format!("set_len: {e}"))?;
+        self.pool.resize(size as i32);
+        // SAFETY: as in `new`.
+        self.mmap = unsafe { MmapMut::map_mut(&self.file).map_err(|e| format!("mmap: {e}"))? };
+        self.capacity = size;
+        Ok(())
+    }
+
+    fn buffer<D>(&self, qh: &QueueHandle<D>, width: i32, height: i32) -> wl_buffer::WlBuffer
+    where
+        D: Dispatch<wl_buffer::WlBuffer, ()> + 'static,
+    {
+        self.pool.create_buffer(0, width, height, width * 4, wl_shm::Format::Argb8888, qh, ())
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.pool.destroy();
+    }
+}
+
+/// A single ARGB8888 buffer backed by a tempfile pool.
+///
+/// The pool is kept across resizes: when a configure asks for a size that
+/// fits in the pool we carve a new wl_buffer out of it, and only grow the
+/// file/mmap when the new size is larger. While the compositor still holds
+/// the current buffer, a resize moves to a fresh pool instead, and the old
+/// buffer and its memory stay alive until the compositor releases it.
+pub struct ShmBuffer {
+    pool: Pool,
+    pub buffer: wl_buffer::WlBuffer,
+    pub width: i32,
+    pub height: i32,
+    /// Attached and not yet released: drawing now would tear.
+    pub busy: bool,
+    /// Buffers replaced while the compositor was reading them, with their pools.
+    retired: Retired<wl_buffer::WlBuffer, Pool>,
+}
+
+impl ShmBuffer {
+    pub fn new<D>(shm: &wl_shm::WlShm, qh: &QueueHandle<D>, width: i32, height: i32) -> Result<Self, String>
+    where
+        D: Dispatch<wl_shm_pool::WlShmPool, ()> + Dispatch<wl_buffer::WlBuffer, ()> + 'static,
+    {
+        let (width, height, size) = buffer_size(width, height);
+        let pool = Pool::new(shm, qh, size)?;
+        let buffer = pool.buffer(qh, width, height);
+        Ok(Self { pool, buffer, width, height, busy: false, retired: Retired::default() })
+    }
+
+    /// Make `slot` hold a buffer of `width`x`height`: resized in place when
+    /// there is one, else newly created. What every configure handler does.
+    pub fn ensure<D>(
+        slot: &mut Option<ShmBuffer>,
+        shm: &wl_shm::WlShm,
+        qh: &QueueHandle<D>,
+        width: i32,
+        height: i32,
+    ) -> Result<(), String>
+    where
+        D: Dispatch<wl_shm_pool::WlShmPool, ()> + Dispatch<wl_buffer::WlBuffer, ()> + 'static,
+    {
+        match slot {
+            Some(buf) => buf.resize(shm, qh, width, height),
+            None => {
+                *slot = Some(ShmBuffer::new(shm, qh, width, height)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Point this buffer at a new size, reusing the pool when it fits and
+    /// the compositor is done with the current buffer.
+    pub fn resize<D>(&mut self, shm: &wl_shm::WlShm, qh: &QueueHandle<D>, width: i32, height: i32) -> Result<(), String>
+    where
+        D: Dispatch<wl_shm_pool::WlShmPool, ()> + Dispatch<wl_buffer::WlBuffer, ()> + 'static,
+    {
+        let (width, height, size) = buffer_size(width, height);
+        if width == self.width && height == self.height {
+            return Ok(());
+        }
+
+        if self.busy {
+            // The compositor may still read the old pixels: leave them be.
+            let pool = std::mem::replace(&mut self.pool, Pool::new(shm, qh, size)?);
+            let buffer = std::mem::replace(&mut self.buffer, self.pool.buffer(qh, width, height));
+            self.retired.push(buffer, pool);
+        } else {
+            self.pool.grow(size)?;
+            self.buffer.destroy();
+            self.buffer = self.pool.buffer(qh, width, height);
+        }
+        self.width = width;
+        self.height = height;
+        self.busy = false;
+
+        Ok(())
+    }
+
+    /// Handle a wl_buffer release. True if `buffer` was one of ours.
+    pub fn release(&mut self, buffer: &wl_buffer::WlBuffer) -> bool {
+        if &self.buffer == buffer {
+            self.busy = false;
+            return true;
+        }
+        match self.retired.take(buffer) {
+            Some((buffer, _pool)) => {
+                buffer.destroy();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pixels for the current size (the pool may be larger).
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        let (_, _, used) = buffer_size(self.width, self.height);
+        &mut self.pool.mmap[..used]
+    }
+}
+
+/// `width`x`height` clamped to at least 1x1, with the bytes ARGB8888 needs.
+fn buffer_size(width: i32, height: i32) -> (i32, i32, usize) {
+    let width = width.max(1);
+    let height = height.max(1);
+    (width, height, width as usize * 4 * height as usize)
+}
+
+/// The capacity a pool needs for `size` bytes, if it has to grow: pools
+/// never shrink, so a smaller buffer reuses the memory already there.
+fn grown_capacity(capacity: usize, size: usize) -> Option<usize> {
+    (size > capacity).then_some(size)
+}
+
+/// Buffers the compositor may still be reading, each kept with its pool
+/// until released.
+struct Retired<B, P>(Vec<(B, P)>);
+
+impl<B, P> Default for Retired<B, P> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<B: PartialEq, P> Retired<B, P> {
+    fn push(&mut self, buffer: B, pool: P) {
+        self.0.push((buffer, pool));
+    }
+
+    /// Stop tracking `buffer`, handing it back with its pool.
+    fn take(&mut self, buffer: &B) -> Option<(B, P)> {
+        let i = self.0.iter().position(|(b, _)| b == buffer)?;
+        Some(self.0.swap_remove(i))
+    }
+}
+
+/// Premultiply a straight 0xAARRGGBB colour by its alpha, as wl_shm's
+/// ARGB8888 expects. Opaque colours come back unchanged.
+pub fn premultiply(argb: u32) -> u32 {
+    let a = argb >> 24;
+    let ch = |shift: u32| ((argb >> shift) & 0xFF) * a / 255;
+    (a << 24) | (ch(16) << 16) | (ch(8) << 8) | ch(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{buffer_size, grown_capacity, premultiply, Retired};
+
+    #[test]
+    fn sizes_are_clamped_and_four_bytes_a_pixel() {
+        assert_eq!(buffer_size(1920, 1080), (1920, 1080, 1920 * 1080 * 4));
+        assert_eq!(buffer_size(0, -5), (1, 1, 4));
+        // Computed in usize, so sizes past i32::MAX bytes don't overflow.
+        assert_eq!(buffer_size(30_000, 20_000).2, 2_400_000_000);
+    }
+
+    #[test]
+    fn pools_only_grow() {
+        assert_eq!(grown_capacity(1000, 4000), Some(4000));
+        assert_eq!(grown_capacity(4000, 1000), None);
+        assert_eq!(grown_capacity(4000, 4000), None);
+    }
+
+    #[test]
+    fn retired_buffers_are_handed_back_once() {
+        let mut retired = Retired::default();
+        retired.push(1, "pool a");
+        retired.push(2, "pool b");
+
+        assert_eq!(retired.take(&2), Some((2, "pool b")));
+        assert_eq!(retired.take(&2), None);
+        assert_eq!(retired.take(&3), None);
+        assert_eq!(retired.take(&1), Some((1, "pool a")));
+        assert!(retired.0.is_empty());
+    }
+
+    #[test]
+    fn premultiply_scales_rgb_by_alpha() {
+        assert_eq!(premultiply(0xFF0A_84FF), 0xFF0A_84FF);
+        assert_eq!(premultiply(0x80FF_0000), 0x8080_0000);
+        assert_eq!(premultiply(0x00FF_FFFF), 0);
+    }
+}
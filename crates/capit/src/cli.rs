@@ -3,14 +3,15 @@
 
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Args as ClapArgs, Parser, Subcommand};
 
-use capit_core::{Mode, Target};
+use capit_core::{ImageFormat, Mode, PostAction, Rect, Target};
+use capit_ipc::CaptureOptions;
 
 #[derive(Debug, Parser)]
 #[command(name = "capit", version, about = "Capit — capture it.")]
 pub struct Args {
-    /// Override IPC socket path (default: $XDG_RUNTIME_DIR/capit.sock)
+    /// Override IPC socket path (default: $XDG_RUNTIME_DIR/capit/<session>/capit.sock)
     #[arg(long)]
     pub socket: Option<PathBuf>,
 
@@ -18,6 +19,16 @@ pub struct Args {
     #[arg(short = 'v', long)]
     pub verbose: bool,
 
+    /// Don't print what a capture produced (saved paths, "copied to clipboard",
+    /// the countdown); errors still go to stderr
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+
+    /// Print results as one JSON object on stdout (errors too, as
+    /// {"status":"error",...}) for other programs to read
+    #[arg(long)]
+    pub json: bool,
+
     /// Override log file path (default: $XDG_STATE_HOME/capit/capit.log)
     #[arg(long)]
     pub log_file: Option<PathBuf>,
@@ -31,12 +42,50 @@ pub enum Cmd {
     /// Show daemon status
     Status,
 
+    /// Check that the daemon answers; exits non-zero if it doesn't in time
+    Ping {
+        /// Give up after this many seconds
+        #[arg(long, default_value_t = 2.0)]
+        timeout: f64,
+    },
+
     /// List outputs (monitors)
     Outputs,
 
     /// Cancel active capture job
     Cancel,
 
+    /// Remove the lock and socket a crashed capitd left behind
+    Abort {
+        /// Stop a capitd that is still running first (SIGTERM, then SIGKILL)
+        #[arg(long)]
+        kill: bool,
+    },
+
+    /// Show which capture modes are available in this environment
+    Modes,
+
+    /// List the image formats the daemon can save, one per line
+    #[command(name = "list-formats")]
+    ListFormats,
+
+    /// Show client and daemon versions side by side
+    Version,
+
+    /// Print the daemon's socket, log file and output directory, one per line
+    #[command(name = "daemon-info")]
+    DaemonInfo,
+
+    /// List captures scheduled with --at/--in
+    Scheduled,
+
+    /// Cancel a scheduled capture by id (see `capit scheduled`)
+    Unschedule { id: u64 },
+
+    /// Save the image on the clipboard to PATH, as offered (PNG when available)
+    #[command(name = "clipboard-save")]
+    ClipboardSave { path: PathBuf },
+
     /// Show floating bar UI (lets you pick mode/target/options)
     Bar {
         /// Preselect a mode (screen/region/window/record)
@@ -49,23 +98,246 @@ pub enum Cmd {
 
     /// Start a region capture (mouse-driven overlay)
     Region {
-        /// Optionally target a specific output by name
+        /// Optionally target a specific output by name, stable id or index (see `capit outputs`)
         #[arg(long, short = 'o')]
         output: Option<String>,
+        /// Skip the overlay and capture X,Y,WxH (or X,Y,W,H); relative to -o's
+        /// output if given, global desktop coordinates otherwise
+        #[arg(long, value_name = "X,Y,WxH", value_parser = parse_geometry)]
+        geometry: Option<Rect>,
+        /// Skip the overlay and capture the last region confirmed in it again
+        #[arg(long, conflicts_with_all = ["output", "geometry"])]
+        repeat: bool,
+        #[command(flatten)]
+        capture: CaptureArgs,
     },
 
     /// Start a full-screen capture (daemon-side overlay)
     Screen {
-        /// Capture a specific output by name, otherwise all screens
-        #[arg(long, short = 'o')]
+        /// Capture a specific output by name, stable id or index (default: capit.screen_default)
+        #[arg(long, short = 'o', conflicts_with = "all")]
         output: Option<String>,
+        /// Capture all screens, ignoring capit.screen_default
+        #[arg(long)]
+        all: bool,
+        /// Save every output as one labelled grid of thumbnails (documents a whole setup)
+        #[arg(long, conflicts_with_all = ["output", "all"])]
+        contact_sheet: bool,
+        /// Use the screenshot portal's own area picker instead of capit's overlay (e.g. on GNOME)
+        #[arg(long, conflicts_with_all = ["output", "all", "contact_sheet"])]
+        portal_interactive: bool,
+        /// Save every output to its own file (name gets -<output>), without the gaps between them
+        #[arg(long, conflicts_with_all = ["output", "all", "contact_sheet", "portal_interactive", "output_file"])]
+        per_output: bool,
+        #[command(flatten)]
+        capture: CaptureArgs,
+    },
+
+    /// IPC round-trip test: send payloads and time the daemon's echo (debugging)
+    #[command(name = "__echo", hide = true)]
+    Echo {
+        /// Payload size in bytes
+        #[arg(long, default_value_t = 0)]
+        size: usize,
+        /// Number of round-trips
+        #[arg(long, default_value_t = 1)]
+        count: u32,
+        /// Largest reply frame the client accepts (bytes)
+        #[arg(long)]
+        max_frame: Option<usize>,
     },
 
     /// Start a window capture (not implemented yet)
-    Window,
+    Window {
+        #[command(flatten)]
+        capture: CaptureArgs,
+    },
+
+    /// Pick a colour: click a pixel on the frozen screen, prints #RRGGBB
+    Pick {
+        /// Open the picker on this output (name, stable id or index)
+        #[arg(long, short = 'o')]
+        output: Option<String>,
+        /// Also copy the hex value to the clipboard
+        #[arg(long, alias = "copy")]
+        clipboard: bool,
+    },
+}
+
+/// Per-capture options shared by region/screen/window.
+#[derive(Debug, ClapArgs)]
+pub struct CaptureArgs {
+    /// Only save the image, whatever capit.default_action says
+    #[arg(long, group = "action")]
+    pub save: bool,
+
+    /// Save and copy the image to the clipboard (overrides capit.default_action)
+    #[arg(long, visible_alias = "clipboard", group = "action")]
+    pub copy: bool,
+
+    /// Copy the image to the clipboard without keeping a file
+    #[arg(long, group = "action")]
+    pub copy_only: bool,
+
+    /// Save, then open the image in capit.editor first
+    #[arg(long, group = "action")]
+    pub edit: bool,
+
+    /// Save this capture into DIR (created if needed), over config and $CAPIT_DIR
+    #[arg(long, value_name = "DIR")]
+    pub output_directory: Option<PathBuf>,
+
+    /// Save this capture to exactly PATH (format from its extension); a directory acts like
+    /// --output-directory, `-` writes the image to stdout
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["output_directory", "format", "raw"])]
+    pub output_file: Option<PathBuf>,
+
+    /// Save in each of these formats, e.g. `png,jpeg` (first is the primary file; default png)
+    #[arg(long, value_name = "FORMATS", value_delimiter = ',')]
+    pub format: Vec<ImageFormat>,
+
+    /// Save an uncompressed BMP (same as `--format bmp`); big files, least daemon CPU
+    #[arg(long, conflicts_with = "format")]
+    pub raw: bool,
+
+    /// Overlay accent colour for this capture only, e.g. `#33FF66`
+    #[arg(long, value_name = "#RRGGBB", value_parser = parse_colour)]
+    pub accent: Option<u32>,
+
+    /// Overlay dimming for this capture only, in percent (0 = none, 100 = black)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub dim: Option<u8>,
+
+    /// Also set the capture as the desktop wallpaper (needs the wallpaper portal)
+    #[arg(long)]
+    pub as_wallpaper: bool,
+
+    /// Wait N seconds after selecting before taking the screenshot (open menus, tooltips)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub delay: u32,
+
+    /// Have the daemon take this capture at local time HH:MM[:SS] (next occurrence)
+    #[arg(long, value_name = "TIME", conflicts_with = "in_")]
+    pub at: Option<String>,
+
+    /// Have the daemon take this capture after a delay, e.g. 30s, 5m, 1h30m
+    #[arg(long = "in", id = "in_", value_name = "DURATION")]
+    pub in_: Option<String>,
+}
+
+impl CaptureArgs {
+    /// Build the IPC options; relative paths are resolved against our cwd,
+    /// since the daemon's working directory is unrelated.
+    pub fn to_options(&self) -> Result<CaptureOptions, String> {
+        let output_dir = match &self.output_directory {
+            Some(dir) => Some(
+                std::path::absolute(dir)
+                    .map_err(|e| format!("invalid --output-directory {}: {e}", dir.display()))?,
+            ),
+            None => None,
+        };
+        let to_stdout = self.output_file.as_deref() == Some(std::path::Path::new("-"));
+        let action = self.action();
+        if to_stdout && (action.is_some_and(|a| a != PostAction::Save)
+            || self.as_wallpaper
+            || self.at.is_some()
+            || self.in_.is_some())
+        {
+            return Err(
+                "--output-file - can't be combined with --copy, --copy-only, --edit, --as-wallpaper, --at or --in"
+                    .into(),
+            );
+        }
+        let output_file = match &self.output_file {
+            Some(_) if to_stdout => None,
+            Some(file) => Some(
+                std::path::absolute(file)
+                    .map_err(|e| format!("invalid --output-file {}: {e}", file.display()))?,
+            ),
+            None => None,
+        };
+
+        Ok(CaptureOptions {
+            action,
+            output_dir,
+            output_file,
+            to_stdout,
+            formats: if self.raw { vec![ImageFormat::Bmp] } else { self.format.clone() },
+            delay_secs: self.delay,
+            geometry: None,
+            repeat_last: false,
+            accent_colour: self.accent,
+            dim_alpha: self.dim.map(|pct| ((pct as u32 * 255 + 50) / 100) as u8),
+            as_wallpaper: self.as_wallpaper,
+            contact_sheet: false,
+            portal_interactive: false,
+            per_output: false,
+        })
+    }
+
+    /// The action flag given, if any (clap keeps them exclusive).
+    fn action(&self) -> Option<PostAction> {
+        if self.save {
+            Some(PostAction::Save)
+        } else if self.copy {
+            Some(PostAction::SaveCopy)
+        } else if self.copy_only {
+            Some(PostAction::Copy)
+        } else if self.edit {
+            Some(PostAction::Edit)
+        } else {
+            None
+        }
+    }
+
+    /// Unix time to schedule this capture for, if `--at`/`--in` was given.
+    pub fn schedule_at(&self, now: i64) -> Result<Option<i64>, String> {
+        use crate::client::schedule::{parse_at, parse_in};
+
+        match (&self.at, &self.in_) {
+            (Some(at), _) => parse_at(at, now).map(Some),
+            (None, Some(d)) => parse_in(d).map(|secs| Some(now + secs)),
+            (None, None) => Ok(None),
+        }
+    }
 }
 
 // handy helpers (keeps run.rs clean)
-pub fn target_from_output_name(output: Option<String>) -> Option<Target> {
-    output.map(Target::OutputName)
+/// `-o` value as a target: all digits is an index into `capit outputs`,
+/// anything else a name or stable id.
+pub fn target_from_output(output: Option<String>) -> Option<Target> {
+    output.map(|o| match o.parse::<u32>() {
+        Ok(i) if o.bytes().all(|b| b.is_ascii_digit()) => Target::OutputIndex(i),
+        _ => Target::OutputName(o),
+    })
+}
+
+/// `#RRGGBB` (the `#` is optional) as opaque ARGB.
+pub fn parse_colour(s: &str) -> Result<u32, String> {
+    let hex = s.trim().strip_prefix('#').unwrap_or(s.trim());
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid colour '{s}' (expected #RRGGBB)"));
+    }
+    let rgb = u32::from_str_radix(hex, 16).map_err(|e| format!("invalid colour '{s}': {e}"))?;
+    Ok(0xFF00_0000 | rgb)
+}
+
+/// `X,Y,WxH`, `X,Y,W,H` or `X,Y WxH` (as printed by slurp).
+pub fn parse_geometry(s: &str) -> Result<Rect, String> {
+    let bad = || format!("invalid geometry '{s}' (expected X,Y,WxH or X,Y,W,H)");
+
+    let nums = s
+        .trim()
+        .split([',', ' ', 'x'])
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<i32>().map_err(|_| bad()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [x, y, w, h] = nums[..] else {
+        return Err(bad());
+    };
+
+    if w <= 0 || h <= 0 {
+        return Err(format!("geometry '{s}' has no area"));
+    }
+    Ok(Rect { x, y, w, h })
 }
@@ -0,0 +1,127 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// `capit abort`: clean up after a capitd that died without removing its
+// lock and socket, which otherwise make the next capitd refuse to start.
+
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use capit_ipc::paths::{lock_path_for_socket, lock_pid, pid_alive};
+use eventline::info;
+use serde_json::json;
+
+use super::print;
+
+/// How long `--kill` waits after SIGTERM before sending SIGKILL.
+const TERM_GRACE: Duration = Duration::from_secs(2);
+
+pub fn run_abort(socket: &Path, kill: bool) -> Result<(), String> {
+    let lock = lock_path_for_socket(socket)
+        .ok_or_else(|| format!("capit: socket path has no parent dir: {}", socket.display()))?;
+
+    let mut removed: Vec<PathBuf> = Vec::new();
+
+    if lock.exists() {
+        let pid = lock_pid(&lock)
+            .ok_or_else(|| format!("capit: can't read a pid from {}; leaving it", lock.display()))?;
+
+        match pid_alive(pid) {
+            None => {
+                return Err(format!(
+                    "capit: can't tell whether capitd (pid {pid}) is running without /proc; leaving {}",
+                    lock.display()
+                ));
+            }
+            // The pid was reused after capitd died: the lock is stale, and
+            // the process is someone else's to keep.
+            Some(true) if !is_capitd(pid) => {
+                info!("abort: pid {pid} from the lock is no longer capitd");
+            }
+            Some(true) if !kill => {
+                return Err(format!(
+                    "capit: capitd is running (pid {pid}); use `capit abort --kill` to stop it"
+                ));
+            }
+            Some(true) => stop(pid)?,
+            Some(false) => {}
+        }
+
+        remove(&lock, &mut removed)?;
+    }
+
+    // A socket with no daemon behind it: nothing accepts, connect is refused.
+    if is_socket(socket) && std::os::unix::net::UnixStream::connect(socket).is_err() {
+        remove(socket, &mut removed)?;
+    }
+
+    if print::json() {
+        let removed: Vec<String> = removed.iter().map(|p| p.display().to_string()).collect();
+        print::print_json(&json!({ "status": "ok", "removed": removed }));
+    } else if removed.is_empty() {
+        println!("nothing to clean up");
+    } else {
+        for path in &removed {
+            println!("removed: {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// SIGTERM `pid`, then SIGKILL if it is still there after `TERM_GRACE`.
+fn stop(pid: u32) -> Result<(), String> {
+    let pid_t = pid as libc::pid_t;
+
+    info!("abort: sending SIGTERM to capitd (pid {pid})");
+    signal(pid_t, libc::SIGTERM)?;
+
+    let deadline = Instant::now() + TERM_GRACE;
+    while Instant::now() < deadline {
+        if pid_alive(pid) == Some(false) {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    info!("abort: capitd (pid {pid}) ignored SIGTERM; sending SIGKILL");
+    signal(pid_t, libc::SIGKILL)?;
+    std::thread::sleep(Duration::from_millis(100));
+    match pid_alive(pid) {
+        Some(false) => Ok(()),
+        _ => Err(format!("capit: capitd (pid {pid}) is still running")),
+    }
+}
+
+fn signal(pid: libc::pid_t, sig: libc::c_int) -> Result<(), String> {
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    if unsafe { libc::kill(pid, sig) } == 0 {
+        return Ok(());
+    }
+    let e = std::io::Error::last_os_error();
+    // Gone in the meantime: that's what we wanted.
+    if e.raw_os_error() == Some(libc::ESRCH) {
+        return Ok(());
+    }
+    Err(format!("capit: signal capitd (pid {pid}): {e}"))
+}
+
+/// Whether `pid` runs capitd, per /proc/<pid>/comm. Unreadable counts as
+/// yes, so a process we can't inspect is never treated as stale.
+fn is_capitd(pid: u32) -> bool {
+    match std::fs::read_to_string(format!("/proc/{pid}/comm")) {
+        Ok(comm) => comm.trim() == "capitd",
+        Err(_) => true,
+    }
+}
+
+fn is_socket(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket())
+}
+
+fn remove(path: &Path, removed: &mut Vec<PathBuf>) -> Result<(), String> {
+    std::fs::remove_file(path).map_err(|e| format!("capit: remove {}: {e}", path.display()))?;
+    info!("abort: removed {}", path.display());
+    removed.push(path.to_path_buf());
+    Ok(())
+}
@@ -1,15 +1,19 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::io::Write;
+
 use capit_core::{Mode, Target};
-use capit_ipc::{Event, IpcClient, Request, Response};
+use capit_ipc::{CaptureOptions, Event, IpcClient, Request, Response};
 use eventline::{debug, error, info};
 
 use super::print;
 
 #[derive(Debug)]
 pub enum CaptureOutcome {
-    Finished { path: String },
+    /// One path per requested format kept on disk, primary first (for
+    /// `per_output`, that per output); none for a clipboard-only capture.
+    Finished { paths: Vec<String> },
     Cancelled,
 }
 
@@ -18,22 +22,38 @@ pub fn start_capture(
     mode: Mode,
     target: Option<Target>,
     with_ui: bool,
+    options: CaptureOptions,
 ) -> Result<CaptureOutcome, String> {
     debug!(
-        "start_capture: mode={:?}, target={:?}, with_ui={}",
-        mode, target, with_ui
+        "start_capture: mode={:?}, target={:?}, with_ui={}, options={:?}",
+        mode, target, with_ui, options
     );
 
+    // --per-output: as many as there are outputs, ended by CaptureBatchFinished.
+    let expected = if options.per_output {
+        usize::MAX
+    } else if options.to_stdout {
+        1
+    } else {
+        options.resolved_formats().len()
+    };
+    let mut paths: Vec<String> = Vec::new();
+    let mut image_data: Vec<u8> = Vec::new();
+    let mut finished = 0;
+
     let resp = client
-        .call(Request::StartCapture { mode, target, with_ui })
+        .call_with_events(Request::StartCapture { mode, target, with_ui, options }, |ev| {
+            match ev {
+                Event::CaptureCountdown { .. } if !print::status_lines() => {}
+                Event::CaptureCountdown { remaining } => eprintln!("capturing in {remaining}..."),
+                _ => {}
+            }
+        })
         .map_err(|e| format!("{e}"))?;
 
     match resp {
         Response::Ok => debug!("StartCapture accepted, waiting for events"),
-        other => {
-            print::print_response(other);
-            return Ok(CaptureOutcome::Cancelled);
-        }
+        other => return Err(print::report_error(other)),
     }
 
     loop {
@@ -41,9 +61,28 @@ pub fn start_capture(
         debug!("event: {:?}", ev);
 
         match ev {
-            Event::CaptureFinished { path } => {
+            Event::CaptureChunk { data } => image_data.extend_from_slice(&data),
+            Event::CaptureFinished { path, on_disk, .. } => {
                 info!("capture finished: {}", path);
-                return Ok(CaptureOutcome::Finished { path });
+                finished += 1;
+                if path == "-" {
+                    write_stdout(&image_data)?;
+                }
+                if on_disk {
+                    paths.push(path);
+                } else if path != "-" && finished == 1 && print::status_lines() {
+                    println!("copied to clipboard");
+                }
+                if finished >= expected {
+                    return Ok(CaptureOutcome::Finished { paths });
+                }
+            }
+            Event::CaptureBatchFinished => return Ok(CaptureOutcome::Finished { paths }),
+            Event::WallpaperSet { path } => {
+                info!("wallpaper set: {}", path);
+                if print::status_lines() {
+                    println!("wallpaper set: {path}");
+                }
             }
             Event::CaptureFailed { message } => {
                 if message == "cancelled" {
@@ -57,3 +96,11 @@ pub fn start_capture(
         }
     }
 }
+
+/// `--output-file -`: the streamed image, as-is.
+fn write_stdout(bytes: &[u8]) -> Result<(), String> {
+    let mut out = std::io::stdout().lock();
+    out.write_all(bytes)
+        .and_then(|_| out.flush())
+        .map_err(|e| format!("write image to stdout: {e}"))
+}
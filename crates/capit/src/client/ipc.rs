@@ -2,12 +2,18 @@
 // License: MIT
 
 use std::path::Path;
+use std::time::Duration;
 
 use capit_ipc::IpcClient;
 use eventline::error;
 
+/// Covers a daemon that was just started (~400ms) without making a daemon
+/// that isn't running at all take noticeably longer to report.
+const CONNECT_ATTEMPTS: u32 = 5;
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
 pub fn connect(socket: &Path) -> Result<IpcClient, String> {
-    IpcClient::connect(socket).map_err(|e| {
+    IpcClient::connect_with_retry(socket, CONNECT_ATTEMPTS, CONNECT_RETRY_DELAY).map_err(|e| {
         // keep structured log for debugging
         error!("failed to connect to daemon: {e}");
 
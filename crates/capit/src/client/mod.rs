@@ -1,7 +1,9 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+pub mod abort;
 pub mod capture;
 pub mod ipc;
 pub mod print;
 pub mod run;
+pub mod schedule;
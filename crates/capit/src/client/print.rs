@@ -1,44 +1,344 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use capit_core::Mode;
 use capit_ipc::Response;
+use serde_json::{Value, json};
+
+use super::schedule;
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+static JSON: AtomicBool = AtomicBool::new(false);
+static REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// With `-v`, errors are printed with their machine-readable code.
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// With `-q`, capture results aren't printed; only the exit code tells.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// With `--json`, every result is one JSON object on stdout, errors included.
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Whether to print human-readable progress ("copied to clipboard", the
+/// countdown): not with `-q`, and not with `--json`, which keeps stdout
+/// parseable.
+pub fn status_lines() -> bool {
+    !quiet() && !json()
+}
+
+pub fn print_json(value: &Value) {
+    println!("{value}");
+}
+
+/// Print a daemon error (with its code) and return its message as the
+/// command's error; `main` then exits non-zero without printing it again.
+pub fn report_error(resp: Response) -> String {
+    let message = match &resp {
+        Response::Error { message, .. } => message.clone(),
+        other => format!("unexpected response: {other:?}"),
+    };
+    print_response(resp);
+    REPORTED.store(true, Ordering::Relaxed);
+    message
+}
+
+/// Whether the error `run` returned has already been printed.
+pub fn error_reported() -> bool {
+    REPORTED.load(Ordering::Relaxed)
+}
+
+/// `{"status":"error",...}`; `code` is left out for client-side failures.
+pub fn error_json(message: &str, code: Option<&str>) -> Value {
+    let mut v = json!({ "status": "error", "message": message });
+    if let Some(code) = code {
+        v["code"] = json!(code);
+    }
+    v
+}
+
+fn mode_name(mode: Mode) -> String {
+    format!("{mode:?}").to_ascii_lowercase()
+}
+
+/// `resp` as the object `--json` prints: `"status"` plus the response's fields.
+fn response_json(resp: Response) -> Value {
+    match resp {
+        Response::Ok => json!({ "status": "ok" }),
+
+        Response::Status {
+            running,
+            active_job,
+            last_saved,
+            last_error,
+            captures_this_session,
+        } => json!({
+            "status": "ok",
+            "running": running,
+            "active_job": active_job.map(mode_name),
+            "last_saved": last_saved,
+            "last_error": last_error,
+            "captures_this_session": captures_this_session,
+        }),
+
+        Response::Error { code, message } => error_json(&message, Some(code.as_str())),
+
+        Response::Outputs { outputs, desktop_bounds: b, excluded } => {
+            let outputs: Vec<Value> = outputs
+                .iter()
+                .enumerate()
+                .map(|(i, o)| {
+                    json!({
+                        "index": i,
+                        "name": o.name,
+                        "id": o.id,
+                        "x": o.x,
+                        "y": o.y,
+                        "width": o.width,
+                        "height": o.height,
+                        "scale": o.effective_scale(),
+                        "make": o.make,
+                        "model": o.model,
+                        "physical_width_mm": o.physical_width_mm,
+                        "physical_height_mm": o.physical_height_mm,
+                        "refresh_mhz": o.refresh_mhz,
+                        "excluded": o.name.as_ref().is_some_and(|n| excluded.contains(n)),
+                    })
+                })
+                .collect();
+            json!({
+                "status": "ok",
+                "outputs": outputs,
+                "desktop": { "x": b.x, "y": b.y, "w": b.w, "h": b.h },
+            })
+        }
+
+        Response::Paths { socket, log, output_dir } => json!({
+            "status": "ok",
+            "socket": socket,
+            "log": log,
+            "output_dir": output_dir,
+        }),
+
+        Response::UiConfig { cfg } => json!({
+            "status": "ok",
+            "accent_colour": format!("#{:08X}", cfg.accent_colour),
+            "bar_background_colour": format!("#{:08X}", cfg.bar_background_colour),
+        }),
+
+        Response::Capabilities {
+            window_capture,
+            record,
+            formats,
+        } => json!({
+            "status": "ok",
+            "modes": {
+                "region": true,
+                "screen": true,
+                "window": window_capture,
+                "record": record,
+            },
+            "formats": formats,
+        }),
+
+        Response::Version { daemon_version, ipc_version } => json!({
+            "status": "ok",
+            "version": daemon_version,
+            "ipc_version": ipc_version,
+        }),
+
+        Response::Echo { payload } => json!({ "status": "ok", "bytes": payload.len() }),
+
+        Response::Pong { uptime_secs } => json!({ "status": "ok", "uptime_secs": uptime_secs }),
+
+        Response::Colour { rgb, hex } => json!({ "status": "ok", "hex": hex, "rgb": rgb }),
+
+        Response::ClipboardSaved { path, mime, bytes } => json!({
+            "status": "ok",
+            "path": path,
+            "mime": mime,
+            "bytes": bytes,
+        }),
+
+        Response::Scheduled { id, at_unix } => json!({
+            "status": "ok",
+            "id": id,
+            "at_unix": at_unix,
+            "at": schedule::format_local(at_unix),
+        }),
+
+        Response::ScheduledList { jobs } => {
+            let jobs: Vec<Value> = jobs
+                .iter()
+                .map(|j| {
+                    json!({
+                        "id": j.id,
+                        "at_unix": j.at_unix,
+                        "at": schedule::format_local(j.at_unix),
+                        "mode": mode_name(j.spec.mode),
+                        "target": j.spec.target,
+                    })
+                })
+                .collect();
+            json!({ "status": "ok", "jobs": jobs })
+        }
+    }
+}
 
 pub fn print_response(resp: Response) {
+    if json() {
+        print_json(&response_json(resp));
+        return;
+    }
+
     match resp {
         Response::Ok => println!("ok"),
 
-        Response::Status { running, active_job } => {
+        Response::Status {
+            running,
+            active_job,
+            last_saved,
+            last_error,
+            captures_this_session,
+        } => {
             println!("running: {running}");
             match active_job {
                 Some(m) => println!("active_job: {m:?}"),
                 None => println!("active_job: none"),
             }
+            println!("last_saved: {}", last_saved.as_deref().unwrap_or("none"));
+            println!("last_error: {}", last_error.as_deref().unwrap_or("none"));
+            println!("captures_this_session: {captures_this_session}");
         }
 
-        Response::Error { message } => eprintln!("error: {message}"),
+        Response::Error { code, message } => {
+            if VERBOSE.load(Ordering::Relaxed) {
+                eprintln!("error [{code}]: {message}");
+            } else {
+                eprintln!("error: {message}");
+            }
+        }
 
-        Response::Outputs { outputs } => println!("outputs: {}", outputs.len()),
+        Response::Outputs { outputs, .. } => println!("outputs: {}", outputs.len()),
+
+        Response::Paths { socket, log, output_dir } => {
+            println!("socket: {socket}");
+            println!("log: {log}");
+            println!("output_dir: {output_dir}");
+        }
 
         Response::UiConfig { cfg } => {
             println!("accent_colour: 0x{:08X}", cfg.accent_colour);
             println!("bar_background_colour: 0x{:08X}", cfg.bar_background_colour);
         }
+
+        Response::Capabilities {
+            window_capture,
+            record,
+            formats,
+        } => {
+            println!("region ✓");
+            println!("screen ✓");
+            if window_capture {
+                println!("window ✓");
+            } else {
+                println!("window ✗ (no window capture backend available)");
+            }
+            if record {
+                println!("record ✓");
+            } else {
+                println!("record ✗ (not supported by this daemon)");
+            }
+            println!("formats: {}", formats.join(", "));
+        }
+
+        Response::Version { daemon_version, ipc_version } => {
+            print_version_line("capitd", &daemon_version, ipc_version);
+        }
+
+        Response::Echo { payload } => println!("echo: {} bytes", payload.len()),
+
+        Response::Pong { uptime_secs } => println!("pong (up {uptime_secs}s)"),
+
+        // Hex only: `$(capit pick)` is the common use.
+        Response::Colour { hex, .. } => println!("{hex}"),
+
+        Response::ClipboardSaved { path, mime, bytes } => {
+            println!("saved clipboard {mime} ({bytes} bytes) to: {path}");
+        }
+
+        Response::Scheduled { id, at_unix } => {
+            println!("scheduled #{id} at {}", schedule::format_local(at_unix));
+        }
+
+        Response::ScheduledList { jobs } => {
+            if jobs.is_empty() {
+                println!("(no scheduled captures)");
+            }
+            for j in jobs {
+                let target = match &j.spec.target {
+                    Some(t) => format!(" {t:?}"),
+                    None => String::new(),
+                };
+                println!(
+                    "#{}: {} {:?}{target}",
+                    j.id,
+                    schedule::format_local(j.at_unix),
+                    j.spec.mode
+                );
+            }
+        }
     }
 }
 
+/// One row of `capit version`: name, build version, protocol version.
+pub fn print_version_line(name: &str, version: &str, ipc_version: u32) {
+    println!("{name:<7} {version} (ipc {ipc_version})");
+}
+
 pub fn print_outputs_or_fallback(resp: Response) {
+    if json() {
+        print_json(&response_json(resp));
+        return;
+    }
+
     match resp {
-        Response::Outputs { outputs } => {
+        Response::Outputs { outputs, desktop_bounds: b, excluded } => {
             if outputs.is_empty() {
                 println!("(no outputs reported yet)");
+                println!("hint: check that a monitor is connected and capitd runs in your Wayland session");
             } else {
                 for (i, o) in outputs.iter().enumerate() {
                     let name = o.name.as_deref().unwrap_or("(unnamed)");
+                    let mark = match &o.name {
+                        Some(n) if excluded.contains(n) => " (excluded)",
+                        _ => "",
+                    };
+                    let id = o.id.as_deref().map(|id| format!(" [{id}]")).unwrap_or_default();
+                    let model = o.model.as_deref().map(|m| format!(" \"{m}\"")).unwrap_or_default();
                     println!(
-                        "#{i}: {name} @ ({}, {}) {}x{} scale {}",
-                        o.x, o.y, o.width, o.height, o.scale
+                        "#{i}: {name}{id}{model} @ ({}, {}) {}x{} scale {}{mark}",
+                        o.x, o.y, o.width, o.height, o.effective_scale()
                     );
                 }
+                println!("desktop: @ ({}, {}) {}x{}", b.x, b.y, b.w, b.h);
             }
         }
         other => print_response(other),
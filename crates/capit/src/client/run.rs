@@ -3,19 +3,23 @@
 
 use std::path::Path;
 
-use capit_core::{Mode, Target};
-use capit_ipc::Request;
+use capit_core::{Mode, PostAction, Target};
+use capit_ipc::{CaptureOptions, CaptureSpec, Request, Response, IPC_VERSION};
 
 use eventline::{debug, info};
+use serde_json::{Value, json};
 
 use crate::cli::{self, Args, Cmd};
 use crate::paths;
 
-use super::{capture, ipc, print};
+use super::{abort, capture, ipc, print, schedule};
 
 pub fn run(args: Args) -> Result<(), String> {
     info!("starting client");
     debug!("parsed args: {:?}", args.cmd);
+    print::set_verbose(args.verbose);
+    print::set_quiet(args.quiet);
+    print::set_json(args.json);
 
     let socket = args.socket.unwrap_or_else(paths::default_socket_path);
     debug!("socket: {}", socket.display());
@@ -23,6 +27,15 @@ pub fn run(args: Args) -> Result<(), String> {
     match args.cmd {
         Cmd::Bar { .. } => run_capit_bar(&socket),
 
+        // Must work when the daemon is down or speaks another protocol.
+        Cmd::Version => run_version(&socket),
+
+        // Needs its own timeout on the handshake: a wedged daemon still accepts.
+        Cmd::Ping { timeout } => run_ping(&socket, timeout),
+
+        // For when the daemon is gone (or wedged): no connection.
+        Cmd::Abort { kill } => abort::run_abort(&socket, kill),
+
         _ => {
             let mut client = ipc::connect(&socket)?;
             info!("connected to daemon");
@@ -40,60 +53,312 @@ pub fn run(args: Args) -> Result<(), String> {
                     Ok(())
                 }
 
-                Cmd::Cancel => {
-                    let resp = client.call(Request::Cancel).map_err(|e| format!("{e}"))?;
+                Cmd::Modes => {
+                    let resp = client.call(Request::GetCapabilities).map_err(|e| format!("{e}"))?;
                     print::print_response(resp);
                     Ok(())
                 }
 
-                Cmd::Region { output } => {
-                    let target = cli::target_from_output_name(output);
-
-                    match capture::start_capture(&mut client, Mode::Region, target, false)? {
-                        capture::CaptureOutcome::Finished { path } => {
-                            println!("saved to: {path}");
-                            Ok(())
+                Cmd::ListFormats => {
+                    let resp = client.call(Request::GetCapabilities).map_err(|e| format!("{e}"))?;
+                    match resp {
+                        Response::Capabilities { formats, .. } if print::json() => {
+                            print::print_json(&json!({ "status": "ok", "formats": formats }));
                         }
-                        capture::CaptureOutcome::Cancelled => {
-                            info!("capture cancelled");
-                            Ok(())
+                        Response::Capabilities { formats, .. } => {
+                            for f in formats {
+                                println!("{f}");
+                            }
                         }
+                        other => print::print_response(other),
                     }
+                    Ok(())
+                }
+
+                Cmd::Echo { size, count, max_frame } => {
+                    if let Some(max) = max_frame {
+                        client.set_max_frame(max);
+                    }
+                    run_echo(&mut client, size, count)
                 }
 
-                Cmd::Screen { output } => {
-                    let target = match output {
-                        Some(name) => Some(Target::OutputName(name)),
-                        None => Some(Target::AllScreens),
+                Cmd::Cancel => {
+                    let resp = client.call(Request::Cancel).map_err(|e| format!("{e}"))?;
+                    print::print_response(resp);
+                    Ok(())
+                }
+
+                Cmd::Scheduled => {
+                    let resp = client.call(Request::ListScheduled).map_err(|e| format!("{e}"))?;
+                    print::print_response(resp);
+                    Ok(())
+                }
+
+                Cmd::Unschedule { id } => {
+                    let resp = client
+                        .call(Request::CancelScheduled { id })
+                        .map_err(|e| format!("{e}"))?;
+                    print::print_response(resp);
+                    Ok(())
+                }
+
+                Cmd::DaemonInfo => {
+                    let resp = client.call(Request::GetPaths).map_err(|e| format!("{e}"))?;
+                    print::print_response(resp);
+                    Ok(())
+                }
+
+                Cmd::ClipboardSave { path } => {
+                    // The daemon's working directory is unrelated to ours.
+                    let path = std::path::absolute(&path)
+                        .map_err(|e| format!("invalid path {}: {e}", path.display()))?;
+                    let resp = client
+                        .call(Request::SaveClipboard { path })
+                        .map_err(|e| format!("{e}"))?;
+                    print::print_response(resp);
+                    Ok(())
+                }
+
+                Cmd::Region { output, geometry, repeat, capture: capture_args } => {
+                    let (target, geometry) = match (output, geometry) {
+                        // Global coordinates need no output: the rect is the target.
+                        (None, Some(rect)) => (Some(Target::Rect(rect)), None),
+                        (output, geometry) => (cli::target_from_output(output), geometry),
+                    };
+                    let options = CaptureOptions {
+                        geometry,
+                        repeat_last: repeat,
+                        ..capture_args.to_options()?
+                    };
+                    run_capture(&mut client, Mode::Region, target, &capture_args, options)
+                }
+
+                Cmd::Screen { output, all, contact_sheet, portal_interactive, per_output, capture: capture_args } => {
+                    // CLI flag > capit.screen_default (resolved daemon-side when None)
+                    let target = match (output, all) {
+                        (Some(output), _) => cli::target_from_output(Some(output)),
+                        (None, true) => Some(Target::AllScreens),
+                        (None, false) => None,
+                    };
+                    let options = CaptureOptions {
+                        contact_sheet,
+                        portal_interactive,
+                        per_output,
+                        ..capture_args.to_options()?
                     };
+                    run_capture(&mut client, Mode::Screen, target, &capture_args, options)
+                }
 
-                    match capture::start_capture(&mut client, Mode::Screen, target, false)? {
-                        capture::CaptureOutcome::Finished { path } => {
-                            println!("saved to: {path}");
-                            Ok(())
-                        }
-                        capture::CaptureOutcome::Cancelled => {
-                            info!("capture cancelled");
-                            Ok(())
+                Cmd::Window { capture: capture_args } => {
+                    let options = capture_args.to_options()?;
+                    run_capture(&mut client, Mode::Window, None, &capture_args, options)
+                }
+
+                Cmd::Pick { output, clipboard } => {
+                    let options = CaptureOptions {
+                        action: clipboard.then_some(PostAction::Copy),
+                        ..CaptureOptions::default()
+                    };
+                    let target = cli::target_from_output(output);
+                    let resp = client
+                        .call(Request::StartCapture { mode: Mode::Pick, target, with_ui: false, options })
+                        .map_err(|e| format!("{e}"))?;
+                    match resp {
+                        // Cancelled, like an overlay capture: nothing to print.
+                        Response::Ok => {
+                            info!("pick cancelled");
+                            print_cancelled();
                         }
+                        err @ Response::Error { .. } => return Err(print::report_error(err)),
+                        other => print::print_response(other),
                     }
+                    Ok(())
                 }
 
-                Cmd::Window => match capture::start_capture(&mut client, Mode::Window, None, false)? {
-                    capture::CaptureOutcome::Finished { path } => {
-                        println!("saved to: {path}");
-                        Ok(())
-                    }
-                    capture::CaptureOutcome::Cancelled => {
-                        info!("capture cancelled");
-                        Ok(())
-                    }
-                },
+                Cmd::Bar { .. } | Cmd::Version | Cmd::Ping { .. } | Cmd::Abort { .. } => unreachable!(),
+            }
+        }
+    }
+}
+
+/// Start a capture now, or hand it to the daemon's scheduler for --at/--in.
+/// `options` are `args` plus the mode's own flags.
+fn run_capture(
+    client: &mut capit_ipc::IpcClient,
+    mode: Mode,
+    target: Option<Target>,
+    args: &cli::CaptureArgs,
+    options: CaptureOptions,
+) -> Result<(), String> {
+    if let Some(at_unix) = args.schedule_at(schedule::unix_now())? {
+        let spec = CaptureSpec { mode, target, options };
+        let resp = client
+            .call(Request::ScheduleCapture { at_unix, spec })
+            .map_err(|e| format!("{e}"))?;
+        print::print_response(resp);
+        return Ok(());
+    }
+
+    let to_stdout = options.to_stdout;
+    match capture::start_capture(client, mode, target, false, options)? {
+        capture::CaptureOutcome::Finished { paths } => {
+            // "-": the image itself went to stdout; keep it clean.
+            let paths: Vec<&String> = paths.iter().filter(|p| *p != "-").collect();
+            if print::quiet() {
+                // Only the exit code.
+            } else if print::json() {
+                // "path" is null for a clipboard-only capture.
+                let value = json!({ "status": "ok", "path": paths.first(), "paths": paths });
+                if to_stdout {
+                    eprintln!("{value}");
+                } else {
+                    print::print_json(&value);
+                }
+            } else {
+                for path in paths {
+                    println!("saved to: {path}");
+                }
+            }
+            Ok(())
+        }
+        capture::CaptureOutcome::Cancelled => {
+            info!("capture cancelled");
+            print_cancelled();
+            Ok(())
+        }
+    }
+}
+
+/// A cancelled capture prints nothing, except `{"status":"cancelled"}` with --json.
+fn print_cancelled() {
+    if print::json() && !print::quiet() {
+        print::print_json(&json!({ "status": "cancelled" }));
+    }
+}
+
+/// Print client and daemon versions; the daemon line explains why it's missing.
+fn run_version(socket: &Path) -> Result<(), String> {
+    if print::json() {
+        return run_version_json(socket);
+    }
+
+    print::print_version_line("capit", env!("CARGO_PKG_VERSION"), IPC_VERSION);
+
+    let daemon = capit_ipc::IpcClient::connect(socket)
+        .and_then(|mut client| client.call(Request::GetVersion));
+    match daemon {
+        Ok(Response::Version { daemon_version, ipc_version }) => {
+            print::print_version_line("capitd", &daemon_version, ipc_version);
+            if daemon_version != env!("CARGO_PKG_VERSION") || ipc_version != IPC_VERSION {
+                println!("warning: client and daemon differ; restart capitd after upgrading");
+            }
+        }
+        Ok(other) => print::print_response(other),
+        Err(e) => println!("capitd  unavailable ({e})"),
+    }
+    Ok(())
+}
+
+/// `capit --json version`: the daemon's half is null when it can't be reached.
+fn run_version_json(socket: &Path) -> Result<(), String> {
+    let daemon = capit_ipc::IpcClient::connect(socket)
+        .and_then(|mut client| client.call(Request::GetVersion));
+    let (daemon, error) = match daemon {
+        Ok(Response::Version { daemon_version, ipc_version }) => {
+            (json!({ "version": daemon_version, "ipc_version": ipc_version }), None)
+        }
+        Ok(other) => (Value::Null, Some(format!("unexpected response {other:?}"))),
+        Err(e) => (Value::Null, Some(e.to_string())),
+    };
+
+    print::print_json(&json!({
+        "status": "ok",
+        "client": { "version": env!("CARGO_PKG_VERSION"), "ipc_version": IPC_VERSION },
+        "daemon": daemon,
+        "daemon_error": error,
+    }));
+    Ok(())
+}
+
+/// Connect, ping and print the daemon's uptime; any failure or a round trip
+/// slower than `timeout_secs` is an error (non-zero exit).
+fn run_ping(socket: &Path, timeout_secs: f64) -> Result<(), String> {
+    use std::time::{Duration, Instant};
+
+    let timeout = Duration::try_from_secs_f64(timeout_secs)
+        .ok()
+        .filter(|t| !t.is_zero())
+        .ok_or_else(|| format!("capit: invalid --timeout {timeout_secs}"))?;
+    let no_answer = |e: capit_ipc::IpcError| match e {
+        capit_ipc::IpcError::Timeout { .. } => format!("capit: no answer from capitd within {timeout:?}"),
+        other => format!("capit: ping failed: {other}"),
+    };
 
-                Cmd::Bar { .. } => unreachable!(),
+    let start = Instant::now();
+    let mut client = capit_ipc::IpcClient::connect_timeout(socket, timeout).map_err(no_answer)?;
+    let resp = client.call(Request::Ping).map_err(no_answer)?;
+    let elapsed = start.elapsed();
+
+    match resp {
+        Response::Pong { .. } if elapsed > timeout => Err(format!(
+            "capit: capitd answered after {elapsed:?} (timeout {timeout:?})"
+        )),
+        Response::Pong { uptime_secs } if print::json() => {
+            print::print_json(&json!({
+                "status": "ok",
+                "uptime_secs": uptime_secs,
+                "elapsed_ms": elapsed.as_millis() as u64,
+            }));
+            Ok(())
+        }
+        Response::Pong { uptime_secs } => {
+            println!("pong (up {uptime_secs}s)");
+            Ok(())
+        }
+        other => Err(format!("capit: unexpected ping response {other:?}")),
+    }
+}
+
+/// Time `count` Echo round-trips of `size` bytes and verify the payload survives.
+fn run_echo(client: &mut capit_ipc::IpcClient, size: usize, count: u32) -> Result<(), String> {
+    use std::time::{Duration, Instant};
+
+    // Non-repeating-ish pattern so truncation/reordering shows up.
+    let payload: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+    let mut times: Vec<Duration> = Vec::with_capacity(count as usize);
+
+    for i in 0..count.max(1) {
+        let start = Instant::now();
+        let resp = client
+            .call(Request::Echo { payload: payload.clone() })
+            .map_err(|e| format!("echo #{i}: {e}"))?;
+        let elapsed = start.elapsed();
+
+        match resp {
+            Response::Echo { payload: back } if back == payload => times.push(elapsed),
+            Response::Echo { payload: back } => {
+                return Err(format!(
+                    "echo #{i}: payload mismatch (sent {} bytes, got {})",
+                    payload.len(),
+                    back.len()
+                ));
             }
+            other => return Err(format!("echo #{i}: unexpected response {other:?}")),
         }
     }
+
+    let min = times.iter().min().copied().unwrap_or_default();
+    let max = times.iter().max().copied().unwrap_or_default();
+    let avg = times.iter().sum::<Duration>() / times.len() as u32;
+
+    println!(
+        "{} x {size} bytes: min {:?} avg {:?} max {:?}",
+        times.len(),
+        min,
+        avg,
+        max
+    );
+    Ok(())
 }
 
 fn run_capit_bar(socket: &Path) -> Result<(), String> {
@@ -0,0 +1,143 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Parsing for `--at HH:MM[:SS]` / `--in 1h30m` and local-time display.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Next occurrence of local wall-clock `HH:MM[:SS]` after `now` (today or tomorrow).
+pub fn parse_at(s: &str, now: i64) -> Result<i64, String> {
+    let bad = || format!("invalid --at '{s}' (expected HH:MM or HH:MM:SS)");
+
+    let parts: Vec<&str> = s.trim().split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return Err(bad());
+    }
+    let num = |p: &str, max: i32| p.parse::<i32>().ok().filter(|v| (0..=max).contains(v));
+
+    let hour = num(parts[0], 23).ok_or_else(bad)?;
+    let min = num(parts[1], 59).ok_or_else(bad)?;
+    let sec = match parts.get(2) {
+        Some(p) => num(p, 59).ok_or_else(bad)?,
+        None => 0,
+    };
+
+    let today = local_at(now, hour, min, sec)?;
+    if today > now {
+        return Ok(today);
+    }
+    // mktime normalises day overflow, so +1 day also handles month/DST edges.
+    local_at(now + 24 * 60 * 60, hour, min, sec)
+}
+
+/// Durations like `90`, `90s`, `5m`, `1h30m` (bare numbers are seconds).
+pub fn parse_in(s: &str) -> Result<i64, String> {
+    let bad = || format!("invalid --in '{s}' (expected e.g. 30s, 5m, 1h30m)");
+
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(bad());
+    }
+    if let Ok(secs) = s.parse::<i64>() {
+        return if secs > 0 { Ok(secs) } else { Err(bad()) };
+    }
+
+    let mut total: i64 = 0;
+    let mut digits = String::new();
+
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            _ => return Err(bad()),
+        };
+        let n: i64 = digits.parse().map_err(|_| bad())?;
+        total = n.checked_mul(unit).and_then(|v| total.checked_add(v)).ok_or_else(bad)?;
+        digits.clear();
+    }
+
+    if !digits.is_empty() || total <= 0 {
+        return Err(bad());
+    }
+    Ok(total)
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in local time.
+pub fn format_local(unix_secs: i64) -> String {
+    let tm = local_tm(unix_secs);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+fn local_tm(unix_secs: i64) -> libc::tm {
+    let t: libc::time_t = unix_secs as libc::time_t;
+    // SAFETY: tm is plain old data; localtime_r fills it (or leaves zeroes on failure).
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::localtime_r(&t, &mut tm);
+    }
+    tm
+}
+
+/// Unix time of `hour:min:sec` local time on the day containing `day_of`.
+fn local_at(day_of: i64, hour: i32, min: i32, sec: i32) -> Result<i64, String> {
+    let mut tm = local_tm(day_of);
+    tm.tm_hour = hour;
+    tm.tm_min = min;
+    tm.tm_sec = sec;
+    tm.tm_isdst = -1; // let mktime decide
+
+    // SAFETY: tm is a valid, initialised struct.
+    let t = unsafe { libc::mktime(&mut tm) };
+    if t == -1 {
+        return Err("could not resolve local time".into());
+    }
+    Ok(t as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_in;
+
+    #[test]
+    fn parse_in_units() {
+        assert_eq!(parse_in("90"), Ok(90));
+        assert_eq!(parse_in("90s"), Ok(90));
+        assert_eq!(parse_in("5m"), Ok(300));
+        assert_eq!(parse_in(" 1h30m "), Ok(5400));
+        assert_eq!(parse_in("1h1m1s"), Ok(3661));
+    }
+
+    #[test]
+    fn parse_in_rejects_bad_input() {
+        for s in ["", "0", "-5", "0m", "5x", "m", "1h30", "1.5h"] {
+            assert!(parse_in(s).is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn parse_in_rejects_overflow() {
+        assert!(parse_in("9223372036854775807h").is_err());
+        assert!(parse_in("9223372036854775807s1s").is_err());
+        assert!(parse_in("99999999999999999999s").is_err());
+    }
+}
@@ -3,13 +3,18 @@
 
 use std::future::Future;
 use std::path::Path;
-use std::pin::Pin;
-use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
 
 use eventline::runtime::{self, LogLevel};
 
 use crate::paths::ensure_parent_dir;
 
+const PARK_TIMEOUT: Duration = Duration::from_millis(10);
+
 pub fn init_logging(log_path: &Path, verbose: bool) -> Result<(), String> {
     ensure_parent_dir(log_path).map_err(|e| format!("create log dir: {e}"))?;
 
@@ -31,25 +36,30 @@ pub fn init_logging(log_path: &Path, verbose: bool) -> Result<(), String> {
     Ok(())
 }
 
-fn block_on<F: Future>(mut fut: F) -> F::Output {
-    unsafe fn clone(_: *const ()) -> RawWaker {
-        RawWaker::new(std::ptr::null(), &VTABLE)
+/// Waker that unparks the thread blocked in `block_on`.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
     }
-    unsafe fn wake(_: *const ()) {}
-    unsafe fn wake_by_ref(_: *const ()) {}
-    unsafe fn drop(_: *const ()) {}
 
-    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
 
-    let waker = unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) };
+/// Run `fut` to completion on this thread, parking while it is pending.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
     let mut cx = Context::from_waker(&waker);
-
-    let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+    let mut fut = pin!(fut);
 
     loop {
         match fut.as_mut().poll(&mut cx) {
             Poll::Ready(v) => return v,
-            Poll::Pending => std::thread::yield_now(),
+            // Timeout only guards against a future that never wakes us.
+            Poll::Pending => thread::park_timeout(PARK_TIMEOUT),
         }
     }
 }
@@ -29,12 +29,19 @@ fn main() {
     info!("capit starting");
     debug!("log file={}", log_path.display());
 
+    let json = args.json;
     if let Err(e) = client::run::run(args) {
         // log for diagnostics
         error!("fatal error: {e}");
 
         // user-facing error (preserves real newlines)
-        eprintln!("{e}");
+        if client::print::error_reported() {
+            // Daemon errors are printed where they arrive, code included.
+        } else if json {
+            client::print::print_json(&client::print::error_json(&e, None));
+        } else {
+            eprintln!("{e}");
+        }
 
         std::process::exit(1);
     }
@@ -3,12 +3,7 @@
 
 use std::path::{Path, PathBuf};
 
-pub fn default_socket_path() -> PathBuf {
-    let base = std::env::var_os("XDG_RUNTIME_DIR")
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("/tmp"));
-    base.join("capit").join("capit.sock")
-}
+pub use capit_ipc::paths::default_socket_path;
 
 pub fn default_log_path(file: &str) -> PathBuf {
     let base = std::env::var_os("XDG_STATE_HOME")
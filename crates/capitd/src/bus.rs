@@ -0,0 +1,34 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// One session-bus connection shared by portal captures and notifications.
+// Opened on first use and reused after that; callers that hit an error call
+// `invalidate()` so the next user reconnects (bus restarted, peer crashed).
+
+use std::sync::{Mutex, MutexGuard};
+
+use zbus::Connection;
+
+static SESSION: Mutex<Option<Connection>> = Mutex::new(None);
+
+fn slot() -> MutexGuard<'static, Option<Connection>> {
+    SESSION.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The shared connection, connecting if there is none yet.
+pub async fn session() -> zbus::Result<Connection> {
+    // Never hold the lock across an await.
+    let cached = slot().clone();
+    if let Some(conn) = cached {
+        return Ok(conn);
+    }
+
+    let conn = Connection::session().await?;
+    *slot() = Some(conn.clone());
+    Ok(conn)
+}
+
+/// Drop the shared connection; the next `session()` reconnects.
+pub fn invalidate() {
+    *slot() = None;
+}
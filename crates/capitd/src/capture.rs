@@ -10,173 +10,558 @@
 // desktop. To support `--output` and region/window flows, we capture full and then crop.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use async_io::Timer;
-use futures_util::{future::select, future::Either, pin_mut, StreamExt};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
 
-use image::GenericImageView;
+use zbus::Proxy;
+use zbus::zvariant::{OwnedObjectPath, Value};
 
-use zbus::{Connection, Proxy};
-use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use capit_core::{DeviceRect, ErrorCode, ImageFormat};
+use eventline::warn;
 
-use capit_core::Rect;
+use crate::bus;
+use crate::config::Corner;
+use crate::font;
+use crate::portal::{self, new_handle_token, PORTAL_DEST, PORTAL_PATH};
 
-const PORTAL_DEST: &str = "org.freedesktop.portal.Desktop";
 const SCREENSHOT_IFACE: &str = "org.freedesktop.portal.Screenshot";
-const REQUEST_IFACE: &str = "org.freedesktop.portal.Request";
-const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
 
+/// How long an interactive screenshot may wait for the user's pick.
+const INTERACTIVE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Screenshot interface version, read once per portal connection (0 = not yet).
+static SCREENSHOT_VERSION: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug)]
+pub enum CaptureError {
+    /// The filesystem holding the output ran out of space (ENOSPC).
+    DiskFull { path: PathBuf },
+    /// The output location is on a read-only filesystem (EROFS).
+    ReadOnly { path: PathBuf },
+    /// The portal answered with a non-zero response (1 = cancelled by the user).
+    PortalDenied { response: u32 },
+    Failed(String),
+}
+
+impl CaptureError {
+    /// True for errors the user fixes by pointing captures somewhere else.
+    pub fn is_storage(&self) -> bool {
+        matches!(self, CaptureError::DiskFull { .. } | CaptureError::ReadOnly { .. })
+    }
+
+    /// Code reported to the client in `Response::Error`.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            CaptureError::DiskFull { .. } | CaptureError::ReadOnly { .. } => ErrorCode::Storage,
+            CaptureError::PortalDenied { response: 1 } => ErrorCode::Cancelled,
+            CaptureError::PortalDenied { .. } => ErrorCode::PortalDenied,
+            CaptureError::Failed(_) => ErrorCode::CaptureFailed,
+        }
+    }
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::DiskFull { path } => write!(
+                f,
+                "no space left on device while writing {}; free some space or set a different capit.screenshot_directory",
+                path.display()
+            ),
+            CaptureError::ReadOnly { path } => write!(
+                f,
+                "cannot write {} (read-only filesystem); set a different capit.screenshot_directory",
+                path.display()
+            ),
+            CaptureError::PortalDenied { response } => {
+                write!(f, "portal screenshot failed (response={response})")
+            }
+            CaptureError::Failed(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<String> for CaptureError {
+    fn from(s: String) -> Self {
+        CaptureError::Failed(s)
+    }
+}
+
+impl From<&str> for CaptureError {
+    fn from(s: &str) -> Self {
+        CaptureError::Failed(s.to_string())
+    }
+}
+
+/// Classify a write-side io error: full/read-only filesystems get their own
+/// variants (actionable), anything else keeps the generic context message.
+fn write_error(e: io::Error, path: &Path, context: &str) -> CaptureError {
+    match e.kind() {
+        io::ErrorKind::StorageFull => CaptureError::DiskFull { path: path.to_path_buf() },
+        io::ErrorKind::ReadOnlyFilesystem => CaptureError::ReadOnly { path: path.to_path_buf() },
+        _ => CaptureError::Failed(format!("{context}: {e}")),
+    }
+}
+
+fn image_write_error(e: image::ImageError, path: &Path, context: &str) -> CaptureError {
+    match e {
+        image::ImageError::IoError(io_err) => write_error(io_err, path, context),
+        other => CaptureError::Failed(format!("{context}: {other}")),
+    }
+}
+
+/// What ended up on disk for a successful capture.
 #[derive(Debug, Clone, Copy)]
-pub struct CaptureCrop {
-    pub x: i32,
-    pub y: i32,
-    pub w: i32,
-    pub h: i32,
+pub struct SavedImage {
+    pub width: u32,
+    pub height: u32,
+    /// Encoded file size.
+    pub bytes: u64,
 }
 
-impl CaptureCrop {
-    /// Convert a core Rect to a crop rect.
-    /// Adjust these field names if your Rect uses width/height naming.
-    pub fn from_rect(r: &Rect) -> Self {
+impl SavedImage {
+    pub fn read(path: &Path) -> Result<Self, CaptureError> {
+        let (width, height) = image::image_dimensions(path)
+            .map_err(|e| format!("read saved image {path:?}: {e}"))?;
+        let bytes = fs::metadata(path)
+            .map_err(|e| format!("stat saved image {path:?}: {e}"))?
+            .len();
+        Ok(Self { width, height, bytes })
+    }
+}
+
+/// Post-processing applied to the final image before it is written.
+/// The default does nothing, so the portal's PNG is kept byte-for-byte.
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+    pub watermark: Option<Watermark>,
+    /// Quality (1..=100) for JPEG outputs; ignored by other formats.
+    pub jpeg_quality: u8,
+    /// Capture once more if the portal's frame comes back blank.
+    pub retry_on_black: bool,
+    /// Ask the portal for an interactive screenshot: it shows its own
+    /// area/window picker and returns only what the user chose.
+    pub interactive: bool,
+}
+
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
         Self {
-            x: r.x,
-            y: r.y,
-            w: r.w,
-            h: r.h,
+            watermark: None,
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            retry_on_black: false,
+            interactive: false,
         }
     }
 }
 
-/// Capture a full screenshot and write it to `out_path`.
+/// Text stamped into a corner of the final image.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    pub text: String,
+    pub corner: Corner,
+    pub colour: u32, // ARGB
+    pub opacity: f32,
+}
+
+/// Capture a full screenshot and write it to each of `out_paths`
+/// (encoded by extension). Returns one `SavedImage` per path, in order.
 ///
 /// Notes:
 /// - Requires xdg-desktop-portal + a backend (gtk/kde/wlr/etc).
 /// - May show a permission dialog depending on portal config.
-pub fn capture_screen_to(out_path: &Path) -> Result<(), String> {
-    ensure_parent_dir(out_path)?;
-
-    let src_path = capture_portal_to_temp_file(out_path)?;
-    fs::copy(&src_path, out_path)
-        .map_err(|e| format!("copy {src_path:?} -> {out_path:?}: {e}"))?;
+pub fn capture_screen_to(
+    out_paths: &[PathBuf],
+    opts: &CaptureOptions,
+) -> Result<Vec<SavedImage>, CaptureError> {
+    let primary = primary_path(out_paths)?;
+    ensure_parent_dir(primary)?;
+
+    let src_path = capture_portal_checked(primary, opts)?;
+
+    // A single plain PNG keeps the portal's file byte-for-byte.
+    let passthrough = opts.watermark.is_none()
+        && out_paths.len() == 1
+        && format_for_path(primary) == ImageFormat::Png;
+
+    let res = if passthrough {
+        fs::copy(&src_path, primary).map(|_| ()).map_err(|e| {
+            write_error(e, primary, &format!("copy {src_path:?} -> {primary:?}"))
+        })
+    } else {
+        image::open(&src_path)
+            .map_err(|e| CaptureError::Failed(format!("open screenshot: {e}")))
+            .and_then(|img| write_images(img, out_paths, opts, "save screenshot"))
+    };
     let _ = fs::remove_file(&src_path);
-    Ok(())
+    res?;
+    read_all(out_paths)
 }
 
-/// Capture a screenshot, then crop and save to `out_path`.
+/// Capture a screenshot, then crop and save to each of `out_paths`.
 ///
 /// This is used for `--output`, and for region/window once you have rects.
-pub fn capture_screen_to_crop(out_path: &Path, crop: CaptureCrop) -> Result<(), String> {
-    ensure_parent_dir(out_path)?;
+pub fn capture_screen_to_crop(
+    out_paths: &[PathBuf],
+    crop: DeviceRect,
+    opts: &CaptureOptions,
+) -> Result<Vec<SavedImage>, CaptureError> {
+    let primary = primary_path(out_paths)?;
+    ensure_parent_dir(primary)?;
+
+    let src_path = capture_portal_checked(primary, opts)?;
+    let res = save_cropped(&src_path, out_paths, crop, opts);
+    let _ = fs::remove_file(&src_path);
+    res?;
+    read_all(out_paths)
+}
 
-    let src_path = capture_portal_to_temp_file(out_path)?;
-    let res = save_cropped_png(&src_path, out_path, crop);
+/// Capture a screenshot and keep only `areas` (e.g. the non-excluded outputs):
+/// the result is their bounding box, with anything outside them transparent.
+pub fn capture_screen_to_areas(
+    out_paths: &[PathBuf],
+    areas: &[DeviceRect],
+    opts: &CaptureOptions,
+) -> Result<Vec<SavedImage>, CaptureError> {
+    let primary = primary_path(out_paths)?;
+    ensure_parent_dir(primary)?;
+
+    let src_path = capture_portal_checked(primary, opts)?;
+    let res = save_areas(&src_path, out_paths, areas, opts);
     let _ = fs::remove_file(&src_path);
+    res?;
+    read_all(out_paths)
+}
+
+/// One entry of `capture_screen_to_crops`: the paths a crop is saved to,
+/// the crop, and the watermark stamped into it (in place of `opts`' own).
+pub type CropOutput = (Vec<PathBuf>, DeviceRect, Option<Watermark>);
+
+/// Capture one screenshot and save a crop of it per entry: `crops[i].1`
+/// to the paths in `crops[i].0` (e.g. each output to its own file).
+/// Returns the `SavedImage`s per entry, in order. On failure no entry's
+/// files are left behind.
+pub fn capture_screen_to_crops(
+    crops: &[CropOutput],
+    opts: &CaptureOptions,
+) -> Result<Vec<Vec<SavedImage>>, CaptureError> {
+    let primary = primary_path(crops.first().map(|(paths, ..)| paths.as_slice()).unwrap_or_default())?;
+    ensure_parent_dir(primary)?;
+
+    let src_path = capture_portal_checked(primary, opts)?;
+    let res = image::open(&src_path)
+        .map_err(|e| CaptureError::Failed(format!("open screenshot: {e}")))
+        .and_then(|img| {
+            crops.iter().try_for_each(|(paths, crop, watermark)| {
+                let opts = CaptureOptions { watermark: watermark.clone(), ..opts.clone() };
+                crop_and_write(&img, paths, *crop, &opts)
+            })
+        });
+    let _ = fs::remove_file(&src_path);
+    let res = res.and_then(|()| crops.iter().map(|(paths, ..)| read_all(paths)).collect());
+    if res.is_err() {
+        // All or nothing: the names were free, so whatever exists is ours.
+        for path in crops.iter().flat_map(|(paths, ..)| paths) {
+            let _ = fs::remove_file(path);
+        }
+    }
     res
 }
 
-/// Capture a screenshot, then crop using a `capit_core::Rect`.
-///
-/// Intended for Region selection (once your UI produces a rect).
-pub fn capture_screen_to_rect(out_path: &Path, rect: &Rect) -> Result<(), String> {
-    capture_screen_to_crop(out_path, CaptureCrop::from_rect(rect))
+/// One output on a contact sheet: where it is in the screenshot and the
+/// text drawn under its thumbnail.
+#[derive(Debug, Clone)]
+pub struct SheetTile {
+    pub label: String,
+    pub area: DeviceRect,
 }
 
-/// Internal: call portal Screenshot() and return a temp PNG path on disk.
-///
-/// We always capture “full desktop” here; selection happens via cropping.
-fn capture_portal_to_temp_file(final_out_path: &Path) -> Result<PathBuf, String> {
-    zbus::block_on(async {
-        ensure_parent_dir(final_out_path)?;
-
-        let conn = Connection::session().await.map_err(|e| {
-            // This is the root cause of your:
-            //   dbus session connect: I/O error: No such file or directory (os error 2)
-            // Most commonly: missing XDG_RUNTIME_DIR and/or DBUS_SESSION_BUS_ADDRESS
-            let xdg = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "(unset)".into());
-            let addr =
-                std::env::var("DBUS_SESSION_BUS_ADDRESS").unwrap_or_else(|_| "(unset)".into());
-            format!(
-                "dbus session connect: {e} (XDG_RUNTIME_DIR={xdg}, DBUS_SESSION_BUS_ADDRESS={addr})"
-            )
-        })?;
+/// Capture a screenshot and lay `tiles` out as a labelled grid of
+/// thumbnails (a contact sheet of the whole setup), saved as one image.
+pub fn capture_screen_to_contact_sheet(
+    out_paths: &[PathBuf],
+    tiles: &[SheetTile],
+    opts: &CaptureOptions,
+) -> Result<Vec<SavedImage>, CaptureError> {
+    let primary = primary_path(out_paths)?;
+    ensure_parent_dir(primary)?;
+
+    let src_path = capture_portal_checked(primary, opts)?;
+    let res = image::open(&src_path)
+        .map_err(|e| CaptureError::Failed(format!("open screenshot: {e}")))
+        .and_then(|img| {
+            let sheet = contact_sheet(&img, tiles);
+            write_images(DynamicImage::ImageRgba8(sheet), out_paths, opts, "save contact sheet")
+        });
+    let _ = fs::remove_file(&src_path);
+    res?;
+    read_all(out_paths)
+}
+
+/// Capture a screenshot and return it decoded instead of saving it: the
+/// frozen desktop the region overlay shows and its loupe samples from.
+pub fn capture_desktop_image(opts: &CaptureOptions) -> Result<RgbaImage, CaptureError> {
+    let scratch = std::env::temp_dir().join("capit-frozen.png");
+
+    let src_path = capture_portal_checked(&scratch, opts)?;
+    let res = image::open(&src_path)
+        .map(|img| img.to_rgba8())
+        .map_err(|e| CaptureError::Failed(format!("open screenshot: {e}")));
+    let _ = fs::remove_file(&src_path);
+    res
+}
+
+/// Crop `crop` out of an already captured desktop `image` (see
+/// `capture_desktop_image`) and save it, without another portal round trip.
+pub fn save_image_crop(
+    out_paths: &[PathBuf],
+    image: RgbaImage,
+    crop: DeviceRect,
+    opts: &CaptureOptions,
+) -> Result<Vec<SavedImage>, CaptureError> {
+    let primary = primary_path(out_paths)?;
+    ensure_parent_dir(primary)?;
+
+    let img = DynamicImage::ImageRgba8(image);
+    crop_and_write(&img, out_paths, crop, opts)?;
+    read_all(out_paths)
+}
+
+fn primary_path(out_paths: &[PathBuf]) -> Result<&Path, CaptureError> {
+    out_paths
+        .first()
+        .map(PathBuf::as_path)
+        .ok_or_else(|| CaptureError::Failed("no output path given".into()))
+}
 
+fn read_all(out_paths: &[PathBuf]) -> Result<Vec<SavedImage>, CaptureError> {
+    out_paths.iter().map(|p| SavedImage::read(p)).collect()
+}
+
+/// Format implied by the extension; unknown extensions are written as PNG.
+fn format_for_path(path: &Path) -> ImageFormat {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .and_then(ImageFormat::from_extension)
+        .unwrap_or(ImageFormat::Png)
+}
+
+/// Connect now and make sure the Screenshot portal answers, so the first
+/// capture doesn't wait for xdg-desktop-portal (and its backend) to start.
+/// Returns the portal interface version.
+pub fn prewarm_portal() -> Result<u32, CaptureError> {
+    let res = zbus::block_on(async {
+        let conn = bus::session().await.map_err(session_connect_error)?;
         let screenshot = Proxy::new(&conn, PORTAL_DEST, PORTAL_PATH, SCREENSHOT_IFACE)
             .await
             .map_err(|e| format!("proxy screenshot: {e}"))?;
+        // Reading a property activates the portal service if it isn't running.
+        let version: u32 = screenshot
+            .get_property("version")
+            .await
+            .map_err(|e| format!("portal Screenshot version: {e}"))?;
+        SCREENSHOT_VERSION.store(version, Ordering::Relaxed);
+        Ok(version)
+    });
+    if res.is_err() {
+        invalidate_portal();
+    }
+    res
+}
 
-        let token = new_handle_token();
-        let mut options: HashMap<&str, Value<'_>> = HashMap::new();
-        options.insert("handle_token", Value::from(token.as_str()));
-        options.insert("interactive", Value::from(false));
+/// Forget the connection and what we learned about the portal on it; a
+/// restarted portal may be a different version.
+fn invalidate_portal() {
+    bus::invalidate();
+    SCREENSHOT_VERSION.store(0, Ordering::Relaxed);
+}
 
-        let parent_window = "";
+/// The Screenshot interface version, cached after the first read. A portal
+/// that doesn't report one is treated as version 1.
+async fn screenshot_version(screenshot: &Proxy<'_>) -> u32 {
+    let cached = SCREENSHOT_VERSION.load(Ordering::Relaxed);
+    if cached != 0 {
+        return cached;
+    }
+    let version = match screenshot.get_property::<u32>("version").await {
+        Ok(v) => v.max(1),
+        Err(e) => {
+            warn!("portal Screenshot version: {e}; assuming 1");
+            1
+        }
+    };
+    SCREENSHOT_VERSION.store(version, Ordering::Relaxed);
+    version
+}
 
-        let request_path: OwnedObjectPath = screenshot
-            .call("Screenshot", &(parent_window, options))
-            .await
-            .map_err(|e| format!("portal Screenshot() call failed: {e}"))?;
+fn session_connect_error(e: zbus::Error) -> CaptureError {
+    // This is the root cause of your:
+    //   dbus session connect: I/O error: No such file or directory (os error 2)
+    // Most commonly: missing XDG_RUNTIME_DIR and/or DBUS_SESSION_BUS_ADDRESS
+    let xdg = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "(unset)".into());
+    let addr = std::env::var("DBUS_SESSION_BUS_ADDRESS").unwrap_or_else(|_| "(unset)".into());
+    format!("dbus session connect: {e} (XDG_RUNTIME_DIR={xdg}, DBUS_SESSION_BUS_ADDRESS={addr})")
+        .into()
+}
 
-        let request = Proxy::new(&conn, PORTAL_DEST, request_path.clone(), REQUEST_IFACE)
-            .await
-            .map_err(|e| format!("proxy request: {e}"))?;
+/// Delay before capturing again after a blank frame; long enough for the
+/// compositor to have presented a real one.
+const BLANK_RETRY_DELAY: Duration = Duration::from_millis(300);
+
+/// Grid of pixels sampled (per axis) when checking for a blank frame.
+const BLANK_SAMPLE_GRID: u32 = 16;
+
+/// Channel values at or below this count as black.
+const BLANK_MAX_CHANNEL: u8 = 4;
+
+/// `capture_portal_to_temp_file`, capturing once more when `retry_on_black`
+/// is set and the first frame looks blank. The second frame is kept either
+/// way: a screen that really is black still gets saved. Interactive
+/// captures aren't retried; that would show the portal's picker again.
+fn capture_portal_checked(
+    final_out_path: &Path,
+    opts: &CaptureOptions,
+) -> Result<PathBuf, CaptureError> {
+    let src_path = capture_portal_to_temp_file(final_out_path, opts.interactive)?;
+    if !opts.retry_on_black || opts.interactive || !is_blank_image(&src_path) {
+        return Ok(src_path);
+    }
 
-        let mut stream = request
-            .receive_signal("Response")
-            .await
-            .map_err(|e| format!("receive Response signal: {e}"))?;
+    warn!(
+        "capture: portal returned a blank frame; retrying in {}ms",
+        BLANK_RETRY_DELAY.as_millis()
+    );
+    let _ = fs::remove_file(&src_path);
+    std::thread::sleep(BLANK_RETRY_DELAY);
 
-        let deadline = std::time::Instant::now() + Duration::from_secs(30);
+    let src_path = capture_portal_to_temp_file(final_out_path, false)?;
+    if is_blank_image(&src_path) {
+        warn!("capture: frame still blank after retry; keeping it");
+    }
+    Ok(src_path)
+}
 
-        loop {
-            if std::time::Instant::now() > deadline {
-                return Err("portal request timed out waiting for Response".into());
-            }
+/// True if every pixel on a sample grid is black or fully transparent.
+/// Unreadable files count as not blank; saving reports those properly.
+fn is_blank_image(path: &Path) -> bool {
+    let img = match image::open(path) {
+        Ok(img) => img,
+        Err(_) => return false,
+    };
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return true;
+    }
 
-            let next_signal = stream.next();
-            let timeout = Timer::after(Duration::from_millis(250));
-            pin_mut!(next_signal, timeout);
+    let n = BLANK_SAMPLE_GRID;
+    (0..n).all(|gy| {
+        (0..n).all(|gx| {
+            // Cell centres, so the edges (panels, borders) don't dominate.
+            let x = ((2 * gx + 1) as u64 * w as u64 / (2 * n) as u64) as u32;
+            let y = ((2 * gy + 1) as u64 * h as u64 / (2 * n) as u64) as u32;
+            let [r, g, b, a] = img.get_pixel(x, y).0;
+            a == 0 || (r <= BLANK_MAX_CHANNEL && g <= BLANK_MAX_CHANNEL && b <= BLANK_MAX_CHANNEL)
+        })
+    })
+}
 
-            let msg = match select(next_signal, timeout).await {
-                Either::Left((Some(msg), _)) => msg,
-                Either::Left((None, _)) => {
-                    return Err("portal signal stream ended unexpectedly".into())
-                }
-                Either::Right((_, _)) => continue,
-            };
+/// Internal: call portal Screenshot() and return a temp PNG path on disk.
+///
+/// Non-interactive, this is the full desktop and selection happens via
+/// cropping; interactive, it is whatever the user picked in the portal.
+fn capture_portal_to_temp_file(final_out_path: &Path, interactive: bool) -> Result<PathBuf, CaptureError> {
+    let res = zbus::block_on(portal_screenshot(final_out_path, interactive));
+    if res.is_err() {
+        // A stale connection (bus restarted, portal crashed) gets a fresh one next time.
+        invalidate_portal();
+    }
+    res
+}
 
-            let (response, results): (u32, HashMap<String, OwnedValue>) = msg
-                .body()
-                .deserialize()
-                .map_err(|e| format!("signal decode: {e}"))?;
+async fn portal_screenshot(final_out_path: &Path, interactive: bool) -> Result<PathBuf, CaptureError> {
+    ensure_parent_dir(final_out_path)?;
 
-            if response != 0 {
-                return Err(format!("portal screenshot failed (response={response})"));
-            }
+    let conn = bus::session().await.map_err(session_connect_error)?;
 
-            let uri: &str = results
-                .get("uri")
-                .ok_or_else(|| "portal response missing 'uri'".to_string())?
-                .downcast_ref::<&str>()
-                .map_err(|e| format!("'uri' had unexpected type: {e}"))?;
+    let screenshot = Proxy::new(&conn, PORTAL_DEST, PORTAL_PATH, SCREENSHOT_IFACE)
+        .await
+        .map_err(|e| format!("proxy screenshot: {e}"))?;
 
-            let src_path = uri_to_path(uri)?;
+    let version = screenshot_version(&screenshot).await;
 
-            // Copy portal-produced file into a stable temp path next to the final output.
-            // (The portal temp file may get cleaned up; we want our own.)
-            let tmp_out = temp_output_path(final_out_path);
-            fs::copy(&src_path, &tmp_out)
-                .map_err(|e| format!("copy {src_path:?} -> {tmp_out:?}: {e}"))?;
+    let token = new_handle_token();
+    let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+    options.insert("interactive", Value::from(interactive));
+    // Version 2 hints; older backends may reject keys they don't know.
+    if version >= 2 {
+        // We never show our own dialog, so nothing should block on one.
+        options.insert("modal", Value::from(false));
+        // Let the portal use its stored permission instead of prompting again.
+        options.insert("permission_store_checked", Value::from(true));
+    }
 
-            return Ok(tmp_out);
-        }
-    })
+    let parent_window = "";
+
+    let request_path: OwnedObjectPath = screenshot
+        .call("Screenshot", &(parent_window, options))
+        .await
+        .map_err(|e| format!("portal Screenshot() call failed: {e}"))?;
+
+    // The portal's picker waits on the user; give them time to choose.
+    let timeout = if interactive { INTERACTIVE_TIMEOUT } else { Duration::from_secs(30) };
+    let (response, results) = portal::wait_for_response(&conn, request_path, timeout).await?;
+    if response != 0 {
+        return Err(CaptureError::PortalDenied { response });
+    }
+
+    let uri: &str = results
+        .get("uri")
+        .ok_or_else(|| "portal response missing 'uri'".to_string())?
+        .downcast_ref::<&str>()
+        .map_err(|e| format!("'uri' had unexpected type: {e}"))?;
+
+    let src_path = uri_to_path(uri)?;
+
+    // Copy portal-produced file into a stable temp path next to the final output.
+    // (The portal temp file may get cleaned up; we want our own.)
+    let tmp_out = temp_output_path(final_out_path);
+    fs::copy(&src_path, &tmp_out)
+        .map_err(|e| write_error(e, &tmp_out, &format!("copy {src_path:?} -> {tmp_out:?}")))?;
+
+    Ok(tmp_out)
 }
 
-fn save_cropped_png(src_path: &Path, out_path: &Path, crop: CaptureCrop) -> Result<(), String> {
+fn save_cropped(
+    src_path: &Path,
+    out_paths: &[PathBuf],
+    crop: DeviceRect,
+    opts: &CaptureOptions,
+) -> Result<(), CaptureError> {
     let img = image::open(src_path).map_err(|e| format!("open screenshot: {e}"))?;
+    crop_and_write(&img, out_paths, crop, opts)
+}
+
+/// Write `crop` of `img` to `out_paths`. Only the cropped pixels are
+/// copied, so one screenshot can serve many crops.
+fn crop_and_write(
+    img: &DynamicImage,
+    out_paths: &[PathBuf],
+    crop: DeviceRect,
+    opts: &CaptureOptions,
+) -> Result<(), CaptureError> {
     let (iw, ih) = img.dimensions();
 
     let x = crop.x;
@@ -198,19 +583,215 @@ fn save_cropped_png(src_path: &Path, out_path: &Path, crop: CaptureCrop) -> Resu
     let ch = y1.saturating_sub(y0);
 
     if cw == 0 || ch == 0 {
-        return Err(format!(
+        return Err(CaptureError::Failed(format!(
             "crop rect empty after clamping: ({x},{y}) {w}x{h} within {iw}x{ih}"
-        ));
+        )));
     }
 
     let cropped = img.crop_imm(x0, y0, cw, ch);
-    cropped
-        .save(out_path)
-        .map_err(|e| format!("save cropped screenshot: {e}"))?;
+    write_images(cropped, out_paths, opts, "save cropped screenshot")
+}
+
+fn save_areas(
+    src_path: &Path,
+    out_paths: &[PathBuf],
+    areas: &[DeviceRect],
+    opts: &CaptureOptions,
+) -> Result<(), CaptureError> {
+    let (Some(x0), Some(y0), Some(x1), Some(y1)) = (
+        areas.iter().map(|a| a.x).min(),
+        areas.iter().map(|a| a.y).min(),
+        areas.iter().map(|a| a.x + a.w).max(),
+        areas.iter().map(|a| a.y + a.h).max(),
+    ) else {
+        return Err(CaptureError::Failed("no areas to capture".into()));
+    };
+
+    let img = image::open(src_path).map_err(|e| format!("open screenshot: {e}"))?;
+    let (iw, ih) = img.dimensions();
+
+    let bx = x0.clamp(0, iw as i32);
+    let by = y0.clamp(0, ih as i32);
+    let bw = (x1.clamp(0, iw as i32) - bx) as u32;
+    let bh = (y1.clamp(0, ih as i32) - by) as u32;
+    if bw == 0 || bh == 0 {
+        return Err(CaptureError::Failed(format!(
+            "areas lie outside the {iw}x{ih} screenshot"
+        )));
+    }
+
+    let mut rgba = img.crop_imm(bx as u32, by as u32, bw, bh).to_rgba8();
+    for (x, y, px) in rgba.enumerate_pixels_mut() {
+        let gx = bx + x as i32;
+        let gy = by + y as i32;
+        let covered = areas
+            .iter()
+            .any(|a| gx >= a.x && gy >= a.y && gx < a.x + a.w && gy < a.y + a.h);
+        if !covered {
+            *px = image::Rgba([0, 0, 0, 0]);
+        }
+    }
+
+    write_images(DynamicImage::ImageRgba8(rgba), out_paths, opts, "save screenshot")
+}
+
+// Contact sheet layout: thumbnails fit a 16:9 cell, label underneath.
+const SHEET_CELL_W: u32 = 640;
+const SHEET_CELL_H: u32 = 360;
+const SHEET_PAD: u32 = 24;
+const SHEET_LABEL_SCALE: u32 = 2;
+const SHEET_LABEL_GAP: u32 = 8;
+const SHEET_BACKGROUND: Rgba<u8> = Rgba([0x1A, 0x1C, 0x20, 0xFF]);
+const SHEET_TEXT: Rgba<u8> = Rgba([0xE6, 0xE6, 0xE6, 0xFF]);
+
+/// Grid of `tiles` cut from `img`: as square as possible, row by row, each
+/// thumbnail centred in its cell (never upscaled) with its label below.
+fn contact_sheet(img: &DynamicImage, tiles: &[SheetTile]) -> RgbaImage {
+    let n = tiles.len().max(1) as u32;
+    let cols = (1..=n).find(|c| c * c >= n).unwrap_or(n);
+    let rows = n.div_ceil(cols);
+
+    let label_h = SHEET_LABEL_GAP + font::GLYPH_H * SHEET_LABEL_SCALE;
+    let step_x = SHEET_CELL_W + SHEET_PAD;
+    let step_y = SHEET_CELL_H + label_h + SHEET_PAD;
+    let mut sheet = RgbaImage::from_pixel(SHEET_PAD + cols * step_x, SHEET_PAD + rows * step_y, SHEET_BACKGROUND);
+
+    let (iw, ih) = img.dimensions();
+    for (i, tile) in tiles.iter().enumerate() {
+        let cell_x = SHEET_PAD + (i as u32 % cols) * step_x;
+        let cell_y = SHEET_PAD + (i as u32 / cols) * step_y;
+
+        // Same clamping as a plain crop; an output outside the screenshot
+        // just leaves its cell empty.
+        let a = tile.area;
+        let x0 = (a.x.max(0) as u32).min(iw);
+        let y0 = (a.y.max(0) as u32).min(ih);
+        let w = (a.w.max(0) as u32).min(iw - x0);
+        let h = (a.h.max(0) as u32).min(ih - y0);
+        if w > 0 && h > 0 {
+            let crop = img.crop_imm(x0, y0, w, h);
+            let thumb = if w > SHEET_CELL_W || h > SHEET_CELL_H {
+                crop.resize(SHEET_CELL_W, SHEET_CELL_H, FilterType::Triangle)
+            } else {
+                crop
+            };
+            let (tw, th) = thumb.dimensions();
+            let tx = cell_x + (SHEET_CELL_W - tw) / 2;
+            let ty = cell_y + (SHEET_CELL_H - th) / 2;
+            image::imageops::overlay(&mut sheet, &thumb.to_rgba8(), tx as i64, ty as i64);
+        }
+
+        // Cut the label to the cell width rather than spill into the next one.
+        let max_chars = ((SHEET_CELL_W / SHEET_LABEL_SCALE + 1) / font::ADVANCE) as usize;
+        let label: String = tile.label.chars().take(max_chars).collect();
+        let label_y = cell_y + SHEET_CELL_H + SHEET_LABEL_GAP;
+        font::for_each_pixel(&label, SHEET_LABEL_SCALE, |x, y| {
+            sheet.put_pixel(cell_x + x, label_y + y, SHEET_TEXT);
+        });
+    }
 
+    sheet
+}
+
+/// Apply post-processing (if any) once, then encode `img` to every path.
+fn write_images(
+    img: DynamicImage,
+    out_paths: &[PathBuf],
+    opts: &CaptureOptions,
+    context: &str,
+) -> Result<(), CaptureError> {
+    let img = match &opts.watermark {
+        Some(wm) => {
+            let mut rgba = img.to_rgba8();
+            apply_watermark(&mut rgba, wm);
+            DynamicImage::ImageRgba8(rgba)
+        }
+        None => img,
+    };
+
+    for path in out_paths {
+        encode_to(&img, path, opts.jpeg_quality).map_err(|e| image_write_error(e, path, context))?;
+    }
     Ok(())
 }
 
+fn encode_to(img: &DynamicImage, path: &Path, jpeg_quality: u8) -> image::ImageResult<()> {
+    match format_for_path(path) {
+        ImageFormat::Jpeg => {
+            let file = io::BufWriter::new(fs::File::create(path)?);
+            let encoder = JpegEncoder::new_with_quality(file, jpeg_quality.clamp(1, 100));
+            // JPEG has no alpha channel; the encoder rejects RGBA input.
+            DynamicImage::ImageRgb8(img.to_rgb8()).write_with_encoder(encoder)
+        }
+        ImageFormat::Png | ImageFormat::Webp | ImageFormat::Bmp => img.save(path),
+    }
+}
+
+/// Draw the watermark text into the configured corner.
+///
+/// Text is composited source-over, so it lands correctly on opaque pixels
+/// and on transparent ones (where it keeps its own alpha). A faint offset
+/// shadow keeps light text readable on light content.
+fn apply_watermark(img: &mut RgbaImage, wm: &Watermark) {
+    let (iw, ih) = img.dimensions();
+
+    // Grow the text with the image so it stays legible on large captures.
+    let scale = (iw.min(ih) / 360).clamp(1, 4);
+    let (tw, th) = font::text_size(&wm.text, scale);
+    if tw == 0 {
+        return;
+    }
+
+    let margin = 4 * scale;
+    if tw + scale + 2 * margin > iw || th + scale + 2 * margin > ih {
+        warn!("watermark '{}' does not fit in {iw}x{ih} image; skipping", wm.text);
+        return;
+    }
+
+    let x0 = match wm.corner {
+        Corner::TopLeft | Corner::BottomLeft => margin,
+        Corner::TopRight | Corner::BottomRight => iw - tw - scale - margin,
+    };
+    let y0 = match wm.corner {
+        Corner::TopLeft | Corner::TopRight => margin,
+        Corner::BottomLeft | Corner::BottomRight => ih - th - scale - margin,
+    };
+
+    let alpha = ((wm.colour >> 24) & 0xFF) as f32 / 255.0 * wm.opacity.clamp(0.0, 1.0);
+    let rgb = [
+        ((wm.colour >> 16) & 0xFF) as u8,
+        ((wm.colour >> 8) & 0xFF) as u8,
+        (wm.colour & 0xFF) as u8,
+    ];
+
+    font::for_each_pixel(&wm.text, scale, |x, y| {
+        blend_over(img, x0 + x + scale, y0 + y + scale, [0, 0, 0], alpha * 0.5);
+    });
+    font::for_each_pixel(&wm.text, scale, |x, y| {
+        blend_over(img, x0 + x, y0 + y, rgb, alpha);
+    });
+}
+
+/// Source-over blend of a straight-alpha colour onto a straight-alpha pixel.
+fn blend_over(img: &mut RgbaImage, x: u32, y: u32, rgb: [u8; 3], a: f32) {
+    if x >= img.width() || y >= img.height() || a <= 0.0 {
+        return;
+    }
+
+    let dst = img.get_pixel_mut(x, y);
+    let da = dst[3] as f32 / 255.0;
+    let out_a = a + da * (1.0 - a);
+    if out_a <= 0.0 {
+        return;
+    }
+
+    for i in 0..3 {
+        let c = (rgb[i] as f32 * a + dst[i] as f32 * da * (1.0 - a)) / out_a;
+        dst[i] = c.round().clamp(0.0, 255.0) as u8;
+    }
+    dst[3] = (out_a * 255.0).round().clamp(0.0, 255.0) as u8;
+}
+
 fn temp_output_path(final_out_path: &Path) -> PathBuf {
     // Keep extension as png, but ensure uniqueness-ish.
     // Example: shot.png -> shot.capit_tmp_<nanos>.png
@@ -229,9 +810,10 @@ fn temp_output_path(final_out_path: &Path) -> PathBuf {
     p
 }
 
-fn ensure_parent_dir(path: &Path) -> Result<(), String> {
+fn ensure_parent_dir(path: &Path) -> Result<(), CaptureError> {
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("create dir {parent:?}: {e}"))?;
+        fs::create_dir_all(parent)
+            .map_err(|e| write_error(e, parent, &format!("create dir {parent:?}")))?;
     }
     Ok(())
 }
@@ -303,10 +885,34 @@ fn from_hex(b: u8) -> Option<u8> {
     }
 }
 
-fn new_handle_token() -> String {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos();
-    format!("capit_{now}")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::scratch_path;
+
+    fn blank(name: &str, fill: impl Fn(u32, u32) -> Rgba<u8>) -> bool {
+        let path = scratch_path(name);
+        RgbaImage::from_fn(64, 48, fill).save(&path).unwrap();
+        let blank = is_blank_image(&path);
+        let _ = fs::remove_file(&path);
+        blank
+    }
+
+    #[test]
+    fn black_and_transparent_screenshots_are_blank() {
+        assert!(blank("blank-black", |_, _| Rgba([0, 0, 0, 255])));
+        // Encoder noise just above black still counts.
+        assert!(blank("blank-near-black", |_, _| Rgba([BLANK_MAX_CHANNEL, 0, 2, 255])));
+        assert!(blank("blank-clear", |_, _| Rgba([200, 200, 200, 0])));
+    }
+
+    #[test]
+    fn a_real_screenshot_is_not_blank() {
+        assert!(!blank("blank-grey", |_, _| Rgba([40, 40, 40, 255])));
+        // Dark desktop with one window in the middle.
+        let window = |x: u32, y: u32| (16..48).contains(&x) && (12..36).contains(&y);
+        assert!(!blank("blank-window", |x, y| {
+            if window(x, y) { Rgba([230, 230, 230, 255]) } else { Rgba([0, 0, 0, 255]) }
+        }));
+    }
 }
@@ -0,0 +1,442 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Clipboard ownership for captures.
+//
+// The daemon owns the data source (wlr data-control) on a background thread,
+// so the offer stays valid after the `capit` command that triggered it exits.
+// The thread ends when another client takes the selection (source cancelled).
+// If data-control is unavailable we fall back to `wl-copy`, which forks its
+// own server process for the same reason.
+//
+// `save_image` goes the other way: it reads the current selection's image
+// back out, so a copy can be verified from scripts.
+
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::{AsFd, AsRawFd};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use eventline::{debug, info, warn};
+
+use wayland_client::{
+    event_created_child,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_registry, wl_seat},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+    zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+};
+
+/// How long `save_image` waits for the clipboard owner to send its data.
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// MIME type for a saved capture, from its extension.
+pub fn mime_for_path(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        _ => "image/png",
+    }
+}
+
+/// Put the file at `path` on the clipboard.
+pub fn copy_file(path: &Path) -> Result<(), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+    copy_bytes(bytes, mime_for_path(path))
+}
+
+/// Put `bytes` on the clipboard as `mime`.
+pub fn copy_bytes(bytes: Vec<u8>, mime: &str) -> Result<(), String> {
+    let bytes = Arc::new(bytes);
+    match serve_data_control(bytes.clone(), mime) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            debug!("data-control unavailable ({e}); falling back to wl-copy");
+            copy_with_wl_copy(&bytes, mime)
+        }
+    }
+}
+
+struct SourceState {
+    bytes: Arc<Vec<u8>>,
+    mime: String,
+    cancelled: bool,
+}
+
+fn serve_data_control(bytes: Arc<Vec<u8>>, mime: &str) -> Result<(), String> {
+    let conn = Connection::connect_to_env().map_err(|e| format!("wayland connect: {e}"))?;
+    let (globals, mut queue): (_, EventQueue<SourceState>) =
+        registry_queue_init(&conn).map_err(|e| format!("registry init: {e}"))?;
+    let qh = queue.handle();
+
+    let manager = globals
+        .bind::<ZwlrDataControlManagerV1, _, _>(&qh, 1..=2, ())
+        .map_err(|_| "zwlr_data_control_manager_v1 not available".to_string())?;
+    let seat = globals
+        .bind::<wl_seat::WlSeat, _, _>(&qh, 1..=7, ())
+        .map_err(|_| "wl_seat not available".to_string())?;
+
+    let mut state = SourceState {
+        bytes,
+        mime: mime.to_string(),
+        cancelled: false,
+    };
+
+    let source = manager.create_data_source(&qh, ());
+    source.offer(state.mime.clone());
+
+    let device = manager.get_data_device(&seat, &qh, ());
+    device.set_selection(Some(&source));
+
+    queue
+        .roundtrip(&mut state)
+        .map_err(|e| format!("roundtrip: {e}"))?;
+
+    info!("clipboard: serving {} bytes as {}", state.bytes.len(), state.mime);
+
+    std::thread::Builder::new()
+        .name("capit-clipboard".into())
+        .spawn(move || {
+            while !state.cancelled {
+                if let Err(e) = queue.blocking_dispatch(&mut state) {
+                    warn!("clipboard: dispatch failed: {e}");
+                    break;
+                }
+            }
+            debug!("clipboard: selection replaced, dropping source");
+            source.destroy();
+            device.destroy();
+            drop(conn);
+        })
+        .map_err(|e| format!("spawn clipboard thread: {e}"))?;
+
+    Ok(())
+}
+
+fn copy_with_wl_copy(bytes: &[u8], mime: &str) -> Result<(), String> {
+    let mut child = Command::new("wl-copy")
+        .arg("--type")
+        .arg(mime)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                "no clipboard backend: compositor lacks wlr data-control and wl-copy is not installed"
+                    .to_string()
+            } else {
+                format!("failed to run wl-copy: {e}")
+            }
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(bytes)
+            .map_err(|e| format!("write to wl-copy: {e}"))?;
+    }
+
+    let status = child.wait().map_err(|e| format!("wait for wl-copy: {e}"))?;
+    if !status.success() {
+        return Err(format!("wl-copy exited with {status}"));
+    }
+    Ok(())
+}
+
+/// Write the image on the clipboard to `path`, byte-for-byte as offered.
+/// Prefers PNG when the owner offers several image types.
+/// Returns the MIME type that was saved and the number of bytes.
+pub fn save_image(path: &Path) -> Result<(String, usize), String> {
+    let conn = Connection::connect_to_env().map_err(|e| format!("wayland connect: {e}"))?;
+    let (globals, mut queue): (_, EventQueue<ReceiveState>) =
+        registry_queue_init(&conn).map_err(|e| format!("registry init: {e}"))?;
+    let qh = queue.handle();
+
+    let manager = globals
+        .bind::<ZwlrDataControlManagerV1, _, _>(&qh, 1..=2, ())
+        .map_err(|_| "reading the clipboard needs zwlr_data_control_manager_v1".to_string())?;
+    let seat = globals
+        .bind::<wl_seat::WlSeat, _, _>(&qh, 1..=7, ())
+        .map_err(|_| "wl_seat not available".to_string())?;
+
+    let mut state = ReceiveState { selection: None };
+    let device = manager.get_data_device(&seat, &qh, ());
+
+    // The current selection (offer + its MIME types) is sent right after the
+    // device is created; one roundtrip delivers it.
+    queue
+        .roundtrip(&mut state)
+        .map_err(|e| format!("roundtrip: {e}"))?;
+
+    let result = read_selection_image(&conn, state.selection.as_ref(), path);
+
+    if let Some(offer) = state.selection.take() {
+        offer.destroy();
+    }
+    device.destroy();
+    let _ = conn.flush();
+    result
+}
+
+fn read_selection_image(
+    conn: &Connection,
+    offer: Option<&ZwlrDataControlOfferV1>,
+    path: &Path,
+) -> Result<(String, usize), String> {
+    let offer = offer.ok_or("clipboard is empty")?;
+    let mimes = offer
+        .data::<Mutex<Vec<String>>>()
+        .map(|m| m.lock().unwrap_or_else(|e| e.into_inner()).clone())
+        .unwrap_or_default();
+
+    let mime = mimes
+        .iter()
+        .find(|m| *m == "image/png")
+        .or_else(|| mimes.iter().find(|m| m.starts_with("image/")))
+        .cloned()
+        .ok_or_else(|| format!("clipboard holds no image (offers: {})", mimes.join(", ")))?;
+
+    let (mut reader, writer) = std::io::pipe().map_err(|e| format!("pipe: {e}"))?;
+    offer.receive(mime.clone(), writer.as_fd());
+    conn.flush().map_err(|e| format!("flush: {e}"))?;
+    // Close our write end so EOF arrives when the owner finishes writing.
+    drop(writer);
+
+    let bytes = read_with_deadline(&mut reader, &mime)?;
+    if bytes.is_empty() {
+        return Err(format!("clipboard owner sent no data for {mime}"));
+    }
+
+    std::fs::write(path, &bytes).map_err(|e| format!("write {}: {e}", path.display()))?;
+    info!("clipboard: saved {} bytes of {mime} to {}", bytes.len(), path.display());
+    Ok((mime, bytes.len()))
+}
+
+/// Read the offer pipe to EOF, giving up after `RECEIVE_TIMEOUT`: an owner
+/// that never writes (or never closes its end) must not wedge the daemon.
+fn read_with_deadline(reader: &mut std::io::PipeReader, mime: &str) -> Result<Vec<u8>, String> {
+    let deadline = Instant::now() + RECEIVE_TIMEOUT;
+    let mut bytes = Vec::new();
+    let mut chunk = [0u8; 64 * 1024];
+
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(format!(
+                "clipboard owner didn't send {mime} within {}s",
+                RECEIVE_TIMEOUT.as_secs()
+            ));
+        }
+
+        let mut pfd = libc::pollfd { fd: reader.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        // SAFETY: one valid pollfd for the duration of the call.
+        let ready = unsafe { libc::poll(&mut pfd, 1, left.as_millis().min(i32::MAX as u128) as i32) };
+        if ready < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(format!("poll clipboard: {e}"));
+        }
+        if ready == 0 {
+            continue;
+        }
+
+        // Readable or hung up: this read won't block.
+        match reader.read(&mut chunk) {
+            Ok(0) => return Ok(bytes),
+            Ok(n) => bytes.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("read clipboard: {e}")),
+        }
+    }
+}
+
+struct ReceiveState {
+    selection: Option<ZwlrDataControlOfferV1>,
+}
+
+// ---- wayland plumbing ----
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for SourceState {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for SourceState {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for SourceState {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlManagerV1,
+        _: <ZwlrDataControlManagerV1 as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for SourceState {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        _: zwlr_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // We only own the selection; offers from other clients are ignored.
+    }
+
+    event_created_child!(SourceState, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for SourceState {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlOfferV1,
+        _: <ZwlrDataControlOfferV1 as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, ()> for SourceState {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlSourceV1,
+        event: zwlr_data_control_source_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_source_v1::Event::Send { mime_type, fd } => {
+                if mime_type != state.mime {
+                    debug!("clipboard: ignoring request for {mime_type}");
+                    return;
+                }
+                let mut f = File::from(fd);
+                if let Err(e) = f.write_all(&state.bytes) {
+                    warn!("clipboard: write to requester failed: {e}");
+                }
+            }
+            zwlr_data_control_source_v1::Event::Cancelled => {
+                state.cancelled = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for ReceiveState {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for ReceiveState {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for ReceiveState {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlManagerV1,
+        _: <ZwlrDataControlManagerV1 as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for ReceiveState {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Only the regular selection; the primary selection is ignored.
+        if let zwlr_data_control_device_v1::Event::Selection { id } = event {
+            if let Some(old) = state.selection.take() {
+                old.destroy();
+            }
+            state.selection = id;
+        }
+    }
+
+    event_created_child!(ReceiveState, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, Mutex::new(Vec::new())),
+    ]);
+}
+
+/// Offers collect their MIME types in their user data.
+impl Dispatch<ZwlrDataControlOfferV1, Mutex<Vec<String>>> for ReceiveState {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlOfferV1,
+        event: <ZwlrDataControlOfferV1 as wayland_client::Proxy>::Event,
+        mimes: &Mutex<Vec<String>>,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            mimes.lock().unwrap_or_else(|e| e.into_inner()).push(mime_type);
+        }
+    }
+}
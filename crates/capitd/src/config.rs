@@ -4,22 +4,209 @@
 use std::env;
 use std::path::{Path, PathBuf};
 
+use capit_core::{ImageFormat, PostAction};
 use eventline::warn;
 use rune_cfg::RuneConfig;
 
+use crate::capture::DEFAULT_JPEG_QUALITY;
+use crate::daemon::paths::expand_filename_template;
+use crate::overlay_region::EDGE_SNAP_THRESHOLD;
+
 #[derive(Debug, Clone)]
 pub struct CapitConfig {
     pub screenshot_directory: PathBuf,
+    /// Format used when the client doesn't ask for one (`--format`).
+    pub screenshot_format: ImageFormat,
+    /// File name template (strftime-style tokens plus %i, %o). Empty = `capit-<unixsecs>`.
+    pub filename_template: String,
+    /// JPEG encoder quality, 1..=100.
+    pub jpeg_quality: u8,
     pub accent_colour: u32,          // ARGB
     pub bar_background_colour: u32,  // ARGB
+    pub bar_margins: [i32; 4],       // extra px: top, right, bottom, left
+
+    /// Text stamped into a corner of every capture (time tokens expanded). None = off.
+    pub watermark_text: Option<String>,
+    pub watermark_position: Corner,
+    pub watermark_colour: u32,       // ARGB
+    pub watermark_opacity: f32,      // 0.0..=1.0, multiplied with the colour's alpha
+
+    /// What a capture ends up as when the client doesn't say.
+    pub default_action: PostAction,
+    /// Ask (notification action) before a capture replaces the clipboard.
+    pub confirm_clipboard_overwrite: bool,
+    /// Write `<image>.json` metadata next to each capture.
+    pub sidecar_json: bool,
+
+    /// Editor command the capture is opened in (`PostAction::Edit`); the
+    /// file path is appended.
+    pub editor: Option<String>,
+
+    /// Connect to the screenshot portal at startup instead of on first capture.
+    pub prewarm_portal: bool,
+    /// Capture again (once) when the portal hands back an all-black frame.
+    pub retry_on_black: bool,
+    /// Bind region/screen capture through the GlobalShortcuts portal.
+    pub enable_global_shortcuts: bool,
+
+    /// Screen-mode target when the client doesn't name one.
+    pub screen_default: ScreenDefault,
+    /// Output names left out of all-screens captures and the screen picker.
+    pub exclude_outputs: Vec<String>,
+
+    /// Prune Capit's own captures older than this many days. None = keep forever.
+    pub retention_days: Option<u32>,
+    /// Move pruned captures to the XDG trash instead of deleting them.
+    pub retention_trash: bool,
+
+    /// Cancel the region overlay if another surface takes keyboard focus.
+    pub cancel_on_focus_loss: bool,
+
+    /// Crop a confirmed region to the parts that lie on an output.
+    pub region_crop_to_outputs: bool,
+
+    pub region_border_style: BorderStyle,
+
+    /// Aspect ratio (w / h) region resizes keep by default. None = free.
+    pub region_default_ratio: Option<f64>,
+
+    /// Distance (px) at which a dragged region edge snaps to an output edge
+    /// or grid line; 0 = no edge snapping.
+    pub snap_threshold: i32,
+
+    /// Pitch (px) of the grid region edges snap to; 0 = no grid.
+    pub grid_size: i32,
+
+    /// Grab a still of the desktop before the region overlay opens and show
+    /// a magnifier loupe next to the cursor ('z' toggles it).
+    pub region_loupe: bool,
+
+    /// Show a still of the desktop behind the region overlay and crop the
+    /// region from it, instead of the live desktop and a second capture.
+    pub freeze_on_select: bool,
+
+    /// Overlay background dim alpha, from `capit.region_dim_opacity` /
+    /// `capit.screen_dim_opacity` (percent). None = the overlay's default.
+    pub region_dim_alpha: Option<u8>,
+    pub screen_dim_alpha: Option<u8>,
+
+    /// Region border width and corner handle diameter in px. None = the
+    /// overlay's defaults.
+    pub region_border_thickness: Option<i32>,
+    pub region_handle_size: Option<i32>,
+
+    /// Region border colour (ARGB), apart from the bar's accent. None = accent.
+    pub selection_colour: Option<u32>,
+
+    /// Region handle colours (ARGB). None = selection colour for outer, outer for inner (solid).
+    pub handle_outer_colour: Option<u32>,
+    pub handle_inner_colour: Option<u32>,
+}
+
+/// How the region selection border is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    Solid,
+    Dashed,
+    /// Dashed, with the dashes animated around the edge ("marching ants").
+    Marching,
+}
+
+impl BorderStyle {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "solid" => Ok(BorderStyle::Solid),
+            "dashed" => Ok(BorderStyle::Dashed),
+            "marching" | "marching-ants" | "marching_ants" => Ok(BorderStyle::Marching),
+            other => Err(format!("unknown style '{other}' (expected solid | dashed | marching)")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScreenDefault {
+    All,
+    Focused,
+    Primary,
+    Output(String),
+}
+
+impl ScreenDefault {
+    fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        match s.to_ascii_lowercase().as_str() {
+            "" => Err("empty value (expected all | focused | primary | <output name>)".into()),
+            "all" => Ok(ScreenDefault::All),
+            "focused" => Ok(ScreenDefault::Focused),
+            "primary" => Ok(ScreenDefault::Primary),
+            // Output names are case-sensitive (e.g. "DP-1").
+            _ => Ok(ScreenDefault::Output(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "top-left" | "top_left" => Ok(Corner::TopLeft),
+            "top-right" | "top_right" => Ok(Corner::TopRight),
+            "bottom-left" | "bottom_left" => Ok(Corner::BottomLeft),
+            "bottom-right" | "bottom_right" => Ok(Corner::BottomRight),
+            other => Err(format!(
+                "unknown corner '{other}' (expected top-left | top-right | bottom-left | bottom-right)"
+            )),
+        }
+    }
 }
 
 impl Default for CapitConfig {
     fn default() -> Self {
         Self {
             screenshot_directory: default_screenshot_dir(),
+            screenshot_format: ImageFormat::Png,
+            filename_template: String::new(),
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
             accent_colour: 0xFF0A_84FF,          // default blue
             bar_background_colour: 0xFF0F_1115,  // matches bar default
+            bar_margins: [0; 4],
+            watermark_text: None,
+            watermark_position: Corner::BottomRight,
+            watermark_colour: 0xFFFF_FFFF,
+            watermark_opacity: 0.8,
+            default_action: PostAction::Save,
+            confirm_clipboard_overwrite: false,
+            sidecar_json: false,
+            editor: None,
+            prewarm_portal: false,
+            retry_on_black: false,
+            enable_global_shortcuts: false,
+            screen_default: ScreenDefault::All,
+            exclude_outputs: Vec::new(),
+            retention_days: None,
+            retention_trash: true,
+            cancel_on_focus_loss: false,
+            region_crop_to_outputs: true,
+            region_border_style: BorderStyle::Dashed,
+            region_default_ratio: None,
+            snap_threshold: EDGE_SNAP_THRESHOLD,
+            grid_size: 0,
+            region_loupe: false,
+            freeze_on_select: false,
+            region_dim_alpha: None,
+            screen_dim_alpha: None,
+            region_border_thickness: None,
+            region_handle_size: None,
+            selection_colour: None,
+            handle_outer_colour: None,
+            handle_inner_colour: None,
         }
     }
 }
@@ -80,9 +267,41 @@ fn parse_config(rc: &RuneConfig) -> CapitConfig {
         Err(e) => warn!("config: invalid capit.screenshot_directory ({e}); using default {}", cfg.screenshot_directory.display()),
     }
 
+    // screenshot_format
+    match rc.get_optional::<String>("capit.screenshot_format") {
+        Ok(Some(s)) => match ImageFormat::from_extension(s.trim().trim_start_matches('.')) {
+            Some(f) => cfg.screenshot_format = f,
+            None => warn!("config: invalid capit.screenshot_format '{s}' (expected png | jpg | webp); using default png"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.screenshot_format ({e}); using default png"),
+    }
+
+    // filename_template
+    match rc.get_optional::<String>("capit.filename_template") {
+        Ok(Some(t)) if t.trim().is_empty() => {}
+        Ok(Some(t)) => match expand_filename_template(t.trim(), 0, 1, None) {
+            Ok(_) => cfg.filename_template = t.trim().to_string(),
+            Err(e) => warn!("config: invalid capit.filename_template ({e}); using capit-<timestamp>"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.filename_template ({e}); using capit-<timestamp>"),
+    }
+
+    // jpeg_quality
+    match rc.get_optional::<u32>("capit.jpeg_quality") {
+        Ok(Some(v)) if (1..=100).contains(&v) => cfg.jpeg_quality = v as u8,
+        Ok(Some(v)) => warn!(
+            "config: capit.jpeg_quality must be within 1..=100 (got {v}); using default {}",
+            cfg.jpeg_quality
+        ),
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.jpeg_quality ({e}); using default {}", cfg.jpeg_quality),
+    }
+
     // accent_colour
     match rc.get_optional::<String>("capit.accent_colour") {
-        Ok(Some(colour_str)) => match parse_hex_colour(&colour_str) {
+        Ok(Some(colour_str)) => match parse_colour(&colour_str) {
             Ok(v) => cfg.accent_colour = v,
             Err(e) => warn!("config: invalid capit.accent_colour ({e}); using default 0x{:08X}", cfg.accent_colour),
         },
@@ -92,7 +311,7 @@ fn parse_config(rc: &RuneConfig) -> CapitConfig {
 
     // bar_background_colour
     match rc.get_optional::<String>("capit.bar_background_colour") {
-        Ok(Some(colour_str)) => match parse_hex_colour(&colour_str) {
+        Ok(Some(colour_str)) => match parse_colour(&colour_str) {
             Ok(v) => cfg.bar_background_colour = v,
             Err(e) => warn!(
                 "config: invalid capit.bar_background_colour ({e}); using default 0x{:08X}",
@@ -106,25 +325,413 @@ fn parse_config(rc: &RuneConfig) -> CapitConfig {
         ),
     }
 
+    // bar_margins
+    match rc.get_optional::<String>("capit.bar_margins") {
+        Ok(Some(s)) => match parse_margins(&s) {
+            Ok(m) => cfg.bar_margins = m,
+            Err(e) => warn!("config: invalid capit.bar_margins ({e}); using default 0"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.bar_margins ({e}); using default 0"),
+    }
+
+    // watermark_text
+    match rc.get_optional::<String>("capit.watermark_text") {
+        Ok(Some(text)) => {
+            if text.trim().is_empty() {
+                warn!("config: capit.watermark_text is empty; watermark disabled");
+            } else {
+                cfg.watermark_text = Some(text);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.watermark_text ({e}); watermark disabled"),
+    }
+
+    // watermark_position
+    match rc.get_optional::<String>("capit.watermark_position") {
+        Ok(Some(pos)) => match Corner::parse(&pos) {
+            Ok(c) => cfg.watermark_position = c,
+            Err(e) => warn!("config: invalid capit.watermark_position ({e}); using default bottom-right"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.watermark_position ({e}); using default bottom-right"),
+    }
+
+    // watermark_colour
+    match rc.get_optional::<String>("capit.watermark_colour") {
+        Ok(Some(colour_str)) => match parse_colour(&colour_str) {
+            Ok(v) => cfg.watermark_colour = v,
+            Err(e) => warn!("config: invalid capit.watermark_colour ({e}); using default 0x{:08X}", cfg.watermark_colour),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.watermark_colour ({e}); using default 0x{:08X}", cfg.watermark_colour),
+    }
+
+    // watermark_opacity
+    match rc.get_optional::<f64>("capit.watermark_opacity") {
+        Ok(Some(v)) if (0.0..=1.0).contains(&v) => cfg.watermark_opacity = v as f32,
+        Ok(Some(v)) => warn!(
+            "config: capit.watermark_opacity must be within 0.0..=1.0 (got {v}); using default {}",
+            cfg.watermark_opacity
+        ),
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.watermark_opacity ({e}); using default {}", cfg.watermark_opacity),
+    }
+
+    // save_and_copy (older spelling of default_action = "save_copy")
+    match rc.get_optional::<bool>("capit.save_and_copy") {
+        Ok(Some(true)) => cfg.default_action = PostAction::SaveCopy,
+        Ok(_) => {}
+        Err(e) => warn!("config: invalid capit.save_and_copy ({e}); ignoring"),
+    }
+
+    // confirm_clipboard_overwrite
+    match rc.get_optional::<bool>("capit.confirm_clipboard_overwrite") {
+        Ok(Some(v)) => cfg.confirm_clipboard_overwrite = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.confirm_clipboard_overwrite ({e}); using default {}", cfg.confirm_clipboard_overwrite),
+    }
+
+    // sidecar_json
+    match rc.get_optional::<bool>("capit.sidecar_json") {
+        Ok(Some(v)) => cfg.sidecar_json = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.sidecar_json ({e}); using default {}", cfg.sidecar_json),
+    }
+
+    // editor
+    match rc.get_optional::<String>("capit.editor") {
+        Ok(Some(cmd)) => {
+            if cmd.trim().is_empty() {
+                warn!("config: capit.editor is empty; ignoring");
+            } else {
+                cfg.editor = Some(cmd);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.editor ({e}); ignoring"),
+    }
+
+    // edit_then_finalize (older spelling of default_action = "edit")
+    match rc.get_optional::<bool>("capit.edit_then_finalize") {
+        Ok(Some(true)) => cfg.default_action = PostAction::Edit,
+        Ok(_) => {}
+        Err(e) => warn!("config: invalid capit.edit_then_finalize ({e}); ignoring"),
+    }
+
+    // default_action (wins over the older keys above)
+    match rc.get_optional::<String>("capit.default_action") {
+        Ok(Some(v)) => match PostAction::parse(&v) {
+            Ok(a) => cfg.default_action = a,
+            Err(e) => warn!("config: invalid capit.default_action ({e}); using {:?}", cfg.default_action),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.default_action ({e}); using {:?}", cfg.default_action),
+    }
+    if cfg.default_action == PostAction::Edit && cfg.editor.is_none() {
+        warn!("config: capit.default_action is edit but capit.editor is not set; saving without editing");
+        cfg.default_action = PostAction::Save;
+    }
+
+    // prewarm_portal
+    match rc.get_optional::<bool>("capit.prewarm_portal") {
+        Ok(Some(v)) => cfg.prewarm_portal = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.prewarm_portal ({e}); using default {}", cfg.prewarm_portal),
+    }
+
+    // retry_on_black
+    match rc.get_optional::<bool>("capit.retry_on_black") {
+        Ok(Some(v)) => cfg.retry_on_black = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.retry_on_black ({e}); using default {}", cfg.retry_on_black),
+    }
+
+    // enable_global_shortcuts
+    match rc.get_optional::<bool>("capit.enable_global_shortcuts") {
+        Ok(Some(v)) => cfg.enable_global_shortcuts = v,
+        Ok(None) => {}
+        Err(e) => warn!(
+            "config: invalid capit.enable_global_shortcuts ({e}); using default {}",
+            cfg.enable_global_shortcuts
+        ),
+    }
+
+    // screen_default
+    match rc.get_optional::<String>("capit.screen_default") {
+        Ok(Some(v)) => match ScreenDefault::parse(&v) {
+            Ok(d) => cfg.screen_default = d,
+            Err(e) => warn!("config: invalid capit.screen_default ({e}); using default all"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.screen_default ({e}); using default all"),
+    }
+
+    // exclude_outputs: "HDMI-A-1, DP-3" (comma and/or space separated)
+    match rc.get_optional::<String>("capit.exclude_outputs") {
+        Ok(Some(s)) => {
+            cfg.exclude_outputs = s
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|n| !n.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.exclude_outputs ({e}); excluding nothing"),
+    }
+
+    // retention_days (0 = off)
+    match rc.get_optional::<u32>("capit.retention_days") {
+        Ok(Some(0)) => cfg.retention_days = None,
+        Ok(Some(d)) => cfg.retention_days = Some(d),
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.retention_days ({e}); retention disabled"),
+    }
+
+    // retention_trash
+    match rc.get_optional::<bool>("capit.retention_trash") {
+        Ok(Some(v)) => cfg.retention_trash = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.retention_trash ({e}); using default {}", cfg.retention_trash),
+    }
+
+    // cancel_on_focus_loss
+    match rc.get_optional::<bool>("capit.cancel_on_focus_loss") {
+        Ok(Some(v)) => cfg.cancel_on_focus_loss = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.cancel_on_focus_loss ({e}); using default {}", cfg.cancel_on_focus_loss),
+    }
+
+    // region_crop_to_outputs
+    match rc.get_optional::<bool>("capit.region_crop_to_outputs") {
+        Ok(Some(v)) => cfg.region_crop_to_outputs = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.region_crop_to_outputs ({e}); using default {}", cfg.region_crop_to_outputs),
+    }
+
+    // region_border_style
+    match rc.get_optional::<String>("capit.region_border_style") {
+        Ok(Some(v)) => match BorderStyle::parse(&v) {
+            Ok(s) => cfg.region_border_style = s,
+            Err(e) => warn!("config: invalid capit.region_border_style ({e}); using default dashed"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.region_border_style ({e}); using default dashed"),
+    }
+
+    // region_default_ratio
+    match rc.get_optional::<String>("capit.region_default_ratio") {
+        Ok(Some(v)) => match parse_ratio(&v) {
+            Ok(r) => cfg.region_default_ratio = r,
+            Err(e) => warn!("config: invalid capit.region_default_ratio ({e}); using default none"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.region_default_ratio ({e}); using default none"),
+    }
+
+    // snap_threshold / grid_size
+    match rc.get_optional::<u32>("capit.snap_threshold") {
+        Ok(Some(v)) if v <= 64 => cfg.snap_threshold = v as i32,
+        Ok(Some(v)) => warn!(
+            "config: capit.snap_threshold must be within 0..=64 (got {v}); using default {}",
+            cfg.snap_threshold
+        ),
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.snap_threshold ({e}); using default {}", cfg.snap_threshold),
+    }
+    match rc.get_optional::<u32>("capit.grid_size") {
+        Ok(Some(v)) if v <= 4096 => cfg.grid_size = v as i32,
+        Ok(Some(v)) => warn!("config: capit.grid_size must be within 0..=4096 (got {v}); using no grid"),
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.grid_size ({e}); using no grid"),
+    }
+
+    // region_loupe
+    match rc.get_optional::<bool>("capit.region_loupe") {
+        Ok(Some(v)) => cfg.region_loupe = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.region_loupe ({e}); using default {}", cfg.region_loupe),
+    }
+
+    // freeze_on_select
+    match rc.get_optional::<bool>("capit.freeze_on_select") {
+        Ok(Some(v)) => cfg.freeze_on_select = v,
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.freeze_on_select ({e}); using default {}", cfg.freeze_on_select),
+    }
+
+    // region_dim_opacity / screen_dim_opacity
+    cfg.region_dim_alpha = read_dim_opacity(rc, "capit.region_dim_opacity");
+    cfg.screen_dim_alpha = read_dim_opacity(rc, "capit.screen_dim_opacity");
+
+    // region_border_thickness / region_handle_size
+    cfg.region_border_thickness = read_px(rc, "capit.region_border_thickness", 1..=32);
+    cfg.region_handle_size = read_px(rc, "capit.region_handle_size", 4..=96);
+
+    // selection_colour
+    match rc.get_optional::<String>("capit.selection_colour") {
+        Ok(Some(colour_str)) => match parse_colour(&colour_str) {
+            Ok(v) => cfg.selection_colour = Some(v),
+            Err(e) => warn!("config: invalid capit.selection_colour ({e}); using accent colour"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.selection_colour ({e}); using accent colour"),
+    }
+
+    // handle_outer_colour / handle_inner_colour
+    match rc.get_optional::<String>("capit.handle_outer_colour") {
+        Ok(Some(colour_str)) => match parse_colour(&colour_str) {
+            Ok(v) => cfg.handle_outer_colour = Some(v),
+            Err(e) => warn!("config: invalid capit.handle_outer_colour ({e}); using selection colour"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.handle_outer_colour ({e}); using selection colour"),
+    }
+
+    match rc.get_optional::<String>("capit.handle_inner_colour") {
+        Ok(Some(colour_str)) => match parse_colour(&colour_str) {
+            Ok(v) => cfg.handle_inner_colour = Some(v),
+            Err(e) => warn!("config: invalid capit.handle_inner_colour ({e}); using solid handles"),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("config: invalid capit.handle_inner_colour ({e}); using solid handles"),
+    }
+
     cfg
 }
 
-fn parse_hex_colour(s: &str) -> Result<u32, String> {
+/// Least dimming a config may ask for (percent): any lighter and the
+/// selection barely stands out from the rest of the screen.
+const MIN_DIM_PERCENT: u32 = 10;
+
+/// A pixel size within `range`, or None (the default) when unset or invalid.
+fn read_px(rc: &RuneConfig, key: &str, range: std::ops::RangeInclusive<u32>) -> Option<i32> {
+    match rc.get_optional::<u32>(key) {
+        Ok(Some(v)) if range.contains(&v) => Some(v as i32),
+        Ok(Some(v)) => {
+            warn!(
+                "config: {key} must be within {}..={} (got {v}); using the default",
+                range.start(),
+                range.end()
+            );
+            None
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!("config: invalid {key} ({e}); using the default");
+            None
+        }
+    }
+}
+
+/// Dim percentage (0–100, like `--dim`) at `key` as an alpha, raised to
+/// `MIN_DIM_PERCENT`. None when unset or invalid (the overlay's default).
+fn read_dim_opacity(rc: &RuneConfig, key: &str) -> Option<u8> {
+    match rc.get_optional::<u32>(key) {
+        Ok(Some(v)) if v <= 100 => {
+            if v < MIN_DIM_PERCENT {
+                warn!("config: {key} = {v} is too light to see the selection; using {MIN_DIM_PERCENT}");
+            }
+            let pct = v.max(MIN_DIM_PERCENT);
+            Some(((pct * 255 + 50) / 100) as u8)
+        }
+        Ok(Some(v)) => {
+            warn!("config: {key} must be within 0..=100 (got {v}); using default");
+            None
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!("config: invalid {key} ({e}); using default");
+            None
+        }
+    }
+}
+
+/// Names accepted in place of hex, with their CSS values.
+const NAMED_COLOURS: &[(&str, u32)] = &[
+    ("blue", 0xFF00_00FF),
+    ("red", 0xFFFF_0000),
+    ("green", 0xFF00_8000),
+    ("orange", 0xFFFF_A500),
+    ("purple", 0xFF80_0080),
+    ("white", 0xFFFF_FFFF),
+    ("black", 0xFF00_0000),
+];
+
+/// A colour name from `NAMED_COLOURS` (any case), "#RRGGBB" or "#AARRGGBB".
+fn parse_colour(s: &str) -> Result<u32, String> {
     let s = s.trim();
 
-    if !s.starts_with('#') {
-        return Err("colour must start with #".into());
+    if let Some(&(_, argb)) = NAMED_COLOURS.iter().find(|(name, _)| s.eq_ignore_ascii_case(name)) {
+        return Ok(argb);
     }
 
-    let hex = &s[1..];
+    let Some(hex) = s.strip_prefix('#') else {
+        let names: Vec<&str> = NAMED_COLOURS.iter().map(|(name, _)| *name).collect();
+        return Err(format!(
+            "unknown colour {s:?}; use one of {} or #RRGGBB / #AARRGGBB",
+            names.join(", ")
+        ));
+    };
+
+    let alpha = match hex.len() {
+        6 => 0xFF00_0000,
+        8 => 0,
+        3 | 4 => return Err("shorthand colours aren't supported; use #RRGGBB or #AARRGGBB".into()),
+        _ => return Err("colour must be 6 or 8 hex digits (#RRGGBB or #AARRGGBB)".into()),
+    };
 
-    if hex.len() != 6 {
-        return Err("colour must be 6 hex digits (RRGGBB)".into());
+    // from_str_radix alone would also take a leading '+'.
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err("invalid hex colour".into());
     }
 
-    let rgb = u32::from_str_radix(hex, 16).map_err(|_| "invalid hex colour".to_string())?;
+    let value = u32::from_str_radix(hex, 16).map_err(|_| "invalid hex colour".to_string())?;
 
-    Ok(0xFF00_0000 | rgb)
+    Ok(alpha | value)
+}
+
+/// "W:H" (e.g. "16:9"), a plain number ("1.5"), or "none"/"" for no lock.
+fn parse_ratio(s: &str) -> Result<Option<f64>, String> {
+    let s = s.trim();
+    if s.is_empty() || s.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+
+    let num = |p: &str| {
+        p.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v > 0.0)
+            .ok_or_else(|| format!("'{}' is not a positive number", p.trim()))
+    };
+
+    let ratio = match s.split_once(':') {
+        Some((w, h)) => num(w)? / num(h)?,
+        None => num(s)?,
+    };
+    Ok(Some(ratio))
+}
+
+/// "N" (all edges) or "top right bottom left", whitespace or comma separated.
+fn parse_margins(s: &str) -> Result<[i32; 4], String> {
+    let vals = s
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            p.parse::<i32>()
+                .ok()
+                .filter(|v| *v >= 0)
+                .ok_or_else(|| format!("'{p}' is not a non-negative integer"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match vals.as_slice() {
+        [all] => Ok([*all; 4]),
+        [t, r, b, l] => Ok([*t, *r, *b, *l]),
+        _ => Err("expected 1 value or 4 values (top right bottom left)".into()),
+    }
 }
 
 fn expand_env(s: &str) -> PathBuf {
@@ -175,3 +782,44 @@ fn default_screenshot_dir() -> PathBuf {
     let home = env::var("HOME").unwrap_or_else(|_| ".".into());
     PathBuf::from(home).join("Pictures").join("Screenshots")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_colour, parse_ratio};
+
+    #[test]
+    fn hex_colours_with_and_without_alpha() {
+        assert_eq!(parse_colour("#80FF0000"), Ok(0x80FF_0000));
+        assert_eq!(parse_colour("#ff0000"), Ok(0xFFFF_0000));
+        assert_eq!(parse_colour(" #00000000 "), Ok(0));
+
+        assert!(parse_colour("#f00").unwrap_err().contains("shorthand"));
+        assert!(parse_colour("#8f00").unwrap_err().contains("shorthand"));
+        assert!(parse_colour("ff0000").is_err());
+        assert!(parse_colour("#ff00zz").is_err());
+        assert!(parse_colour("#ff00000").is_err());
+    }
+
+    #[test]
+    fn named_colours() {
+        assert_eq!(parse_colour("blue"), Ok(0xFF00_00FF));
+        assert_eq!(parse_colour(" Orange "), Ok(0xFFFF_A500));
+        assert_eq!(parse_colour("BLACK"), Ok(0xFF00_0000));
+
+        let err = parse_colour("teal").unwrap_err();
+        assert!(err.contains("blue, red, green, orange, purple, white, black"), "{err}");
+        assert!(parse_colour("#blue").is_err());
+    }
+
+    #[test]
+    fn ratios() {
+        assert_eq!(parse_ratio("16:9"), Ok(Some(16.0 / 9.0)));
+        assert_eq!(parse_ratio(" 1.5 "), Ok(Some(1.5)));
+        assert_eq!(parse_ratio("none"), Ok(None));
+        assert_eq!(parse_ratio(""), Ok(None));
+
+        for bad in ["0:1", "1:0", "16/0", "abc", "-4:3", "4:", ":3", "inf"] {
+            assert!(parse_ratio(bad).is_err(), "{bad:?}");
+        }
+    }
+}
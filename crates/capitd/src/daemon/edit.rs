@@ -0,0 +1,60 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Editor round trip for the edit action (`capit.default_action`, `--edit`):
+// open the saved capture in `capit.editor`, wait for it to exit on a worker
+// thread, then finish the save with whatever is on disk. The daemon keeps
+// serving requests meanwhile.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use eventline::{info, warn};
+
+/// Open `path` in `editor` and call `finalize(modified)` once it exits.
+///
+/// `finalize` also runs when the editor can't be started or exits with an
+/// error: the capture is already saved and shouldn't be left half
+/// finished (no clipboard copy, no sidecar) because of the editor.
+pub fn edit_then(editor: &str, path: PathBuf, finalize: impl FnOnce(bool) + Send + 'static) {
+    let editor = editor.to_string();
+
+    let spawned = std::thread::Builder::new()
+        .name("capit-edit".into())
+        .spawn(move || {
+            let before = mtime(&path);
+            run_editor(&editor, &path);
+            let modified = mtime(&path) != before;
+            if modified {
+                info!("edit: {} was modified", path.display());
+            } else {
+                info!("edit: {} unchanged", path.display());
+            }
+            finalize(modified);
+        });
+
+    if let Err(e) = spawned {
+        // The closure went down with the failed spawn; nothing left to finish.
+        warn!("spawn editor thread: {e}; capture saved without editing");
+    }
+}
+
+fn run_editor(editor: &str, path: &Path) {
+    let mut parts = editor.split_whitespace();
+    let Some(program) = parts.next() else {
+        warn!("edit: capit.editor is empty");
+        return;
+    };
+
+    info!("edit: opening {} in {program}", path.display());
+    match Command::new(program).args(parts).arg(path).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!("edit: {program} exited with {status}"),
+        Err(e) => warn!("edit: run {program}: {e}"),
+    }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
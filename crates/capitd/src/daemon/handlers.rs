@@ -1,75 +1,40 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use capit_core::{Mode, OutputInfo, Target};
-use capit_ipc::{Event, Request, Response};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use capit_core::{desktop_bounds, DeviceRect, ErrorCode, ImageFormat, Mode, OutputInfo, PostAction, Rect, Target};
+use capit_ipc::{CaptureOptions, Event, Request, Response, IPC_VERSION};
+use image::RgbaImage;
 
 use eventline::{debug, error, info, warn};
 
-use crate::{capture, overlay_region, overlay_screen, selection::SelectionState};
+use crate::config::{CapitConfig, ScreenDefault};
+use crate::{capture, clipboard, overlay_region, overlay_screen, selection::SelectionState, wallpaper, wayland_outputs};
 
+use super::edit;
+use super::last_region;
 use super::notify;
-use super::paths::default_output_path;
+use super::sink::EventSink;
+use super::retention;
+use super::sidecar::{self, CaptureMeta};
+use super::paths::{
+    expand_tokens, output_dir_from_cfg, output_paths, per_output_paths, prepare_output_dir, resolve_output_file,
+    stdout_temp_path, unix_now,
+};
 use super::state::DaemonState;
 
 pub fn handle_request(
     state: &mut DaemonState,
     selection: &mut SelectionState,
-    conn: &mut capit_ipc::ClientConn,
+    conn: &mut dyn EventSink,
     req: Request,
 ) -> Response {
     // StartCapture FIRST
-    if let Request::StartCapture { mode, target, with_ui } = req {
-        info!(
-            "StartCapture: mode={:?} target={:?} with_ui={}",
-            mode, target, with_ui
-        );
-
-        return match mode {
-            Mode::Region => {
-                state.active_job = Some(Mode::Region);
-                let _ = conn.send_event(Event::CaptureStarted { mode: Mode::Region });
-
-                let target_output_idx = match determine_output_index(&state.outputs, target) {
-                    Ok(idx) => idx,
-                    Err(msg) => {
-                        error!("determine_output_index failed: {}", msg);
-                        state.active_job = None;
-                        let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
-                        let _ = notify::notify_failed(&msg);
-                        return Response::Error { message: msg };
-                    }
-                };
-
-                handle_region_overlay_capture(state, conn, target_output_idx)
-            }
-
-            Mode::Screen => handle_screen_overlay_capture(state, conn, target),
-
-            Mode::Window => {
-                state.active_job = Some(Mode::Window);
-                let _ = conn.send_event(Event::CaptureStarted { mode: Mode::Window });
-
-                let msg = String::from(
-                    "window capture is not implemented yet.\n\
-                     planned backends: sway (ipc tree), hyprland (hyprctl), niri (ipc).",
-                );
-
-                warn!("{msg}");
-                let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
-                let _ = notify::notify_failed(&msg);
-
-                state.active_job = None;
-                Response::Error { message: msg }
-            }
-
-            Mode::Record => {
-                let msg = "record not implemented yet".to_string();
-                let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
-                let _ = notify::notify_failed(&msg);
-                Response::Error { message: msg }
-            }
-        };
+    if let Request::StartCapture { mode, target, with_ui, options } = req {
+        return start_capture(state, conn, mode, target, with_ui, options);
     }
 
     // SetSelection / ConfirmSelection (selection-driven UI flow)
@@ -88,38 +53,45 @@ pub fn handle_request(
                                 Some(r) => r,
                                 None => {
                                     let msg = "no selection rect set".to_string();
+                                    state.record_error(&msg);
                                     let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
                                     let _ = notify::notify_failed(&msg);
                                     state.active_job = None;
-                                    return Response::Error { message: msg };
+                                    return Response::Error { code: ErrorCode::InvalidRequest, message: msg };
                                 }
                             };
 
-                            let out_path = default_output_path(&state.cfg, "png");
-                            let result = capture::capture_screen_to_rect(&out_path, &rect);
+                            if !countdown(conn, sel.options.delay_secs) {
+                                return send_cancelled(state, conn);
+                            }
+
+                            let (out_paths, index) = output_paths(&state.cfg, &sel.options, None);
+                            let opts = capture_options(&state.cfg, None, index);
+                            let result = capture::capture_screen_to_crop(&out_paths, portal_area(&state.outputs, &rect), &opts);
 
                             match result {
-                                Ok(()) => {
-                                    let _ = conn.send_event(Event::CaptureFinished {
-                                        path: out_path.display().to_string(),
-                                    });
-                                    let _ = notify::notify_saved(&out_path);
+                                Ok(saved) => {
+                                    let meta = CaptureMeta::for_rect(Mode::Region, rect, &state.outputs);
+                                    finalize_capture(state, conn, &sel.options, &meta, &out_paths, &saved);
                                     state.active_job = None;
                                 }
-                                Err(msg) => {
+                                Err(e) => {
+                                    let msg = e.to_string();
+                                    state.record_error(&msg);
                                     let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
-                                    let _ = notify::notify_failed(&msg);
+                                    notify_capture_error(&e);
                                     state.active_job = None;
-                                    return Response::Error { message: msg };
+                                    return Response::Error { code: e.code(), message: msg };
                                 }
                             }
                         }
                         other => {
                             let msg = format!("ConfirmSelection for {other:?} not implemented yet");
+                            state.record_error(&msg);
                             let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
                             let _ = notify::notify_failed(&msg);
                             state.active_job = None;
-                            return Response::Error { message: msg };
+                            return Response::Error { code: ErrorCode::NotImplemented, message: msg };
                         }
                     }
                 }
@@ -135,25 +107,65 @@ pub fn handle_request(
         Request::Status => Response::Status {
             running: true,
             active_job: state.active_job,
+            last_saved: state.last_saved.clone(),
+            last_error: state.last_error.clone(),
+            captures_this_session: state.captures_this_session,
         },
 
-        Request::ListOutputs => Response::Outputs {
-            outputs: state.outputs.clone(),
+        Request::Ping => Response::Pong {
+            uptime_secs: state.started.elapsed().as_secs(),
         },
 
+        Request::ListOutputs => {
+            // Same retry as captures, so `capit outputs` and a capture agree.
+            if let Err(msg) = ensure_outputs(state) {
+                warn!("{msg}");
+            }
+            Response::Outputs {
+                outputs: state.outputs.clone(),
+                desktop_bounds: desktop_bounds(&state.outputs),
+                excluded: state
+                    .outputs
+                    .iter()
+                    .filter(|o| is_excluded(&state.cfg, o))
+                    .filter_map(|o| o.name.clone())
+                    .collect(),
+            }
+        }
+
         Request::GetUiConfig => Response::UiConfig {
             cfg: state.ui.to_ipc(),
         },
 
+        Request::GetCapabilities => Response::Capabilities {
+            window_capture: window_capture_supported(),
+            record: unsupported_reason(Mode::Record).is_none(),
+            formats: ImageFormat::ALL.iter().map(|f| f.extension().to_string()).collect(),
+        },
+
+        Request::GetVersion => Response::Version {
+            daemon_version: env!("CARGO_PKG_VERSION").to_string(),
+            ipc_version: IPC_VERSION,
+        },
+
+        Request::GetPaths => Response::Paths {
+            socket: state.socket_path.display().to_string(),
+            log: state.log_path.display().to_string(),
+            output_dir: output_dir_from_cfg(&state.cfg).display().to_string(),
+        },
+
         Request::StartCapture { .. } => Response::Error {
+            code: ErrorCode::InvalidRequest,
             message: "Internal error: StartCapture not handled properly".into(),
         },
 
         Request::SetSelection { .. } => Response::Error {
+            code: ErrorCode::InvalidRequest,
             message: "SetSelection without an active UI session".into(),
         },
 
         Request::ConfirmSelection => Response::Error {
+            code: ErrorCode::InvalidRequest,
             message: "ConfirmSelection without an active UI session".into(),
         },
 
@@ -161,41 +173,331 @@ pub fn handle_request(
             state.active_job = None;
             Response::Ok
         }
+
+        Request::Echo { payload } => Response::Echo { payload },
+
+        Request::ScheduleCapture { at_unix, spec } => {
+            // A little slack for clock skew between parse and send.
+            if at_unix < unix_now() - 5 {
+                return Response::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: "scheduled time is in the past".into(),
+                };
+            }
+            let id = state.scheduler.add(at_unix, spec);
+            info!("scheduled capture #{id} at unix {at_unix}");
+            Response::Scheduled { id, at_unix }
+        }
+
+        Request::ListScheduled => Response::ScheduledList {
+            jobs: state.scheduler.list(),
+        },
+
+        Request::CancelScheduled { id } => {
+            if state.scheduler.cancel(id) {
+                info!("scheduled capture #{id} cancelled");
+                Response::Ok
+            } else {
+                Response::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!("no scheduled capture #{id}"),
+                }
+            }
+        }
+
+        Request::SaveClipboard { path } => match clipboard::save_image(&path) {
+            Ok((mime, bytes)) => Response::ClipboardSaved {
+                path: path.display().to_string(),
+                mime,
+                bytes: bytes as u64,
+            },
+            Err(message) => {
+                warn!("clipboard-save failed: {message}");
+                Response::Error { code: ErrorCode::Clipboard, message }
+            }
+        },
     }
 }
 
-fn handle_region_overlay_capture(
+pub fn start_capture(
     state: &mut DaemonState,
-    conn: &mut capit_ipc::ClientConn,
-    target_output_idx: usize,
+    conn: &mut dyn EventSink,
+    mode: Mode,
+    target: Option<Target>,
+    with_ui: bool,
+    mut options: CaptureOptions,
 ) -> Response {
-    let accent = state.ui.accent_colour;
+    info!(
+        "StartCapture: mode={:?} target={:?} with_ui={}",
+        mode, target, with_ui
+    );
 
-    match overlay_region::run_region_overlay(state.outputs.clone(), target_output_idx, accent) {
-        Ok(Some(rect)) => {
-            info!("overlay confirmed: {:?}", rect);
+    // --output-file -: capture into a scratch file, streamed back when done.
+    if options.to_stdout {
+        let format = options.formats.first().copied().unwrap_or(state.cfg.screenshot_format);
+        options.output_file = Some(stdout_temp_path(format));
+    }
+
+    // --output-file first: a directory there becomes output_dir, checked next.
+    if let Err((code, msg)) = resolve_output_file(&mut options) {
+        error!("{msg}");
+        state.record_error(&msg);
+        let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+        let _ = if code == ErrorCode::Storage {
+            notify::notify_storage_failed(&msg)
+        } else {
+            notify::notify_failed(&msg)
+        };
+        return Response::Error { code, message: msg };
+    }
 
-            let out_path = default_output_path(&state.cfg, "png");
-            info!("capturing to: {}", out_path.display());
+    // Fail fast on a bad --output-directory, before any overlay is shown.
+    if let Some(dir) = options.output_dir.as_deref()
+        && let Err(msg) = prepare_output_dir(dir)
+    {
+        error!("{msg}");
+        state.record_error(&msg);
+        let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+        let _ = notify::notify_storage_failed(&msg);
+        return Response::Error { code: ErrorCode::Storage, message: msg };
+    }
+
+    // Overlays and crops need the output layout.
+    if matches!(mode, Mode::Region | Mode::Screen | Mode::Pick)
+        && let Err(msg) = ensure_outputs(state)
+    {
+        error!("{msg}");
+        state.record_error(&msg);
+        let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+        let _ = notify::notify_failed(&msg);
+        return Response::Error { code: ErrorCode::NoOutputs, message: msg };
+    }
 
-            match capture::capture_screen_to_rect(&out_path, &rect) {
-                Ok(()) => {
-                    info!("capture successful");
-                    let _ = conn.send_event(Event::CaptureFinished {
-                        path: out_path.display().to_string(),
-                    });
-                    let _ = notify::notify_saved(&out_path);
+    match mode {
+        Mode::Region => {
+            state.active_job = Some(Mode::Region);
+            let _ = conn.send_event(Event::CaptureStarted { mode: Mode::Region });
+
+            // No overlay when the rect is already chosen: a global Target::Rect,
+            // or --geometry relative to the target output.
+            let geometry = match &target {
+                Some(Target::Rect(r)) => Some((None, *r)),
+                t => options.geometry.map(|g| (t.as_ref(), g)),
+            };
+            if options.repeat_last {
+                let Some(rect) = last_region::load() else {
+                    let msg = "no region to repeat yet: confirm one in the overlay first".to_string();
+                    error!("{msg}");
                     state.active_job = None;
-                    Response::Ok
-                }
-                Err(msg) => {
-                    error!("capture failed: {}", msg);
+                    state.record_error(&msg);
                     let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
                     let _ = notify::notify_failed(&msg);
+                    return Response::Error { code: ErrorCode::InvalidRequest, message: msg };
+                };
+                info!("repeating last region: {:?}", rect);
+                return capture_region(state, conn, rect, &options, None);
+            }
+
+            if let Some((relative_to, geometry)) = geometry {
+                let rect = match resolve_geometry(&state.outputs, relative_to, geometry) {
+                    Ok(rect) => rect,
+                    Err((code, msg)) => {
+                        error!("{msg}");
+                        state.active_job = None;
+                        state.record_error(&msg);
+                        let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+                        let _ = notify::notify_failed(&msg);
+                        return Response::Error { code, message: msg };
+                    }
+                };
+                info!("region from geometry: {:?}", rect);
+                return capture_region(state, conn, rect, &options, None);
+            }
+
+            let target_output_idx = match determine_output_index(&state.outputs, target) {
+                Ok(idx) => idx,
+                Err((code, msg)) => {
+                    error!("determine_output_index failed: {}", msg);
                     state.active_job = None;
-                    Response::Error { message: msg }
+                    state.record_error(&msg);
+                    let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+                    let _ = notify::notify_failed(&msg);
+                    return Response::Error { code, message: msg };
+                }
+            };
+
+            handle_region_overlay_capture(state, conn, target_output_idx, &options)
+        }
+
+        Mode::Screen => handle_screen_overlay_capture(state, conn, target, &options),
+
+        Mode::Window => {
+            state.active_job = Some(Mode::Window);
+            let _ = conn.send_event(Event::CaptureStarted { mode: Mode::Window });
+
+            let msg = unsupported_reason(Mode::Window).unwrap_or_default();
+
+            warn!("{msg}");
+            state.record_error(&msg);
+            let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+            let _ = notify::notify_failed(&msg);
+
+            state.active_job = None;
+            Response::Error { code: ErrorCode::NotImplemented, message: msg }
+        }
+
+        Mode::Record => {
+            let msg = unsupported_reason(Mode::Record).unwrap_or_default();
+            state.record_error(&msg);
+            let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+            let _ = notify::notify_failed(&msg);
+            Response::Error { code: ErrorCode::NotImplemented, message: msg }
+        }
+
+        Mode::Pick => handle_pick(state, conn, target, &options),
+    }
+}
+
+/// `capit pick`: freeze the desktop, let the user click a pixel and answer
+/// with its colour. A copying action (`--clipboard`) also copies the hex.
+fn handle_pick(
+    state: &mut DaemonState,
+    conn: &mut dyn EventSink,
+    target: Option<Target>,
+    options: &CaptureOptions,
+) -> Response {
+    state.active_job = Some(Mode::Pick);
+    let _ = conn.send_event(Event::CaptureStarted { mode: Mode::Pick });
+
+    let picked = determine_output_index(&state.outputs, target).and_then(|idx| {
+        let image = capture::capture_desktop_image(&capture_options(&state.cfg, None, 1))
+            .map_err(|e| (e.code(), e.to_string()))?;
+        let theme = overlay_region::Theme {
+            accent_colour: options
+                .accent_colour
+                .or(state.cfg.selection_colour)
+                .unwrap_or(state.ui.accent_colour),
+            border_style: state.cfg.region_border_style,
+            handle_outer_colour: None,
+            handle_inner_colour: None,
+            // The still is shown as-is: dimming would skew the colours.
+            dim_alpha: Some(0),
+            border_thickness: state.cfg.region_border_thickness,
+            handle_size: state.cfg.region_handle_size,
+        };
+        let frozen = overlay_region::Frozen { image: Rc::new(image), backdrop: true, loupe: true };
+        overlay_region::run_pick_overlay(
+            state.outputs.clone(),
+            idx,
+            theme,
+            state.cfg.cancel_on_focus_loss,
+            frozen,
+        )
+        .map_err(|msg| (ErrorCode::CaptureFailed, msg))
+    });
+    state.active_job = None;
+
+    match picked {
+        Ok(Some(rgb)) => {
+            let hex = colour_hex(rgb);
+            info!("picked colour {hex}");
+            if options.action.is_some_and(|a| a.copies()) {
+                match clipboard::copy_bytes(hex.clone().into_bytes(), "text/plain;charset=utf-8") {
+                    Ok(()) => info!("copied {hex} to clipboard"),
+                    Err(e) => warn!("clipboard copy failed: {e}"),
                 }
             }
+            Response::Colour { rgb, hex }
+        }
+        Ok(None) => {
+            info!("pick cancelled");
+            let _ = conn.send_event(Event::CaptureFailed { message: "cancelled".into() });
+            Response::Ok
+        }
+        Err((code, msg)) => {
+            error!("pick failed: {msg}");
+            state.record_error(&msg);
+            let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+            let _ = notify::notify_failed(&msg);
+            Response::Error { code, message: msg }
+        }
+    }
+}
+
+/// "#RRGGBB" for a 0xRRGGBB colour.
+fn colour_hex(rgb: u32) -> String {
+    format!("#{:06X}", rgb & 0x00FF_FFFF)
+}
+
+fn handle_region_overlay_capture(
+    state: &mut DaemonState,
+    conn: &mut dyn EventSink,
+    target_output_idx: usize,
+    options: &CaptureOptions,
+) -> Response {
+    // --accent > capit.selection_colour > capit.accent_colour
+    let theme = overlay_region::Theme {
+        accent_colour: options
+            .accent_colour
+            .or(state.cfg.selection_colour)
+            .unwrap_or(state.ui.accent_colour),
+        border_style: state.cfg.region_border_style,
+        handle_outer_colour: state.cfg.handle_outer_colour,
+        handle_inner_colour: state.cfg.handle_inner_colour,
+        // --dim > capit.region_dim_opacity > overlay default
+        dim_alpha: options.dim_alpha.or(state.cfg.region_dim_alpha),
+        border_thickness: state.cfg.region_border_thickness,
+        handle_size: state.cfg.region_handle_size,
+    };
+
+    // No overlay possible: skip the still, which would cost a portal screenshot.
+    if !overlay_region::layer_shell_available() {
+        return region_portal_fallback(state, conn, options);
+    }
+
+    // The still has to be taken before the overlay covers the desktop.
+    let freeze = state.cfg.freeze_on_select;
+    let still = if freeze || state.cfg.region_loupe {
+        match capture::capture_desktop_image(&capture_options(&state.cfg, None, 1)) {
+            Ok(img) => Some(Rc::new(img)),
+            Err(e) => {
+                warn!("region: freeze desktop: {e}; continuing over the live desktop");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let frozen = still.clone().map(|image| overlay_region::Frozen {
+        image,
+        backdrop: freeze,
+        loupe: state.cfg.region_loupe,
+    });
+
+    let region_opts = overlay_region::RegionOptions {
+        cancel_on_focus_loss: state.cfg.cancel_on_focus_loss,
+        crop_to_outputs: state.cfg.region_crop_to_outputs,
+        default_ratio: state.cfg.region_default_ratio,
+        snap_threshold: state.cfg.snap_threshold,
+        grid_size: state.cfg.grid_size,
+        initial: last_region::load(),
+        frozen,
+    };
+
+    match overlay_region::run_region_overlay(
+        state.outputs.clone(),
+        target_output_idx,
+        theme,
+        region_opts,
+    ) {
+        Ok(Some(rect)) => {
+            info!("overlay confirmed: {:?}", rect);
+            last_region::save(&rect);
+            // The overlay is gone, so this is the last reference to the still.
+            let still = still
+                .filter(|_| freeze)
+                .map(|rc| Rc::try_unwrap(rc).unwrap_or_else(|rc| (*rc).clone()));
+            capture_region(state, conn, rect, options, still)
         }
         Ok(None) => {
             // Cancel: do NOT notify (avoid spam)
@@ -206,35 +508,174 @@ fn handle_region_overlay_capture(
             state.active_job = None;
             Response::Ok
         }
+        Err(msg) if msg == overlay_region::NO_LAYER_SHELL => region_portal_fallback(state, conn, options),
         Err(msg) => {
             error!("overlay error: {}", msg);
+            state.record_error(&msg);
             let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
             let _ = notify::notify_failed(&msg);
             state.active_job = None;
-            Response::Error { message: msg }
+            Response::Error { code: ErrorCode::CaptureFailed, message: msg }
+        }
+    }
+}
+
+/// Save `rect` (global coordinates) once the region is settled, after any
+/// `--delay` countdown. With a `still` (`capit.freeze_on_select`) and no
+/// delay, `rect` is cropped from it: exactly what was on screen while
+/// selecting, and no second portal round trip.
+fn capture_region(
+    state: &mut DaemonState,
+    conn: &mut dyn EventSink,
+    rect: Rect,
+    options: &CaptureOptions,
+    still: Option<RgbaImage>,
+) -> Response {
+    if !countdown(conn, options.delay_secs) {
+        return send_cancelled(state, conn);
+    }
+
+    let (out_paths, index) = output_paths(&state.cfg, options, None);
+    let out_path = out_paths[0].clone();
+    info!("capturing to: {}", out_path.display());
+
+    let opts = capture_options(&state.cfg, None, index);
+    // The still is the same full-desktop screenshot, so it crops the same.
+    let crop = portal_area(&state.outputs, &rect);
+    // A delay is there to let the screen change; the still would ignore it.
+    let result = match still.filter(|_| options.delay_secs == 0) {
+        Some(image) => capture::save_image_crop(&out_paths, image, crop, &opts),
+        None => capture::capture_screen_to_crop(&out_paths, crop, &opts),
+    };
+    match result {
+        Ok(saved) => {
+            info!("capture successful");
+            let meta = CaptureMeta::for_rect(Mode::Region, rect, &state.outputs);
+            finalize_capture(state, conn, options, &meta, &out_paths, &saved);
+            state.active_job = None;
+            Response::Ok
+        }
+        Err(e) => {
+            let msg = e.to_string();
+            error!("capture failed: {}", msg);
+            state.record_error(&msg);
+            let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+            notify_capture_error(&e);
+            state.active_job = None;
+            Response::Error { code: e.code(), message: msg }
+        }
+    }
+}
+
+fn region_portal_fallback(state: &mut DaemonState, conn: &mut dyn EventSink, options: &CaptureOptions) -> Response {
+    warn!("region: no wlr-layer-shell (e.g. GNOME); falling back to the portal's interactive screenshot");
+    capture_region_via_portal(state, conn, options)
+}
+
+/// Region capture without capit's overlay: the portal's interactive
+/// screenshot lets the user pick the area, and its result is saved as is.
+/// `--delay` counts down before the portal dialog opens.
+fn capture_region_via_portal(
+    state: &mut DaemonState,
+    conn: &mut dyn EventSink,
+    options: &CaptureOptions,
+) -> Response {
+    if !countdown(conn, options.delay_secs) {
+        return send_cancelled(state, conn);
+    }
+
+    let (out_paths, index) = output_paths(&state.cfg, options, None);
+    info!("capturing to: {}", out_paths[0].display());
+
+    let opts = capture::CaptureOptions { interactive: true, ..capture_options(&state.cfg, None, index) };
+    let result = capture::capture_screen_to(&out_paths, &opts);
+    // Only the portal knows the area picked; the sidecar gets the desktop.
+    let meta = CaptureMeta::for_rect(Mode::Region, desktop_bounds(&state.outputs), &state.outputs);
+    finish_capture(state, conn, options, &meta, &out_paths, result)
+}
+
+/// Finalize a capture's saved images, or report why there are none.
+fn finish_capture(
+    state: &mut DaemonState,
+    conn: &mut dyn EventSink,
+    options: &CaptureOptions,
+    meta: &CaptureMeta,
+    out_paths: &[PathBuf],
+    result: std::result::Result<Vec<capture::SavedImage>, capture::CaptureError>,
+) -> Response {
+    match result {
+        Ok(saved) => {
+            info!("capture successful");
+            finalize_capture(state, conn, options, meta, out_paths, &saved);
+            state.active_job = None;
+            Response::Ok
+        }
+        // Dismissing the portal's dialog is a cancel, like Esc in the overlay.
+        Err(e) if e.code() == ErrorCode::Cancelled => {
+            info!("portal screenshot cancelled");
+            send_cancelled(state, conn)
+        }
+        Err(e) => {
+            let msg = e.to_string();
+            error!("capture failed: {}", msg);
+            state.record_error(&msg);
+            let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+            notify_capture_error(&e);
+            state.active_job = None;
+            Response::Error { code: e.code(), message: msg }
         }
     }
 }
 
 fn handle_screen_overlay_capture(
     state: &mut DaemonState,
-    conn: &mut capit_ipc::ClientConn,
+    conn: &mut dyn EventSink,
     target: Option<Target>,
+    options: &CaptureOptions,
 ) -> Response {
     state.active_job = Some(Mode::Screen);
     let _ = conn.send_event(Event::CaptureStarted { mode: Mode::Screen });
 
-    let initial_idx = match &target {
-        Some(Target::OutputName(name)) => state
-            .outputs
-            .iter()
-            .position(|o| o.name.as_deref() == Some(name.as_str())),
-        _ => None,
+    // CLI flag > capit.screen_default
+    let target = target.unwrap_or_else(|| screen_default_target(&state.cfg));
+    debug!("screen target: {:?}", target);
+
+    // Excluded outputs get no picker surface.
+    let pickable: Vec<OutputInfo> = state
+        .outputs
+        .iter()
+        .filter(|o| !is_excluded(&state.cfg, o))
+        .cloned()
+        .collect();
+    // An index counts every output, as `capit outputs` lists them, so it is
+    // resolved before excluded outputs are dropped from the picker.
+    let initial_idx = match target {
+        Target::OutputIndex(i) => match determine_output_index(&state.outputs, Some(Target::OutputIndex(i))) {
+            Ok(idx) => pickable.iter().position(|o| *o == state.outputs[idx]),
+            Err((code, msg)) => {
+                error!("{msg}");
+                state.active_job = None;
+                state.record_error(&msg);
+                let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
+                let _ = notify::notify_failed(&msg);
+                return Response::Error { code, message: msg };
+            }
+        },
+        _ => screen_initial_index(&pickable, &target),
     };
 
-    let accent = state.ui.accent_colour;
+    let accent = options.accent_colour.unwrap_or(state.ui.accent_colour);
 
-    let picked = match overlay_screen::run_screen_overlay(state.outputs.clone(), initial_idx, accent) {
+    // The portal's own picker replaces ours with --portal-interactive, and
+    // a contact sheet or --per-output always takes every pickable output:
+    // nothing to pick.
+    let overlay = if options.portal_interactive || options.contact_sheet || options.per_output {
+        Ok(Some(Target::AllScreens))
+    } else {
+        let dim_alpha = options.dim_alpha.or(state.cfg.screen_dim_alpha);
+        overlay_screen::run_screen_overlay(pickable, initial_idx, accent, dim_alpha)
+    };
+    let picked = match overlay {
         Ok(Some(t)) => t,
         Ok(None) => {
             // Cancel: do NOT notify
@@ -247,18 +688,78 @@ fn handle_screen_overlay_capture(
         }
         Err(msg) => {
             error!("screen overlay error: {}", msg);
+            state.record_error(&msg);
             let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
             let _ = notify::notify_failed(&msg);
             state.active_job = None;
-            return Response::Error { message: msg };
+            return Response::Error { code: ErrorCode::CaptureFailed, message: msg };
         }
     };
 
-    let out_path = default_output_path(&state.cfg, "png");
+    if !countdown(conn, options.delay_secs) {
+        return send_cancelled(state, conn);
+    }
+
+    if options.per_output {
+        return capture_per_output(state, conn, options);
+    }
+
+    let output = match &picked {
+        Target::OutputName(name) => Some(name.as_str()),
+        _ => None,
+    };
+    let (out_paths, index) = output_paths(&state.cfg, options, output);
+    let out_path = out_paths[0].clone();
     info!("capturing to: {}", out_path.display());
 
-    let result: std::result::Result<(), String> = match picked {
-        Target::AllScreens => capture::capture_screen_to(&out_path),
+    // The outputs this capture covers, for the metadata sidecar. With
+    // --portal-interactive only the portal knows what was picked, so the
+    // sidecar gets the whole desktop.
+    let covered: Vec<OutputInfo> = state
+        .outputs
+        .iter()
+        .filter(|o| match &picked {
+            Target::AllScreens => !is_excluded(&state.cfg, o),
+            Target::OutputName(name) => o.name.as_deref() == Some(name.as_str()),
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    let meta = CaptureMeta::for_rect(Mode::Screen, desktop_bounds(&covered), &covered);
+
+    let opts = capture_options(&state.cfg, output, index);
+    let result: std::result::Result<Vec<capture::SavedImage>, capture::CaptureError> = match picked {
+        Target::AllScreens if options.portal_interactive => {
+            info!("screen: handing selection to the portal's picker");
+            capture::capture_screen_to(&out_paths, &capture::CaptureOptions { interactive: true, ..opts })
+        }
+
+        Target::AllScreens if options.contact_sheet => {
+            if covered.is_empty() {
+                Err("every output is listed in capit.exclude_outputs".into())
+            } else {
+                let tiles: Vec<capture::SheetTile> =
+                    covered.iter().enumerate().map(|(i, o)| sheet_tile(i, o, &state.outputs)).collect();
+                capture::capture_screen_to_contact_sheet(&out_paths, &tiles, &opts)
+            }
+        }
+
+        Target::AllScreens => {
+            let kept: Vec<&OutputInfo> = state
+                .outputs
+                .iter()
+                .filter(|o| !is_excluded(&state.cfg, o))
+                .collect();
+            if kept.len() == state.outputs.len() {
+                capture::capture_screen_to(&out_paths, &opts)
+            } else if kept.is_empty() {
+                Err("every output is listed in capit.exclude_outputs".into())
+            } else {
+                let areas: Vec<DeviceRect> =
+                    kept.iter().map(|o| portal_area(&state.outputs, &o.logical_rect())).collect();
+                capture::capture_screen_to_areas(&out_paths, &areas, &opts)
+            }
+        }
 
         Target::OutputName(name) => match state
             .outputs
@@ -266,14 +767,7 @@ fn handle_screen_overlay_capture(
             .find(|o| o.name.as_deref() == Some(name.as_str()))
         {
             Some(out) => {
-                let s = out.scale.max(1);
-                let crop = capture::CaptureCrop {
-                    x: out.x * s,
-                    y: out.y * s,
-                    w: out.width * s,
-                    h: out.height * s,
-                };
-                capture::capture_screen_to_crop(&out_path, crop)
+                capture::capture_screen_to_crop(&out_paths, portal_area(&state.outputs, &out.logical_rect()), &opts)
             }
             None => {
                 let known = state
@@ -282,28 +776,517 @@ fn handle_screen_overlay_capture(
                     .filter_map(|o| o.name.as_deref())
                     .collect::<Vec<_>>()
                     .join(", ");
-                Err(format!("unknown output '{name}'. Try one of: {known}"))
+                Err(format!("unknown output '{name}'. Try one of: {known}").into())
             }
         },
 
-        other => Err(format!("overlay returned unsupported target: {other:?}")),
+        other => Err(format!("overlay returned unsupported target: {other:?}").into()),
+    };
+
+    finish_capture(state, conn, options, &meta, &out_paths, result)
+}
+
+/// `capit screen --per-output`: one screenshot, each non-excluded output
+/// cropped from it into its own file(s), so gaps between outputs are never
+/// saved. Each output is finalized like a capture of its own.
+fn capture_per_output(state: &mut DaemonState, conn: &mut dyn EventSink, options: &CaptureOptions) -> Response {
+    let kept: Vec<(String, OutputInfo)> = state
+        .outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| !is_excluded(&state.cfg, o))
+        .map(|(i, o)| (o.name.clone().unwrap_or_else(|| format!("output{i}")), o.clone()))
+        .collect();
+
+    let crops: Vec<capture::CropOutput> = kept
+        .iter()
+        .map(|(name, o)| {
+            let (paths, index) = per_output_paths(&state.cfg, options, name);
+            let watermark = capture_options(&state.cfg, Some(name), index).watermark;
+            (paths, portal_area(&state.outputs, &o.logical_rect()), watermark)
+        })
+        .collect();
+    for (paths, ..) in &crops {
+        info!("capturing to: {}", paths[0].display());
+    }
+
+    let result = if crops.is_empty() {
+        Err("every output is listed in capit.exclude_outputs".into())
+    } else {
+        capture::capture_screen_to_crops(&crops, &capture_options(&state.cfg, None, 1))
     };
 
     match result {
-        Ok(()) => {
-            let _ = conn.send_event(Event::CaptureFinished {
-                path: out_path.display().to_string(),
-            });
-            let _ = notify::notify_saved(&out_path);
+        Ok(saved) => {
+            for (((_, o), (paths, ..)), saved) in kept.iter().zip(&crops).zip(&saved) {
+                let meta = CaptureMeta::for_rect(Mode::Screen, o.logical_rect(), std::slice::from_ref(o));
+                finalize_capture(state, conn, options, &meta, paths, saved);
+            }
+            let _ = conn.send_event(Event::CaptureBatchFinished);
             state.active_job = None;
             Response::Ok
         }
-        Err(msg) => {
+        Err(e) => {
+            let msg = e.to_string();
             error!("capture failed: {}", msg);
+            state.record_error(&msg);
             let _ = conn.send_event(Event::CaptureFailed { message: msg.clone() });
-            let _ = notify::notify_failed(&msg);
+            notify_capture_error(&e);
             state.active_job = None;
-            Response::Error { message: msg }
+            Response::Error { code: e.code(), message: msg }
+        }
+    }
+}
+
+/// Contact-sheet tile for the `i`th output: its name and native size.
+fn sheet_tile(i: usize, out: &OutputInfo, outputs: &[OutputInfo]) -> capture::SheetTile {
+    let native = out.device_rect();
+    let name = out.name.clone().unwrap_or_else(|| format!("output {i}"));
+    capture::SheetTile {
+        label: format!("{name}  {}x{}", native.w, native.h),
+        area: portal_area(outputs, &out.logical_rect()),
+    }
+}
+
+/// Run the `--delay` countdown, one `CaptureCountdown` per second, so menus
+/// and tooltips can be opened before the screenshot. False if cancelled.
+fn countdown(conn: &mut dyn EventSink, delay_secs: u32) -> bool {
+    for remaining in (1..=delay_secs).rev() {
+        debug!("capture in {remaining}s");
+        let _ = conn.send_event(Event::CaptureCountdown { remaining });
+        if conn.wait_for_cancel(Duration::from_secs(1)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// User cancelled: tell the client, but don't notify (avoid spam).
+fn send_cancelled(state: &mut DaemonState, conn: &mut dyn EventSink) -> Response {
+    let _ = conn.send_event(Event::CaptureFailed {
+        message: "cancelled".into(),
+    });
+    state.active_job = None;
+    Response::Ok
+}
+
+fn finished_event(mode: Mode, out_path: &Path, saved: &capture::SavedImage, on_disk: bool) -> Event {
+    Event::CaptureFinished {
+        path: out_path.display().to_string(),
+        width: saved.width,
+        height: saved.height,
+        bytes: saved.bytes,
+        mode,
+        format: out_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("png")
+            .to_ascii_lowercase(),
+        on_disk,
+    }
+}
+
+/// The startup query can come back empty (no monitor yet, hotplug race):
+/// re-query once before giving up with an actionable message.
+fn ensure_outputs(state: &mut DaemonState) -> std::result::Result<(), String> {
+    if !state.outputs.is_empty() {
+        return Ok(());
+    }
+
+    info!("no outputs known; re-querying");
+    match wayland_outputs::query_outputs() {
+        Ok(outputs) if !outputs.is_empty() => {
+            info!("found {} outputs", outputs.len());
+            state.outputs = outputs;
+            Ok(())
+        }
+        Ok(_) => Err(NO_OUTPUTS.into()),
+        Err(e) => Err(format!("{NO_OUTPUTS} ({e})")),
+    }
+}
+
+const NO_OUTPUTS: &str = "no outputs reported yet: is a monitor connected, and is capitd \
+     running inside your Wayland session (WAYLAND_DISPLAY)?";
+
+/// Where global `rect` lands in the portal's full-desktop screenshot
+/// (physical pixels). The screenshot covers `desktop_bounds(outputs)` at a
+/// single scale, the highest among the outputs, as the region overlay's
+/// still assumes too; on a mixed-DPI layout the lower-scale outputs are
+/// upscaled in it.
+fn portal_area(outputs: &[OutputInfo], rect: &Rect) -> DeviceRect {
+    let desktop = desktop_bounds(outputs);
+    let scale = outputs.iter().map(OutputInfo::effective_scale).fold(1.0, f64::max);
+    let local = Rect { x: rect.x - desktop.x, y: rect.y - desktop.y, w: rect.w, h: rect.h };
+    local.to_device(scale)
+}
+
+fn is_excluded(cfg: &CapitConfig, out: &OutputInfo) -> bool {
+    cfg.exclude_outputs.iter().any(|e| out.matches(e))
+}
+
+/// One CaptureFinished per written file, primary first.
+fn send_finished(
+    conn: &mut dyn EventSink,
+    mode: Mode,
+    out_paths: &[PathBuf],
+    saved: &[capture::SavedImage],
+    on_disk: bool,
+) {
+    for (path, saved) in out_paths.iter().zip(saved) {
+        let _ = conn.send_event(finished_event(mode, path, saved, on_disk));
+    }
+}
+
+/// Everything after a successful capture, routed by its `PostAction`:
+/// bookkeeping, post-save work, the client's events and the notification.
+/// A `--output-file -` capture is streamed to the client instead and its
+/// scratch file removed.
+fn finalize_capture(
+    state: &mut DaemonState,
+    conn: &mut dyn EventSink,
+    options: &CaptureOptions,
+    meta: &CaptureMeta,
+    out_paths: &[PathBuf],
+    saved: &[capture::SavedImage],
+) {
+    let out_path = &out_paths[0];
+    if options.to_stdout {
+        stream_to_client(conn, meta.mode, out_path, &saved[0]);
+        if let Err(e) = std::fs::remove_file(out_path) {
+            warn!("remove {}: {e}", out_path.display());
+        }
+        return;
+    }
+
+    let action = capture_action(&state.cfg, options);
+    // Before after_save: a clipboard-only capture's file is gone after it.
+    set_wallpaper_if_requested(conn, options, out_path);
+    let on_disk = after_save(&state.cfg, action, meta, out_paths, saved);
+    if on_disk {
+        state.record_saved(out_path);
+    }
+    send_finished(conn, meta.mode, out_paths, saved, on_disk);
+    let _ = if on_disk {
+        notify::notify_saved(out_path)
+    } else {
+        notify::notify_copied()
+    };
+}
+
+/// The client's action, else `capit.default_action`. Edit without an
+/// editor configured just saves.
+fn capture_action(cfg: &CapitConfig, options: &CaptureOptions) -> PostAction {
+    let action = options.action.unwrap_or(cfg.default_action);
+    if action == PostAction::Edit && cfg.editor.is_none() {
+        warn!("edit requested but capit.editor is not set; saving without editing");
+        return PostAction::Save;
+    }
+    action
+}
+
+/// Largest `CaptureChunk` payload; well under the IPC frame limit.
+const STREAM_CHUNK: usize = 256 * 1024;
+
+/// Send the file at `path` as `CaptureChunk`s, then a `CaptureFinished`
+/// with path "-".
+fn stream_to_client(conn: &mut dyn EventSink, mode: Mode, path: &Path, saved: &capture::SavedImage) {
+    let bytes = match std::fs::read(path) {
+        Ok(b) => b,
+        Err(e) => {
+            let msg = format!("read {} for streaming: {e}", path.display());
+            error!("{msg}");
+            let _ = conn.send_event(Event::CaptureFailed { message: msg });
+            return;
+        }
+    };
+
+    for chunk in bytes.chunks(STREAM_CHUNK) {
+        if let Err(e) = conn.send_event(Event::CaptureChunk { data: chunk.to_vec() }) {
+            warn!("stream capture: client went away ({e})");
+            return;
+        }
+    }
+    info!("streamed {} bytes to the client", bytes.len());
+
+    let mut finished = finished_event(mode, path, saved, false);
+    if let Event::CaptureFinished { path, .. } = &mut finished {
+        *path = "-".into();
+    }
+    let _ = conn.send_event(finished);
+}
+
+/// Post-save work that must never fail the capture: clipboard copy,
+/// metadata sidecars, retention. With `PostAction::Edit` it waits for the
+/// editor first, off the daemon thread. True if the files are still on
+/// disk afterwards.
+fn after_save(
+    cfg: &CapitConfig,
+    action: PostAction,
+    meta: &CaptureMeta,
+    out_paths: &[PathBuf],
+    saved: &[capture::SavedImage],
+) -> bool {
+    let Some(editor) = cfg.editor.as_deref().filter(|_| action == PostAction::Edit) else {
+        return finish_save(cfg, action, meta, out_paths, saved);
+    };
+
+    // Only the primary file goes through the editor; extra formats keep the
+    // image as captured.
+    let (cfg, meta) = (cfg.clone(), meta.clone());
+    let (out_paths, mut saved) = (out_paths.to_vec(), saved.to_vec());
+    edit::edit_then(editor, out_paths[0].clone(), move |modified| {
+        if modified {
+            match capture::SavedImage::read(&out_paths[0]) {
+                Ok(img) => saved[0] = img,
+                Err(e) => warn!("edit: re-read {}: {e}", out_paths[0].display()),
+            }
+        }
+        finish_save(&cfg, action, &meta, &out_paths, &saved);
+    });
+    true
+}
+
+/// Clipboard, sidecars and retention for a capture that is final on disk.
+/// A clipboard-only capture's files are removed once the copy succeeded;
+/// if it failed they are kept and treated as a save. True if the files
+/// are still on disk.
+fn finish_save(
+    cfg: &CapitConfig,
+    action: PostAction,
+    meta: &CaptureMeta,
+    out_paths: &[PathBuf],
+    saved: &[capture::SavedImage],
+) -> bool {
+    if !action.keeps_file() {
+        // Copy without asking: declining would lose the capture outright.
+        match copy_to_clipboard(&out_paths[0]) {
+            Ok(()) => {
+                for path in out_paths {
+                    if let Err(e) = std::fs::remove_file(path) {
+                        warn!("remove {}: {e}", path.display());
+                    }
+                }
+                return false;
+            }
+            Err(e) => {
+                warn!("clipboard copy failed: {e}; keeping {}", out_paths[0].display());
+                let _ = notify::notify_failed(&format!("clipboard copy failed ({e}); saved instead"));
+            }
+        }
+    } else {
+        copy_if_requested(cfg, action, &out_paths[0]);
+    }
+    if cfg.sidecar_json {
+        sidecar::write_sidecars(meta, out_paths, saved);
+    }
+    retention::prune_old_captures(cfg);
+    true
+}
+
+/// Copy a saved capture to the clipboard when the action says so (`--copy`
+/// or `capit.default_action = "save_copy"`). The file is already on disk, so
+/// failure only warns.
+fn copy_if_requested(cfg: &CapitConfig, action: PostAction, out_path: &Path) {
+    if !action.copies() {
+        return;
+    }
+
+    if cfg.confirm_clipboard_overwrite {
+        confirm_then_copy(out_path.to_path_buf());
+        return;
+    }
+
+    copy_saved_to_clipboard(out_path);
+}
+
+/// Ask before replacing the clipboard; the answer arrives on a worker thread
+/// so the daemon keeps serving requests meanwhile.
+fn confirm_then_copy(out_path: PathBuf) {
+    const CONFIRM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+    let spawned = std::thread::Builder::new()
+        .name("capit-clipboard-confirm".into())
+        .spawn(move || {
+            let answer = notify::ask(
+                "Replace clipboard?",
+                &format!("Copy {} to the clipboard", out_path.display()),
+                &[("replace", "Replace"), ("keep", "Keep current")],
+                CONFIRM_TIMEOUT,
+            );
+            match answer {
+                Ok(Some(key)) if key == "replace" => copy_saved_to_clipboard(&out_path),
+                Ok(_) => info!("clipboard left unchanged (not confirmed)"),
+                Err(e) => warn!("clipboard confirmation unavailable ({e}); clipboard left unchanged"),
+            }
+        });
+
+    if let Err(e) = spawned {
+        warn!("spawn clipboard confirmation: {e}; clipboard left unchanged");
+    }
+}
+
+/// `--as-wallpaper`: hand the primary file to the wallpaper portal. The
+/// capture is already saved, so failure only warns.
+fn set_wallpaper_if_requested(conn: &mut dyn EventSink, options: &CaptureOptions, out_path: &Path) {
+    if !options.as_wallpaper {
+        return;
+    }
+
+    match wallpaper::set_wallpaper(out_path) {
+        Ok(()) => {
+            info!("wallpaper set to {}", out_path.display());
+            let _ = conn.send_event(Event::WallpaperSet {
+                path: out_path.display().to_string(),
+            });
+        }
+        Err(e) => {
+            warn!("{e}");
+            let _ = notify::notify_failed(&format!("saved, but setting the wallpaper failed: {e}"));
+        }
+    }
+}
+
+fn copy_to_clipboard(out_path: &Path) -> std::result::Result<(), String> {
+    clipboard::copy_file(out_path)?;
+    info!("copied {} to clipboard", out_path.display());
+    Ok(())
+}
+
+/// Copy a capture that stays on disk; failure only warns.
+fn copy_saved_to_clipboard(out_path: &Path) {
+    if let Err(e) = copy_to_clipboard(out_path) {
+        warn!("clipboard copy failed: {e}");
+        let _ = notify::notify_failed(&format!("saved, but clipboard copy failed: {e}"));
+    }
+}
+
+/// Full/read-only output locations get their own notification so the user
+/// knows to change `screenshot_directory` rather than retry.
+fn notify_capture_error(e: &capture::CaptureError) {
+    let msg = e.to_string();
+    let _ = if e.is_storage() {
+        notify::notify_storage_failed(&msg)
+    } else {
+        notify::notify_failed(&msg)
+    };
+}
+
+/// Post-processing for a capture taken now, derived from config. `output`
+/// and `index` fill the watermark's %o and %i as they filled the file name.
+fn capture_options(cfg: &CapitConfig, output: Option<&str>, index: u32) -> capture::CaptureOptions {
+    let watermark = cfg.watermark_text.as_ref().map(|text| capture::Watermark {
+        text: expand_tokens(text, unix_now(), index, output),
+        corner: cfg.watermark_position,
+        colour: cfg.watermark_colour,
+        opacity: cfg.watermark_opacity,
+    });
+
+    capture::CaptureOptions {
+        watermark,
+        jpeg_quality: cfg.jpeg_quality,
+        retry_on_black: cfg.retry_on_black,
+        interactive: false,
+    }
+}
+
+/// `capit.screen_default` as a target, used when the client didn't send one.
+fn screen_default_target(cfg: &CapitConfig) -> Target {
+    match &cfg.screen_default {
+        ScreenDefault::All => Target::AllScreens,
+        ScreenDefault::Focused => Target::FocusedOutput,
+        ScreenDefault::Primary => Target::PrimaryOutput,
+        ScreenDefault::Output(name) => Target::OutputName(name.clone()),
+    }
+}
+
+/// Whether window capture is served; the bar reads it from UiConfig.
+pub fn window_capture_supported() -> bool {
+    unsupported_reason(Mode::Window).is_none()
+}
+
+/// Why `mode` can't be captured yet, or None when a backend serves it.
+/// Drives both GetCapabilities and the StartCapture error.
+fn unsupported_reason(mode: Mode) -> Option<String> {
+    match mode {
+        Mode::Region | Mode::Screen => None,
+        Mode::Window => Some(
+            "window capture is not implemented yet.\n\
+             planned backends: sway (ipc tree), hyprland (hyprctl), niri (ipc)."
+                .into(),
+        ),
+        Mode::Record => Some("record not implemented yet".into()),
+        Mode::Pick => None,
+    }
+}
+
+/// Output the screen picker should preselect for `target`, if any.
+fn screen_initial_index(outputs: &[OutputInfo], target: &Target) -> Option<usize> {
+    match target {
+        Target::OutputName(name) => {
+            let idx = outputs.iter().position(|o| o.matches(name));
+            if idx.is_none() {
+                warn!("screen target '{name}' not found; no output preselected");
+            }
+            idx
+        }
+        Target::OutputIndex(i) => Some(*i as usize).filter(|i| *i < outputs.len()),
+        Target::PrimaryOutput => primary_output_index(outputs),
+        // Wayland doesn't expose output focus to clients. With no preselection the
+        // picker highlights the output under the pointer, which is where focus is
+        // on focus-follows-mouse setups.
+        Target::FocusedOutput | Target::AllScreens | Target::ActiveWindow | Target::Rect(_) => None,
+    }
+}
+
+/// The output at the desktop origin, else the first one reported.
+fn primary_output_index(outputs: &[OutputInfo]) -> Option<usize> {
+    if outputs.is_empty() {
+        return None;
+    }
+    Some(outputs.iter().position(|o| o.x == 0 && o.y == 0).unwrap_or(0))
+}
+
+/// Turn a headless region rect into global coordinates. With a target
+/// output it is relative to that output's origin and must fit inside it;
+/// without one it is global and must fit on the desktop.
+fn resolve_geometry(
+    outputs: &[OutputInfo],
+    target: Option<&Target>,
+    geometry: Rect,
+) -> std::result::Result<Rect, (ErrorCode, String)> {
+    let Rect { x, y, w, h } = geometry;
+    if w <= 0 || h <= 0 {
+        return Err((ErrorCode::InvalidRequest, format!("geometry {w}x{h}+{x}+{y} has no area")));
+    }
+
+    match target {
+        None | Some(Target::AllScreens) => {
+            let d = desktop_bounds(outputs);
+            if x < d.x || y < d.y || x + w > d.x + d.w || y + h > d.y + d.h {
+                return Err((
+                    ErrorCode::InvalidRequest,
+                    format!(
+                        "geometry {w}x{h}+{x}+{y} lies outside the desktop ({}x{}+{}+{})",
+                        d.w, d.h, d.x, d.y
+                    ),
+                ));
+            }
+            Ok(geometry)
+        }
+        Some(t) => {
+            let o = &outputs[determine_output_index(outputs, Some(t.clone()))?];
+            if x < 0 || y < 0 || x + w > o.width || y + h > o.height {
+                return Err((
+                    ErrorCode::InvalidRequest,
+                    format!(
+                        "geometry {w}x{h}+{x}+{y} does not fit on output {} ({}x{})",
+                        o.name.as_deref().unwrap_or("?"),
+                        o.width,
+                        o.height
+                    ),
+                ));
+            }
+            Ok(Rect { x: o.x + x, y: o.y + y, w, h })
         }
     }
 }
@@ -311,24 +1294,365 @@ fn handle_screen_overlay_capture(
 fn determine_output_index(
     outputs: &[OutputInfo],
     target: Option<Target>,
-) -> std::result::Result<usize, String> {
+) -> std::result::Result<usize, (ErrorCode, String)> {
     if outputs.is_empty() {
-        return Err("no outputs available".into());
+        return Err((ErrorCode::NoOutputs, "no outputs available".into()));
     }
 
     match target {
         None | Some(Target::AllScreens) => Ok(0),
+        // Only the screen picker can stand in for focus (the output under the
+        // pointer); there is nothing to fall back on here.
+        Some(Target::FocusedOutput) => Err((
+            ErrorCode::InvalidRequest,
+            "focused output is not exposed on Wayland; name an output or use its index".into(),
+        )),
+        Some(Target::PrimaryOutput) => Ok(primary_output_index(outputs).unwrap_or(0)),
+        Some(Target::OutputIndex(i)) => Some(i as usize)
+            .filter(|i| *i < outputs.len())
+            .ok_or_else(|| {
+                let n = outputs.len();
+                let valid = if n == 1 { "0".to_string() } else { format!("0-{}", n - 1) };
+                (
+                    ErrorCode::UnknownOutput,
+                    format!("output index {i} out of range: {n} output(s) available ({valid}, see `capit outputs`)"),
+                )
+            }),
         Some(Target::OutputName(name)) => outputs
             .iter()
-            .position(|o| o.name.as_deref() == Some(name.as_str()))
+            .position(|o| o.matches(&name))
             .ok_or_else(|| {
                 let known = outputs
                     .iter()
                     .filter_map(|o| o.name.as_deref())
                     .collect::<Vec<_>>()
                     .join(", ");
-                format!("unknown output '{name}'. Available: {known}")
+                (ErrorCode::UnknownOutput, format!("unknown output '{name}'. Available: {known}"))
             }),
-        other => Err(format!("target not supported for region: {other:?}")),
+        other => Err((
+            ErrorCode::InvalidRequest,
+            format!("target not supported for region: {other:?}"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::sink::LogSink;
+
+    // Both lists are guarded by a match, so a new variant fails to compile
+    // here until it is listed (and therefore covered by the tests below).
+    fn all_modes() -> Vec<Mode> {
+        let all = vec![Mode::Region, Mode::Screen, Mode::Window, Mode::Record, Mode::Pick];
+        for m in &all {
+            match m {
+                Mode::Region | Mode::Screen | Mode::Window | Mode::Record | Mode::Pick => {}
+            }
+        }
+        all
+    }
+
+    fn all_targets() -> Vec<Option<Target>> {
+        let all = vec![
+            None,
+            Some(Target::AllScreens),
+            Some(Target::OutputName("DP-1".into())),
+            Some(Target::OutputName("missing".into())),
+            Some(Target::OutputIndex(1)),
+            Some(Target::OutputIndex(99)),
+            Some(Target::FocusedOutput),
+            Some(Target::PrimaryOutput),
+            Some(Target::ActiveWindow),
+            Some(Target::Rect(Rect { x: 0, y: 0, w: 100, h: 100 })),
+        ];
+        for t in all.iter().flatten() {
+            match t {
+                Target::AllScreens
+                | Target::OutputName(_)
+                | Target::OutputIndex(_)
+                | Target::FocusedOutput
+                | Target::PrimaryOutput
+                | Target::ActiveWindow
+                | Target::Rect(_) => {}
+            }
+        }
+        all
+    }
+
+    fn output(name: &str, x: i32) -> OutputInfo {
+        OutputInfo {
+            name: Some(name.into()),
+            x,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            scale: 1,
+            fractional_scale: None,
+            id: None,
+            physical_width_mm: None,
+            physical_height_mm: None,
+            refresh_mhz: None,
+            make: None,
+            model: None,
+        }
+    }
+
+    fn layouts() -> Vec<Vec<OutputInfo>> {
+        vec![
+            Vec::new(),
+            vec![output("HDMI-A-1", 1920), output("DP-1", 0)],
+        ]
+    }
+
+    #[test]
+    fn capabilities_match_served_modes() {
+        let mut state = DaemonState::default();
+        let mut selection = SelectionState::default();
+        let resp = handle_request(
+            &mut state,
+            &mut selection,
+            &mut LogSink,
+            Request::GetCapabilities,
+        );
+        let Response::Capabilities { window_capture, record, .. } = resp else {
+            panic!("unexpected response: {resp:?}");
+        };
+
+        for mode in all_modes() {
+            let served = unsupported_reason(mode).is_none();
+            match mode {
+                Mode::Region | Mode::Screen | Mode::Pick => assert!(served, "{mode:?} must be served"),
+                Mode::Window => assert_eq!(served, window_capture),
+                Mode::Record => assert_eq!(served, record),
+            }
+        }
+    }
+
+    #[test]
+    fn ping_reports_uptime() {
+        let mut state = DaemonState {
+            started: std::time::Instant::now() - Duration::from_secs(90),
+            ..DaemonState::default()
+        };
+        let mut selection = SelectionState::default();
+        let resp = handle_request(&mut state, &mut selection, &mut LogSink, Request::Ping);
+        let Response::Pong { uptime_secs } = resp else {
+            panic!("unexpected response: {resp:?}");
+        };
+        assert!((90..95).contains(&uptime_secs), "uptime {uptime_secs}");
+    }
+
+    #[test]
+    fn colour_hex_is_six_upper_digits() {
+        assert_eq!(colour_hex(0x1E90FF), "#1E90FF");
+        assert_eq!(colour_hex(0x000a0b), "#000A0B");
+        assert_eq!(colour_hex(0xFF12_3456), "#123456");
+    }
+
+    #[test]
+    fn unsupported_modes_explain_themselves() {
+        for mode in all_modes() {
+            let reason = unsupported_reason(mode);
+            match mode {
+                Mode::Window | Mode::Record => {
+                    let msg = reason.unwrap_or_else(|| panic!("{mode:?} should be unsupported"));
+                    assert!(!msg.trim().is_empty(), "{mode:?} has an empty reason");
+                }
+                Mode::Region | Mode::Screen | Mode::Pick => assert_eq!(reason, None, "{mode:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn region_resolves_every_target() {
+        for outputs in layouts() {
+            for target in all_targets() {
+                match determine_output_index(&outputs, target.clone()) {
+                    Ok(idx) => assert!(idx < outputs.len(), "{target:?} -> {idx}"),
+                    Err((_, msg)) => assert!(!msg.is_empty(), "{target:?}: empty error"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn region_targets_pick_expected_outputs() {
+        let outputs = &layouts()[1];
+        let pick = |t| determine_output_index(outputs, Some(t));
+        assert_eq!(pick(Target::OutputName("DP-1".into())), Ok(1));
+        assert_eq!(pick(Target::PrimaryOutput), Ok(1));
+        assert!(pick(Target::ActiveWindow).is_err());
+        assert_eq!(pick(Target::FocusedOutput).unwrap_err().0, ErrorCode::InvalidRequest);
+    }
+
+    /// Records what a handler sends; a countdown is cancelled straight away.
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Vec<Event>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn send_event(&mut self, ev: Event) -> capit_ipc::Result<()> {
+            self.events.push(ev);
+            Ok(())
+        }
+
+        fn wait_for_cancel(&mut self, _: Duration) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn start_capture_refuses_unservable_combinations() {
+        let outputs = layouts().remove(1);
+        for mode in all_modes() {
+            for target in all_targets() {
+                // Combinations that reach an overlay or the portal need a session.
+                let refused = match mode {
+                    Mode::Window | Mode::Record => true,
+                    Mode::Region if matches!(target, Some(Target::Rect(_))) => false,
+                    Mode::Region | Mode::Pick => determine_output_index(&outputs, target.clone()).is_err(),
+                    Mode::Screen => false,
+                };
+                if !refused {
+                    continue;
+                }
+
+                let mut state = DaemonState { outputs: outputs.clone(), ..DaemonState::default() };
+                let mut selection = SelectionState::default();
+                let mut sink = RecordingSink::default();
+                let req = Request::StartCapture {
+                    mode,
+                    target: target.clone(),
+                    with_ui: false,
+                    options: CaptureOptions::default(),
+                };
+                let resp = handle_request(&mut state, &mut selection, &mut sink, req);
+
+                let case = format!("{mode:?} {target:?}");
+                assert!(matches!(resp, Response::Error { .. }), "{case}: {resp:?}");
+                let failed = sink.events.iter().filter(|e| matches!(e, Event::CaptureFailed { .. })).count();
+                assert_eq!(failed, 1, "{case}: {:?}", sink.events);
+                assert!(state.active_job.is_none(), "{case}: job left running");
+            }
+        }
+    }
+
+    #[test]
+    fn a_dismissed_portal_dialog_is_a_cancel() {
+        let mut state = DaemonState { active_job: Some(Mode::Screen), ..DaemonState::default() };
+        let mut sink = RecordingSink::default();
+        let meta = CaptureMeta::for_rect(Mode::Screen, desktop_bounds(&state.outputs), &state.outputs);
+        let result = Err(capture::CaptureError::PortalDenied { response: 1 });
+
+        let resp = finish_capture(&mut state, &mut sink, &CaptureOptions::default(), &meta, &[], result);
+
+        assert!(matches!(resp, Response::Ok), "{resp:?}");
+        assert!(
+            matches!(sink.events.as_slice(), [Event::CaptureFailed { message }] if message == "cancelled"),
+            "{:?}",
+            sink.events
+        );
+        assert!(state.active_job.is_none());
+    }
+
+    #[test]
+    fn fractional_scales_crop_whole_pixels() {
+        let scaled = |x, width, height, s| OutputInfo {
+            x,
+            width,
+            height,
+            fractional_scale: Some(s),
+            ..output("DP-1", 0)
+        };
+        let crop = |x, y, w, h| DeviceRect { x, y, w, h };
+
+        // 2560x1440 panel at 1.25 -> 2048x1152 logical, right of a 1920 px output.
+        assert_eq!(scaled(1920, 2048, 1152, 1.25).device_rect(), crop(2400, 0, 2560, 1440));
+        // 1920x1080 panel at 1.5 -> 1280x720 logical.
+        assert_eq!(scaled(0, 1280, 720, 1.5).device_rect(), crop(0, 0, 1920, 1080));
+        // Odd logical edges round per edge; the two halves still tile exactly.
+        let left = scaled(0, 853, 720, 1.5).device_rect();
+        let right = scaled(853, 427, 720, 1.5).device_rect();
+        assert_eq!(left, crop(0, 0, 1280, 1080));
+        assert_eq!(right, crop(1280, 0, 640, 1080));
+        // No fractional factor: the integer scale, as before.
+        assert_eq!(OutputInfo { scale: 2, ..output("DP-1", 100) }.device_rect(), crop(200, 0, 3840, 2160));
+    }
+
+    #[test]
+    fn mixed_scales_crop_at_the_screenshot_scale() {
+        // 1080p at scale 1 left of a 1440p panel at 1.25: the screenshot is
+        // the whole 3968x1152 desktop at 1.25.
+        let hidpi = OutputInfo { width: 2048, height: 1152, fractional_scale: Some(1.25), ..output("DP-2", 1920) };
+        let outputs = [output("DP-1", 0), hidpi.clone()];
+        let area = |o: &OutputInfo| portal_area(&outputs, &o.logical_rect());
+        let crop = |x, y, w, h| DeviceRect { x, y, w, h };
+
+        assert_eq!(area(&outputs[0]), crop(0, 0, 2400, 1350));
+        assert_eq!(area(&hidpi), crop(2400, 0, 2560, 1440));
+        // A region on the scale-1 output is upscaled with it.
+        assert_eq!(portal_area(&outputs, &Rect { x: 10, y: 10, w: 100, h: 100 }), crop(13, 13, 125, 125));
+
+        // The screenshot starts at the desktop's top-left, not at 0,0.
+        let left_of_origin = [OutputInfo { x: -1920, ..output("DP-1", 0) }, output("DP-2", 0)];
+        assert_eq!(portal_area(&left_of_origin, &left_of_origin[1].logical_rect()), crop(1920, 0, 1920, 1080));
+    }
+
+    #[test]
+    fn output_errors_carry_their_code() {
+        let code = |outputs: &[OutputInfo], t| determine_output_index(outputs, Some(t)).unwrap_err().0;
+        let outputs = &layouts()[1];
+        assert_eq!(code(outputs, Target::OutputName("missing".into())), ErrorCode::UnknownOutput);
+        assert_eq!(code(outputs, Target::OutputIndex(99)), ErrorCode::UnknownOutput);
+        let (_, msg) = determine_output_index(outputs, Some(Target::OutputIndex(2))).unwrap_err();
+        assert!(msg.contains("2 output(s) available (0-1"), "{msg}");
+        assert_eq!(code(outputs, Target::ActiveWindow), ErrorCode::InvalidRequest);
+        assert_eq!(code(&[], Target::AllScreens), ErrorCode::NoOutputs);
+    }
+
+    #[test]
+    fn geometry_is_relative_to_the_target_output() {
+        let outputs = &layouts()[1];
+        let local = Rect { x: 10, y: 20, w: 100, h: 100 };
+        let hdmi = Target::OutputName("HDMI-A-1".into());
+
+        let global = resolve_geometry(outputs, Some(&hdmi), local);
+        assert_eq!(global, Ok(Rect { x: 1930, y: 20, w: 100, h: 100 }));
+        assert_eq!(resolve_geometry(outputs, None, local), Ok(local));
+
+        // Fits the desktop but not the output it is relative to.
+        let wide = Rect { x: 1900, y: 0, w: 100, h: 100 };
+        assert!(resolve_geometry(outputs, Some(&hdmi), wide).is_err());
+        assert!(resolve_geometry(outputs, None, wide).is_ok());
+
+        let outside = Rect { x: 3800, y: 0, w: 100, h: 100 };
+        assert!(resolve_geometry(outputs, None, outside).is_err());
+    }
+
+    #[test]
+    fn screen_preselects_only_real_outputs() {
+        for outputs in layouts() {
+            for target in all_targets().into_iter().flatten() {
+                if let Some(idx) = screen_initial_index(&outputs, &target) {
+                    assert!(idx < outputs.len(), "{target:?} -> {idx}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn screen_default_is_a_screen_target() {
+        let mut cfg = CapitConfig::default();
+        for (default, target) in [
+            (ScreenDefault::All, Target::AllScreens),
+            (ScreenDefault::Focused, Target::FocusedOutput),
+            (ScreenDefault::Primary, Target::PrimaryOutput),
+            (ScreenDefault::Output("DP-1".into()), Target::OutputName("DP-1".into())),
+        ] {
+            cfg.screen_default = default;
+            assert_eq!(screen_default_target(&cfg), target);
+        }
     }
 }
@@ -3,9 +3,11 @@
 
 use std::fmt;
 use std::fs::{self, OpenOptions};
-use std::io::{Read, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use capit_ipc::paths::{is_lock_stale, lock_path_for_socket};
+
 #[derive(Debug)]
 pub enum LockError {
     NoParent(PathBuf),
@@ -47,16 +49,11 @@ pub struct InstanceLock {
 
 impl InstanceLock {
     pub fn acquire_for_socket(sock_path: &Path) -> Result<Self, LockError> {
-        let dir = sock_path
-            .parent()
-            .map(Path::to_path_buf)
+        // "daemonname.lock" next to the socket, matching gessod's style.
+        // Shared with `capit abort`, which cleans up after a crash.
+        let lock_path = lock_path_for_socket(sock_path)
             .ok_or_else(|| LockError::NoParent(sock_path.to_path_buf()))?;
 
-        // Keep your existing naming scheme if you want:
-        //   sock.with_extension("lock")
-        // but this matches gessod's "daemonname.lock" style.
-        let lock_path = dir.join("capitd.lock");
-
         // If stale lock exists (process dead), clean it up and retry once.
         if lock_path.exists() && is_lock_stale(&lock_path) {
             let _ = fs::remove_file(&lock_path);
@@ -89,25 +86,3 @@ impl Drop for InstanceLock {
         let _ = fs::remove_file(&self.path);
     }
 }
-
-fn is_lock_stale(lock_path: &Path) -> bool {
-    let mut s = String::new();
-    if std::fs::File::open(lock_path).and_then(|mut f| f.read_to_string(&mut s)).is_err() {
-        return false;
-    }
-
-    let pid = s
-        .lines()
-        .find_map(|l| l.strip_prefix("pid="))
-        .and_then(|v| v.trim().parse::<u32>().ok());
-
-    let Some(pid) = pid else { return false; };
-
-    // If /proc doesn't exist, we can't do this check reliably.
-    let proc = Path::new("/proc");
-    if !proc.exists() {
-        return false;
-    }
-
-    !proc.join(pid.to_string()).exists()
-}
@@ -0,0 +1,55 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// The last region confirmed in the overlay, kept in
+// `$XDG_STATE_HOME/capit/last_region.json`: the next region overlay starts
+// from it, and `capit region --repeat` captures it again without one.
+
+use std::path::PathBuf;
+
+use capit_core::Rect;
+use eventline::{debug, warn};
+
+use super::paths::{default_log_path, ensure_parent_dir};
+
+const FILE: &str = "last_region.json";
+
+pub fn path() -> PathBuf {
+    default_log_path(FILE)
+}
+
+/// The saved rect, if there is a readable one with an area.
+pub fn load() -> Option<Rect> {
+    let path = path();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(e) => {
+            debug!("last region: read {}: {e}", path.display());
+            return None;
+        }
+    };
+
+    match serde_json::from_str::<Rect>(&text) {
+        Ok(r) if r.w > 0 && r.h > 0 => Some(r),
+        Ok(r) => {
+            warn!("last region: ignoring empty rect {r:?} in {}", path.display());
+            None
+        }
+        Err(e) => {
+            warn!("last region: parse {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Remember `rect`. Failures only warn: the capture itself is unaffected.
+pub fn save(rect: &Rect) {
+    let path = path();
+    let res = ensure_parent_dir(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|()| serde_json::to_string(rect).map_err(|e| e.to_string()))
+        .and_then(|text| std::fs::write(&path, text + "\n").map_err(|e| e.to_string()));
+    if let Err(e) = res {
+        warn!("last region: write {}: {e}", path.display());
+    }
+}
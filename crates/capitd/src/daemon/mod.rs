@@ -1,12 +1,18 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+pub mod edit;
 pub mod handlers;
 pub mod instance_lock;
+pub mod last_region;
 pub mod notify;
 pub mod paths;
+pub mod retention;
+pub mod scheduler;
 pub mod server;
 pub mod session;
+pub mod sidecar;
+pub mod sink;
 pub mod state;
 
 pub use paths::{default_log_path}; 
@@ -5,8 +5,14 @@
 // Best-effort: failures should never break captures.
 
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-use zbus::{Connection, Proxy};
+use async_io::Timer;
+use futures_util::{future::select, future::Either, pin_mut, StreamExt};
+
+use crate::bus;
+
+use zbus::Proxy;
 use zbus::zvariant::Value;
 
 const DEST: &str = "org.freedesktop.Notifications";
@@ -34,10 +40,20 @@ fn default_timeout_ms(kind: Kind) -> i32 {
     }
 }
 
+/// Run a notification call; a failure drops the shared bus connection so the
+/// next call reconnects instead of reusing a dead one.
+fn on_session_bus<T>(fut: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    let res = zbus::block_on(fut);
+    if res.is_err() {
+        bus::invalidate();
+    }
+    res
+}
+
 /// Send a desktop notification (best-effort).
 pub fn send(kind: Kind, summary: &str, body: &str) -> Result<(), String> {
-    zbus::block_on(async {
-        let conn = Connection::session()
+    on_session_bus(async {
+        let conn = bus::session()
             .await
             .map_err(|e| format!("notify: dbus session connect: {e}"))?;
 
@@ -77,12 +93,127 @@ pub fn send(kind: Kind, summary: &str, body: &str) -> Result<(), String> {
     })
 }
 
+/// Show a notification with action buttons (`(key, label)` pairs) and wait
+/// for the user. Returns the invoked key, or None if it was dismissed or
+/// `timeout` passed. Errors if the notification server has no action support.
+///
+/// Blocks: call from a worker thread, never the daemon loop.
+pub fn ask(
+    summary: &str,
+    body: &str,
+    actions: &[(&str, &str)],
+    timeout: Duration,
+) -> Result<Option<String>, String> {
+    on_session_bus(async {
+        let conn = bus::session()
+            .await
+            .map_err(|e| format!("notify: dbus session connect: {e}"))?;
+
+        let proxy = Proxy::new(&conn, DEST, PATH, IFACE)
+            .await
+            .map_err(|e| format!("notify: proxy: {e}"))?;
+
+        let caps: Vec<String> = proxy
+            .call("GetCapabilities", &())
+            .await
+            .map_err(|e| format!("notify: call GetCapabilities: {e}"))?;
+        if !caps.iter().any(|c| c == "actions") {
+            return Err("notification server does not support actions".into());
+        }
+
+        // Subscribe before Notify so a fast click can't be missed.
+        let invoked = proxy
+            .receive_signal("ActionInvoked")
+            .await
+            .map_err(|e| format!("notify: receive ActionInvoked: {e}"))?;
+        let closed = proxy
+            .receive_signal("NotificationClosed")
+            .await
+            .map_err(|e| format!("notify: receive NotificationClosed: {e}"))?;
+        let mut signals = futures_util::stream::select(invoked, closed);
+
+        let flat: Vec<&str> = actions.iter().flat_map(|(key, label)| [*key, *label]).collect();
+
+        let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+        hints.insert("urgency", Value::from(urgency(Kind::Info)));
+
+        let id: u32 = proxy
+            .call(
+                "Notify",
+                &(
+                    "Capit",
+                    0u32,
+                    "",
+                    summary,
+                    body,
+                    flat,
+                    hints,
+                    timeout.as_millis().min(i32::MAX as u128) as i32,
+                ),
+            )
+            .await
+            .map_err(|e| format!("notify: call Notify: {e}"))?;
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if Instant::now() > deadline {
+                let _ = proxy.call_method("CloseNotification", &(id,)).await;
+                return Ok(None);
+            }
+
+            let next_signal = signals.next();
+            let tick = Timer::after(Duration::from_millis(250));
+            pin_mut!(next_signal, tick);
+
+            let msg = match select(next_signal, tick).await {
+                Either::Left((Some(msg), _)) => msg,
+                Either::Left((None, _)) => return Ok(None),
+                Either::Right((_, _)) => continue,
+            };
+
+            let header = msg.header();
+            match header.member().map(|m| m.as_str()) {
+                Some("ActionInvoked") => {
+                    let (nid, key): (u32, String) = msg
+                        .body()
+                        .deserialize()
+                        .map_err(|e| format!("notify: ActionInvoked decode: {e}"))?;
+                    if nid == id {
+                        return Ok(Some(key));
+                    }
+                }
+                Some("NotificationClosed") => {
+                    let (nid, _reason): (u32, u32) = msg
+                        .body()
+                        .deserialize()
+                        .map_err(|e| format!("notify: NotificationClosed decode: {e}"))?;
+                    if nid == id {
+                        return Ok(None);
+                    }
+                }
+                _ => {}
+            }
+        }
+    })
+}
+
 /// Convenience: "Saved" notification.
 pub fn notify_saved(path: &std::path::Path) -> Result<(), String> {
     send(Kind::Info, "Screenshot saved", &path.display().to_string())
 }
 
+/// Convenience: "Copied" notification for a clipboard-only capture.
+pub fn notify_copied() -> Result<(), String> {
+    send(Kind::Info, "Screenshot copied", "Copied to the clipboard")
+}
+
 /// Convenience: "Failed" notification.
 pub fn notify_failed(msg: &str) -> Result<(), String> {
     send(Kind::Error, "Screenshot failed", msg)
 }
+
+/// Convenience: the screenshot directory is full or read-only.
+pub fn notify_storage_failed(msg: &str) -> Result<(), String> {
+    send(Kind::Error, "Screenshot not saved: check screenshot directory", msg)
+}
@@ -4,24 +4,13 @@
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use capit_ipc::Result;
-use crate::config::CapitConfig;
+use capit_core::{ErrorCode, ImageFormat};
+use capit_ipc::{CaptureOptions, Result};
+use eventline::warn;
 
-/// Runtime dir for IPC files (socket + lock).
-/// Prefers XDG_RUNTIME_DIR, falls back to /tmp.
-fn runtime_ipc_dir() -> PathBuf {
-    std::env::var_os("XDG_RUNTIME_DIR")
-        .map(PathBuf::from)
-        .unwrap_or_else(|| PathBuf::from("/tmp"))
-        .join("capit")
-}
+use crate::config::CapitConfig;
 
-pub fn default_socket_path() -> PathBuf {
-    // Move socket into a subfolder:
-    //   $XDG_RUNTIME_DIR/capit/capit.sock
-    // (fallback: /tmp/capit/capit.sock)
-    runtime_ipc_dir().join("capit.sock")
-}
+pub use capit_ipc::paths::default_socket_path;
 
 pub fn ensure_parent_dir(path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
@@ -63,13 +52,471 @@ pub fn output_dir_from_cfg(cfg: &CapitConfig) -> PathBuf {
         .unwrap_or_else(|| PathBuf::from("/tmp"))
 }
 
-pub fn default_output_path(cfg: &CapitConfig, ext: &str) -> PathBuf {
-    let base = output_dir_from_cfg(cfg);
+/// Directory a capture taken now is saved in. `dir_override` (from the
+/// client's `--output-directory`) wins over env/config for this capture only.
+fn capture_dir(cfg: &CapitConfig, dir_override: Option<&Path>) -> PathBuf {
+    match dir_override {
+        Some(dir) => dir.to_path_buf(),
+        None => output_dir_from_cfg(cfg),
+    }
+}
+
+/// One output path per requested format: a shared name, differing only in
+/// extension. The first entry is the primary file. Without `--format` the
+/// single format comes from `capit.screenshot_format`.
+///
+/// The name comes from `capit.filename_template` (`output` fills `%o`), else
+/// `capit-<unixsecs>`. A name already taken by any of the formats gets `_1`,
+/// `_2`, ... appended so an earlier capture is never overwritten.
+///
+/// `--output-file` bypasses all of this: that exact path, overwritten if present.
+///
+/// Also returns the index the name's `%i` took (1 without one), so the
+/// watermark can show the same number.
+pub fn output_paths(cfg: &CapitConfig, options: &CaptureOptions, output: Option<&str>) -> (Vec<PathBuf>, u32) {
+    if let Some(file) = &options.output_file {
+        return (vec![file.clone()], 1);
+    }
+
+    let formats = if options.formats.is_empty() {
+        vec![cfg.screenshot_format]
+    } else {
+        options.resolved_formats()
+    };
+    let dir = capture_dir(cfg, options.output_dir.as_deref());
+    let now = unix_now();
+
+    let paths_for = |stem: &str| -> Vec<PathBuf> {
+        formats
+            .iter()
+            .map(|f| dir.join(format!("{stem}.{}", f.extension())))
+            .collect()
+    };
+    let taken = |paths: &[PathBuf]| paths.iter().any(|p| p.exists());
+
+    let template = cfg.filename_template.trim();
+    let (stem, index) = if template.is_empty() {
+        (format!("capit-{now}"), 1)
+    } else {
+        let expanded = if template.contains("%i") {
+            // %i is the first index whose name is still free.
+            (1..=u32::MAX)
+                .map(|i| expand_filename_template(template, now, i, output).map(|stem| (stem, i)))
+                .find(|r| match r {
+                    Ok((stem, _)) => !taken(&paths_for(stem)),
+                    Err(_) => true,
+                })
+                .unwrap_or_else(|| Err("no free %i index".into()))
+        } else {
+            expand_filename_template(template, now, 1, output).map(|stem| (stem, 1))
+        };
+        expanded.unwrap_or_else(|e| {
+            warn!("capit.filename_template: {e}; using capit-<timestamp>");
+            (format!("capit-{now}"), 1)
+        })
+    };
+
+    let mut paths = paths_for(&stem);
+    let mut n = 1u32;
+    while taken(&paths) {
+        paths = paths_for(&format!("{stem}_{n}"));
+        n += 1;
+    }
+    (paths, index)
+}
+
+/// `output_paths` for one output of a `--per-output` capture: the
+/// name gets `-<output>` appended unless the template already has `%o`.
+pub fn per_output_paths(cfg: &CapitConfig, options: &CaptureOptions, output: &str) -> (Vec<PathBuf>, u32) {
+    let (paths, index) = output_paths(cfg, options, Some(output));
+    if cfg.filename_template.contains("%o") {
+        return (paths, index);
+    }
+    let label = output.replace('/', "_");
+    let paths = paths
+        .into_iter()
+        .map(|p| {
+            let stem = p.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            let name = match p.extension() {
+                Some(ext) => format!("{stem}-{label}.{}", ext.to_string_lossy()),
+                None => format!("{stem}-{label}"),
+            };
+            p.with_file_name(name)
+        })
+        .collect();
+    (paths, index)
+}
+
+/// Expand `capit.filename_template` into a file stem:
+///   tokens as in `expand_tokens`, %i `counter`, %o `output` (empty when the
+///   capture isn't of a single output).
+/// A trailing image extension (`.png`, ...) is dropped; the format adds its
+/// own. Unknown tokens and names that aren't a single path component are
+/// errors.
+pub fn expand_filename_template(
+    template: &str,
+    unix_secs: i64,
+    counter: u32,
+    output: Option<&str>,
+) -> std::result::Result<String, String> {
+    let template = ImageFormat::ALL
+        .iter()
+        .find_map(|f| {
+            let (stem, ext) = template.rsplit_once('.')?;
+            ext.eq_ignore_ascii_case(f.extension()).then_some(stem)
+        })
+        .unwrap_or(template);
+
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some('Y' | 'm' | 'd' | 'H' | 'M' | 'S' | '%' | 'i' | 'o') => {}
+            Some(t) => return Err(format!("unknown token %{t} in {template:?}")),
+            None => return Err(format!("dangling % at the end of {template:?}")),
+        }
+    }
+
+    let stem = expand_tokens(template, unix_secs, counter, output);
+    if stem.is_empty() || stem == "." || stem == ".." || stem.contains('/') || stem.contains('\0') {
+        return Err(format!("{template:?} does not expand to a file name"));
+    }
+    Ok(stem)
+}
+
+/// Expand a capture's name or watermark template: the time tokens of
+/// `expand_time_tokens`, plus %i `counter` and %o `output` (empty when the
+/// capture isn't of a single output). Unknown tokens are left as-is.
+pub fn expand_tokens(template: &str, unix_secs: i64, counter: u32, output: Option<&str>) -> String {
+    let mut pending = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            pending.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(t @ ('Y' | 'm' | 'd' | 'H' | 'M' | 'S')) => {
+                pending.push('%');
+                pending.push(t);
+            }
+            Some('i') => pending.push_str(&counter.to_string()),
+            Some('o') => pending.push_str(&output.unwrap_or_default().replace('%', "%%")),
+            Some(t) => {
+                pending.push_str("%%");
+                if t != '%' {
+                    pending.push(t);
+                }
+            }
+            None => pending.push_str("%%"),
+        }
+    }
+
+    expand_time_tokens(&pending, unix_secs)
+}
+
+/// Scratch file for a `--output-file -` capture; removed once it has been
+/// streamed to the client.
+pub fn stdout_temp_path(format: ImageFormat) -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    dir.join(format!(".capit-stdout-{}-{nanos}.{}", std::process::id(), format.extension()))
+}
+
+/// Check `--output-file` before anything is captured. A directory (existing,
+/// or given with a trailing `/`) becomes the output directory, keeping the
+/// templated name. Otherwise the extension must be a format we write and the
+/// parent directory is created.
+pub fn resolve_output_file(options: &mut CaptureOptions) -> std::result::Result<(), (ErrorCode, String)> {
+    let Some(file) = options.output_file.take() else {
+        return Ok(());
+    };
+
+    if file.is_dir() || file.as_os_str().as_encoded_bytes().ends_with(b"/") {
+        options.output_dir = Some(file);
+        return Ok(());
+    }
+
+    let ext = file.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    if ImageFormat::from_extension(ext).is_none() {
+        let known: Vec<&str> = ImageFormat::ALL.iter().map(|f| f.extension()).collect();
+        return Err((
+            ErrorCode::InvalidRequest,
+            format!(
+                "unsupported output file {} (extension must be one of: {})",
+                file.display(),
+                known.join(", ")
+            ),
+        ));
+    }
+
+    let parent = file.parent().unwrap_or(Path::new("/"));
+    prepare_output_dir(parent).map_err(|msg| (ErrorCode::Storage, msg))?;
+
+    options.output_file = Some(file);
+    Ok(())
+}
+
+/// Create `dir` if needed and check we can actually write into it.
+pub fn prepare_output_dir(dir: &Path) -> std::result::Result<(), String> {
+    if !dir.is_absolute() {
+        return Err(format!("output directory must be absolute: {}", dir.display()));
+    }
+
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("cannot create output directory {}: {e}", dir.display()))?;
+
+    tempfile::Builder::new()
+        .prefix(".capit-write-test")
+        .tempfile_in(dir)
+        .map(drop)
+        .map_err(|e| format!("output directory {} is not writable: {e}", dir.display()))
+}
+
+/// Expand strftime-style tokens against local time:
+///   %Y year, %m month, %d day, %H hour, %M minute, %S second, %% literal '%'.
+/// Unknown tokens are left as-is.
+pub fn expand_time_tokens(template: &str, unix_secs: i64) -> String {
+    let tm = local_time(unix_secs);
+
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some('Y') => out.push_str(&format!("{:04}", tm.tm_year + 1900)),
+            Some('m') => out.push_str(&format!("{:02}", tm.tm_mon + 1)),
+            Some('d') => out.push_str(&format!("{:02}", tm.tm_mday)),
+            Some('H') => out.push_str(&format!("{:02}", tm.tm_hour)),
+            Some('M') => out.push_str(&format!("{:02}", tm.tm_min)),
+            Some('S') => out.push_str(&format!("{:02}", tm.tm_sec)),
+            Some('%') => out.push('%'),
+            _ => {
+                out.push('%');
+                continue;
+            }
+        }
+        chars.next();
+    }
 
-    let ts = SystemTime::now()
+    out
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
-        .as_secs();
+        .as_secs() as i64
+}
+
+fn local_time(unix_secs: i64) -> libc::tm {
+    let t: libc::time_t = unix_secs as libc::time_t;
+    // SAFETY: tm is plain old data; localtime_r fills it (or leaves zeroes on failure).
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::localtime_r(&t, &mut tm);
+    }
+    tm
+}
+
+/// Per-user trash directory ($XDG_DATA_HOME/Trash, else ~/.local/share/Trash).
+fn home_trash_dir() -> PathBuf {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|p| !p.as_os_str().is_empty())
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("Trash")
+}
+
+/// Move `path` to the home trash following the XDG trash spec
+/// (`files/<name>` plus `info/<name>.trashinfo`). Returns the trashed path.
+pub fn move_to_trash(path: &Path) -> std::io::Result<PathBuf> {
+    use std::io::{Error, ErrorKind, Write};
+
+    let abs = std::fs::canonicalize(path)?;
+    let file_name = abs
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "path has no usable file name"))?
+        .to_string();
+
+    let trash = home_trash_dir();
+    let files_dir = trash.join("files");
+    let info_dir = trash.join("info");
+    std::fs::create_dir_all(&files_dir)?;
+    std::fs::create_dir_all(&info_dir)?;
+
+    // Reserve a unique name by creating the .trashinfo exclusively first (per spec).
+    let (stem, ext) = match file_name.rsplit_once('.') {
+        Some((s, e)) if !s.is_empty() => (s.to_string(), format!(".{e}")),
+        _ => (file_name.clone(), String::new()),
+    };
+
+    let mut n = 0u32;
+    let (name, mut info) = loop {
+        let name = if n == 0 {
+            file_name.clone()
+        } else {
+            format!("{stem}.{n}{ext}")
+        };
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(info_dir.join(format!("{name}.trashinfo")))
+        {
+            Ok(f) => break (name, f),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    };
+
+    let info_path = info_dir.join(format!("{name}.trashinfo"));
+    let encoded = url::Url::from_file_path(&abs)
+        .map(|u| u.path().to_string())
+        .unwrap_or_else(|_| abs.display().to_string());
+    let deleted = expand_time_tokens("%Y-%m-%dT%H:%M:%S", unix_now());
+
+    let dest = files_dir.join(&name);
+    let moved = write!(info, "[Trash Info]\nPath={encoded}\nDeletionDate={deleted}\n")
+        .and_then(|_| match std::fs::rename(&abs, &dest) {
+            Ok(()) => Ok(()),
+            // Different filesystem: copy then remove.
+            Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+                std::fs::copy(&abs, &dest)?;
+                std::fs::remove_file(&abs)
+            }
+            Err(e) => Err(e),
+        });
+
+    if let Err(e) = moved {
+        let _ = std::fs::remove_file(&info_path);
+        return Err(e);
+    }
+
+    Ok(dest)
+}
 
-    base.join(format!("capit-{ts}.{ext}"))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg_with(template: &str) -> CapitConfig {
+        CapitConfig {
+            filename_template: template.into(),
+            ..CapitConfig::default()
+        }
+    }
+
+    // --output-directory, so $CAPIT_DIR in the environment can't interfere.
+    fn options_in(dir: &Path) -> CaptureOptions {
+        CaptureOptions {
+            output_dir: Some(dir.to_path_buf()),
+            ..CaptureOptions::default()
+        }
+    }
+
+    fn names(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn template_expands_counter_output_and_time() {
+        let stem = expand_filename_template("Shot_%o_%i_%Y-%m-%d_%H-%M-%S_%%", 0, 7, Some("DP-1")).unwrap();
+        assert!(stem.starts_with("Shot_DP-1_7_"), "{stem}");
+        assert!(stem.ends_with("_%"), "{stem}");
+        // %Y-%m-%d_%H-%M-%S is 19 characters whatever the local zone.
+        assert_eq!(stem.len(), "Shot_DP-1_7_".len() + 19 + 2);
+    }
+
+    #[test]
+    fn template_drops_image_extension_and_rejects_bad_names() {
+        assert_eq!(expand_filename_template("shot.PNG", 0, 1, None).unwrap(), "shot");
+        assert_eq!(expand_filename_template("shot.v2", 0, 1, None).unwrap(), "shot.v2");
+        assert!(expand_filename_template("shot_%q", 0, 1, None).is_err());
+        assert!(expand_filename_template("shot_%", 0, 1, None).is_err());
+        assert!(expand_filename_template("a/%Y", 0, 1, None).is_err());
+        assert!(expand_filename_template("%o", 0, 1, None).is_err());
+    }
+
+    #[test]
+    fn watermark_tokens_match_the_name_and_keep_unknown_ones() {
+        assert_eq!(expand_tokens("%o #%i, 100%% %q%", 0, 4, Some("DP-1")), "DP-1 #4, 100% %q%");
+        assert_eq!(expand_tokens("[%o]", 0, 1, None), "[]");
+    }
+
+    #[test]
+    fn taken_names_get_a_suffix() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = cfg_with("shot");
+        let options = options_in(dir.path());
+
+        assert_eq!(names(&output_paths(&cfg, &options, None).0), ["shot.png"]);
+        std::fs::write(dir.path().join("shot.png"), b"").unwrap();
+        assert_eq!(names(&output_paths(&cfg, &options, None).0), ["shot_1.png"]);
+        std::fs::write(dir.path().join("shot_1.png"), b"").unwrap();
+        assert_eq!(names(&output_paths(&cfg, &options, None).0), ["shot_2.png"]);
+    }
+
+    #[test]
+    fn per_output_names_carry_the_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = options_in(dir.path());
+
+        assert_eq!(names(&per_output_paths(&cfg_with("shot"), &options, "DP-1").0), ["shot-DP-1.png"]);
+        // %o already names the output: nothing appended.
+        assert_eq!(names(&per_output_paths(&cfg_with("shot_%o"), &options, "DP-1").0), ["shot_DP-1.png"]);
+    }
+
+    #[test]
+    fn counter_takes_the_first_free_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = cfg_with("shot-%i");
+        let options = options_in(dir.path());
+
+        std::fs::write(dir.path().join("shot-1.png"), b"").unwrap();
+        std::fs::write(dir.path().join("shot-2.png"), b"").unwrap();
+        let (paths, index) = output_paths(&cfg, &options, None);
+        assert_eq!(names(&paths), ["shot-3.png"]);
+        assert_eq!(index, 3);
+    }
+
+    #[test]
+    fn output_file_is_used_as_is_or_as_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("sub/screen.jpg");
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        std::fs::write(&file, b"").unwrap();
+
+        // Exact path, even though a file is already there.
+        let mut options = CaptureOptions { output_file: Some(file.clone()), ..CaptureOptions::default() };
+        resolve_output_file(&mut options).unwrap();
+        assert_eq!(output_paths(&cfg_with("shot"), &options, None).0, [file]);
+
+        // A directory keeps the templated name.
+        let mut options = CaptureOptions { output_file: Some(dir.path().into()), ..CaptureOptions::default() };
+        resolve_output_file(&mut options).unwrap();
+        assert_eq!(names(&output_paths(&cfg_with("shot"), &options, None).0), ["shot.png"]);
+
+        let mut options = CaptureOptions {
+            output_file: Some(dir.path().join("screen.txt")),
+            ..CaptureOptions::default()
+        };
+        assert_eq!(resolve_output_file(&mut options).unwrap_err().0, ErrorCode::InvalidRequest);
+    }
 }
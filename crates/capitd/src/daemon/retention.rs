@@ -0,0 +1,142 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Retention: prune old captures from the screenshot directory after a save.
+// Only files Capit named itself (`capit-*.<image ext>`, or starting with the
+// fixed prefix of `capit.filename_template`, plus their `.json` metadata
+// sidecars) are ever touched.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use eventline::{debug, info, warn};
+
+use crate::config::CapitConfig;
+
+use super::paths::{move_to_trash, output_dir_from_cfg};
+use super::sidecar::sidecar_path;
+
+const IMAGE_EXTS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+
+/// Remove (or trash) captures older than `capit.retention_days`. No-op when unset.
+pub fn prune_old_captures(cfg: &CapitConfig) {
+    let Some(days) = cfg.retention_days else {
+        return;
+    };
+
+    let dir = output_dir_from_cfg(cfg);
+    let max_age = Duration::from_secs(u64::from(days) * 24 * 60 * 60);
+    let now = SystemTime::now();
+
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(e) => {
+            warn!("retention: cannot read {}: {e}", dir.display());
+            return;
+        }
+    };
+
+    let prefix = template_prefix(&cfg.filename_template);
+    let mut pruned = 0usize;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !is_capit_capture(&path, &prefix) {
+            continue;
+        }
+
+        let Ok(meta) = entry.metadata() else { continue };
+        if !meta.is_file() {
+            continue;
+        }
+
+        let age = meta
+            .modified()
+            .ok()
+            .and_then(|m| now.duration_since(m).ok())
+            .unwrap_or_default();
+        if age <= max_age {
+            continue;
+        }
+
+        match prune(cfg, &path) {
+            Ok(()) => pruned += 1,
+            Err(e) => {
+                warn!("retention: failed to prune {}: {e}", path.display());
+                continue;
+            }
+        }
+
+        // The metadata sidecar goes with its image.
+        let sidecar = sidecar_path(&path);
+        if sidecar.is_file()
+            && let Err(e) = prune(cfg, &sidecar)
+        {
+            warn!("retention: failed to prune {}: {e}", sidecar.display());
+        }
+    }
+
+    if pruned > 0 {
+        let how = if cfg.retention_trash { "trashed" } else { "deleted" };
+        info!("retention: {how} {pruned} capture(s) older than {days} day(s)");
+    }
+}
+
+fn prune(cfg: &CapitConfig, path: &Path) -> std::io::Result<()> {
+    if cfg.retention_trash {
+        move_to_trash(path).map(|dest| debug!("retention: trashed {} -> {}", path.display(), dest.display()))
+    } else {
+        std::fs::remove_file(path).map(|_| debug!("retention: deleted {}", path.display()))
+    }
+}
+
+/// The literal text `template` expands to before its first token (`%%` is a
+/// literal '%'). Empty for an unset template or one that starts with a token:
+/// such names can't be told apart from anyone else's images.
+fn template_prefix(template: &str) -> String {
+    let mut prefix = String::new();
+    let mut chars = template.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if chars.next() == Some('%') => prefix.push('%'),
+            '%' => break,
+            c => prefix.push(c),
+        }
+    }
+    prefix
+}
+
+fn is_capit_capture(path: &Path, template_prefix: &str) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+
+    // capit-<timestamp> is also the fallback when a template fails to expand.
+    let ours = name.starts_with("capit-") || (!template_prefix.is_empty() && name.starts_with(template_prefix));
+    ours && ext.is_some_and(|e| IMAGE_EXTS.contains(&e.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templated_names_are_captures_too() {
+        let prefix = template_prefix("Shot_%Y-%m-%d_%i");
+        assert_eq!(prefix, "Shot_");
+        assert!(is_capit_capture(Path::new("/tmp/Shot_2026-10-16_3.png"), &prefix));
+        assert!(is_capit_capture(Path::new("/tmp/capit-1760000000.webp"), &prefix));
+        assert!(!is_capit_capture(Path::new("/tmp/holiday.png"), &prefix));
+        assert!(!is_capit_capture(Path::new("/tmp/Shot_notes.txt"), &prefix));
+
+        assert_eq!(template_prefix("100%%_%o"), "100%_");
+        // A leading token leaves nothing to match on: only capit-* qualify.
+        let prefix = template_prefix("%Y%m%d");
+        assert!(prefix.is_empty());
+        assert!(!is_capit_capture(Path::new("/tmp/20261016.png"), &prefix));
+    }
+}
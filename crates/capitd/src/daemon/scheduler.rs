@@ -0,0 +1,76 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Captures scheduled for a future time. The daemon's accept loop calls
+// `fire_due` while idle; a job that comes due while a client is connected
+// fires as soon as that client disconnects.
+
+use capit_ipc::{CaptureSpec, Response, ScheduledCapture};
+
+use eventline::{info, warn};
+
+use super::handlers;
+use super::paths::unix_now;
+use super::sink::LogSink;
+use super::state::DaemonState;
+
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    next_id: u64,
+    jobs: Vec<ScheduledCapture>,
+}
+
+impl Scheduler {
+    pub fn add(&mut self, at_unix: i64, spec: CaptureSpec) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.jobs.push(ScheduledCapture { id, at_unix, spec });
+        id
+    }
+
+    /// Pending jobs, soonest first.
+    pub fn list(&self) -> Vec<ScheduledCapture> {
+        let mut jobs = self.jobs.clone();
+        jobs.sort_by_key(|j| (j.at_unix, j.id));
+        jobs
+    }
+
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let before = self.jobs.len();
+        self.jobs.retain(|j| j.id != id);
+        self.jobs.len() != before
+    }
+
+    fn take_due(&mut self, now: i64) -> Vec<ScheduledCapture> {
+        let (due, pending): (Vec<_>, Vec<_>) =
+            self.jobs.drain(..).partition(|j| j.at_unix <= now);
+        self.jobs = pending;
+
+        let mut due = due;
+        due.sort_by_key(|j| (j.at_unix, j.id));
+        due
+    }
+}
+
+/// Start every job whose time has come.
+pub fn fire_due(state: &mut DaemonState) {
+    let due = state.scheduler.take_due(unix_now());
+
+    for job in due {
+        info!("scheduled capture #{} firing: {:?}", job.id, job.spec);
+
+        let spec = job.spec;
+        let resp = handlers::start_capture(
+            state,
+            &mut LogSink,
+            spec.mode,
+            spec.target,
+            false,
+            spec.options,
+        );
+
+        if let Response::Error { code, message } = resp {
+            warn!("scheduled capture #{} failed [{code}]: {message}", job.id);
+        }
+    }
+}
@@ -1,20 +1,24 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use capit_ipc::{IpcServer, Result};
+use capit_core::{Mode, OutputInfo};
+use capit_ipc::{CaptureOptions, ClientConn, Event, IpcServer, Response, Result};
 use eventline::{debug, info, warn};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::{config, selection::SelectionState, wayland_outputs};
+use crate::{capture, config, selection::SelectionState, shortcuts, wayland_outputs};
 use crate::config::CapitConfig;
 
 use super::instance_lock::{InstanceLock, LockError};
 
-use super::handlers::handle_request;
+use super::handlers::{handle_request, start_capture, window_capture_supported};
 use super::paths::{default_socket_path, ensure_parent_dir, output_dir_from_cfg};
+use super::scheduler;
 use super::session;
+use super::sink::LogSink;
 use super::state::{DaemonState, UiCfg};
 
 use std::os::unix::fs::FileTypeExt;
@@ -46,6 +50,100 @@ fn cleanup_stale_socket(sock: &Path) -> std::io::Result<()> {
     }
 }
 
+/// How long startup waits for a first output (e.g. behind a slow dock).
+const STARTUP_OUTPUT_SETTLE: Duration = Duration::from_secs(2);
+
+/// Set from the SIGHUP handler; the main loop reloads the config when it sees it.
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sighup(_sig: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Route SIGHUP to `on_sighup` instead of the default (terminate).
+/// SA_RESTART keeps a client's blocking socket reads from failing with EINTR.
+fn install_sighup_handler() -> std::io::Result<()> {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(libc::SIGHUP, &action, std::ptr::null_mut()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Reload the config if SIGHUP arrived. Waits while a capture is in flight
+/// so it finishes with the settings it started with.
+fn reload_if_requested(state: &mut DaemonState) {
+    if state.active_job.is_some() || !RELOAD_REQUESTED.swap(false, Ordering::Relaxed) {
+        return;
+    }
+
+    info!("SIGHUP: reloading config");
+    let cfg = match config::load() {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("config reload failed (keeping current config): {e}");
+            return;
+        }
+    };
+
+    state.ui = UiCfg::from_cfg(&cfg, state.ui.window_supported);
+    state.cfg = cfg;
+
+    let out_dir = output_dir_from_cfg(&state.cfg);
+    if let Err(e) = std::fs::create_dir_all(&out_dir) {
+        warn!("failed to create output dir '{}': {e}", out_dir.display());
+    }
+
+    info!(
+        "config reloaded: accent_colour=0x{:08X} output dir={}",
+        state.ui.accent_colour,
+        out_dir.display()
+    );
+}
+
+/// How often an idle connected client is checked for monitor changes.
+const CLIENT_POLL: Duration = Duration::from_millis(100);
+
+/// Take the newest list from the output watcher, if monitors changed.
+/// True if `state.outputs` was replaced.
+fn apply_output_updates(state: &mut DaemonState, updates: &Receiver<Vec<OutputInfo>>) -> bool {
+    let Some(outputs) = updates.try_iter().last() else {
+        return false;
+    };
+    if outputs == state.outputs {
+        return false;
+    }
+
+    let names: Vec<&str> = outputs.iter().map(|o| o.name.as_deref().unwrap_or("?")).collect();
+    info!("outputs changed: {} ({})", outputs.len(), names.join(", "));
+    state.outputs = outputs;
+    true
+}
+
+/// Apply monitor changes and tell the connected client about them.
+fn push_output_updates(state: &mut DaemonState, updates: &Receiver<Vec<OutputInfo>>, conn: &mut ClientConn) {
+    if apply_output_updates(state, updates)
+        && let Err(e) = conn.send_event(Event::OutputsChanged { outputs: state.outputs.clone() })
+    {
+        warn!("send OutputsChanged: {e}");
+    }
+}
+
+/// Start a capture for every global shortcut pressed since the last check.
+fn fire_shortcuts(state: &mut DaemonState, shortcuts: &Receiver<Mode>) {
+    while let Ok(mode) = shortcuts.try_recv() {
+        let resp = start_capture(state, &mut LogSink, mode, None, false, CaptureOptions::default());
+        if let Response::Error { code, message } = resp {
+            warn!("shortcut capture failed [{code}]: {message}");
+        }
+    }
+}
+
 fn capit_dir_for_log() -> String {
     // Match output_dir_from_cfg() semantics: treat empty as "not set".
     match std::env::var_os("CAPIT_DIR") {
@@ -61,7 +159,7 @@ fn capit_dir_for_log() -> String {
     }
 }
 
-pub fn run(verbose: bool) -> Result<()> {
+pub fn run(verbose: bool, log_path: &Path) -> Result<()> {
     // Verify Wayland session is alive before starting
     if let Err(e) = session::ensure_wayland_alive() {
         warn!("not running in wayland session: {e}");
@@ -83,10 +181,7 @@ pub fn run(verbose: bool) -> Result<()> {
         }
     };
 
-    let ui = UiCfg {
-        accent_colour: cfg.accent_colour,
-        bar_background_colour: cfg.bar_background_colour,
-    };
+    let ui = UiCfg::from_cfg(&cfg, window_capture_supported());
 
     let sock = default_socket_path();
     info!("socket path: {}", sock.display());
@@ -131,6 +226,8 @@ pub fn run(verbose: bool) -> Result<()> {
     let mut state = DaemonState::default();
     state.cfg = cfg;
     state.ui = ui;
+    state.socket_path = sock.clone();
+    state.log_path = log_path.to_path_buf();
 
     let out_dir = output_dir_from_cfg(&state.cfg);
     if let Err(e) = std::fs::create_dir_all(&out_dir) {
@@ -152,14 +249,28 @@ pub fn run(verbose: bool) -> Result<()> {
     server.set_nonblocking(true)?;
 
     info!("querying Wayland outputs...");
-    let outputs = wayland_outputs::query_outputs().unwrap_or_else(|e| {
+    let outputs = wayland_outputs::query_outputs_within(STARTUP_OUTPUT_SETTLE).unwrap_or_else(|e| {
         warn!("output query failed: {e}");
         Vec::new()
     });
 
-    info!("found {} outputs", outputs.len());
+    if outputs.is_empty() {
+        warn!("no outputs yet; the first capture will query again");
+    } else {
+        info!("found {} outputs", outputs.len());
+    }
     state.outputs = outputs;
 
+    if state.cfg.prewarm_portal {
+        match capture::prewarm_portal() {
+            Ok(version) => info!("screenshot portal ready (version {version})"),
+            Err(e) => warn!("portal prewarm failed (first capture will connect): {e}"),
+        }
+    }
+
+    let shortcuts = state.cfg.enable_global_shortcuts.then(shortcuts::spawn);
+    let output_updates = wayland_outputs::spawn_watcher();
+
     // ------------------------------
     // SESSION MONITORING
     // ------------------------------
@@ -168,6 +279,13 @@ pub fn run(verbose: bool) -> Result<()> {
     info!("session watcher started");
     // ------------------------------
 
+    // Portal prewarm and global shortcuts are set up once above; a reload
+    // doesn't redo them.
+    match install_sighup_handler() {
+        Ok(()) => debug!("SIGHUP reloads config"),
+        Err(e) => warn!("failed to install SIGHUP handler (config reload disabled): {e}"),
+    }
+
     loop {
         // Check shutdown flag before accept
         if shutdown_flag.load(Ordering::Relaxed) {
@@ -180,6 +298,16 @@ pub fn run(verbose: bool) -> Result<()> {
         let mut conn = match server.accept() {
             Ok(c) => c,
             Err(e) if is_would_block(&e) => {
+                // Idle: pick up a config reload and monitor changes, run any
+                // scheduled captures that came due, and captures asked for
+                // by global shortcuts.
+                reload_if_requested(&mut state);
+                apply_output_updates(&mut state, &output_updates);
+                scheduler::fire_due(&mut state);
+                if let Some(rx) = &shortcuts {
+                    fire_shortcuts(&mut state, rx);
+                }
+
                 // Nothing to accept; keep loop responsive to watcher shutdown.
                 std::thread::sleep(Duration::from_millis(100));
                 continue;
@@ -206,13 +334,28 @@ pub fn run(verbose: bool) -> Result<()> {
         conn.handle_hello(&first)?;
 
         debug!("entering request loop...");
-        while let Ok(req) = conn.recv() {
+        loop {
             // Check shutdown flag even during client connection
             if shutdown_flag.load(Ordering::Relaxed) {
                 info!("shutdown requested during client session");
                 return Ok(());
             }
 
+            // Waiting on the client: monitor changes are pushed to it as
+            // they happen, so long-lived UIs can refresh.
+            let req = match conn.recv_timeout(CLIENT_POLL) {
+                Ok(Some(req)) => req,
+                Ok(None) => {
+                    push_output_updates(&mut state, &output_updates, &mut conn);
+                    continue;
+                }
+                Err(_) => break,
+            };
+
+            // Between requests, never during one.
+            reload_if_requested(&mut state);
+            push_output_updates(&mut state, &output_updates, &mut conn);
+
             debug!("request: {:?}", req);
             let resp = handle_request(&mut state, &mut selection, &mut conn, req);
             debug!("sending response: {:?}", resp);
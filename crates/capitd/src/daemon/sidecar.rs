@@ -0,0 +1,87 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Metadata sidecars: `capit-<ts>.png.json` next to each saved capture when
+// `capit.sidecar_json` is on. Per-file and machine-readable, so tools can
+// pick up a capture's context without parsing image chunks.
+
+use std::path::{Path, PathBuf};
+
+use capit_core::{Mode, OutputInfo, Rect};
+use eventline::warn;
+
+use crate::capture::SavedImage;
+
+use super::paths::{expand_time_tokens, unix_now};
+
+/// What was captured, independent of how each file was encoded.
+#[derive(Debug, Clone)]
+pub struct CaptureMeta {
+    pub mode: Mode,
+    /// Captured area in global (logical) coordinates.
+    pub rect: Rect,
+    /// Names of the outputs the area covers.
+    pub outputs: Vec<String>,
+}
+
+impl CaptureMeta {
+    /// Meta for `rect`, listing every output it overlaps.
+    pub fn for_rect(mode: Mode, rect: Rect, outputs: &[OutputInfo]) -> Self {
+        let outputs = outputs
+            .iter()
+            .filter(|o| o.logical_rect().overlaps(&rect))
+            .filter_map(|o| o.name.clone())
+            .collect();
+        Self { mode, rect, outputs }
+    }
+}
+
+/// Sidecar path for a saved image: the image name plus `.json`.
+pub fn sidecar_path(image: &Path) -> PathBuf {
+    let mut name = image.as_os_str().to_os_string();
+    name.push(".json");
+    PathBuf::from(name)
+}
+
+/// Write one sidecar per saved file. Failures only warn: the image is saved.
+pub fn write_sidecars(meta: &CaptureMeta, out_paths: &[PathBuf], saved: &[SavedImage]) {
+    let now = unix_now();
+    let compositor = std::env::var("XDG_CURRENT_DESKTOP")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".into());
+
+    for (path, img) in out_paths.iter().zip(saved) {
+        let format = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("png")
+            .to_ascii_lowercase();
+
+        let doc = serde_json::json!({
+            "path": path.display().to_string(),
+            "mode": format!("{:?}", meta.mode).to_ascii_lowercase(),
+            "outputs": meta.outputs,
+            "rect": { "x": meta.rect.x, "y": meta.rect.y, "w": meta.rect.w, "h": meta.rect.h },
+            "timestamp": now,
+            "local_time": expand_time_tokens("%Y-%m-%dT%H:%M:%S", now),
+            "format": format,
+            "width": img.width,
+            "height": img.height,
+            "bytes": img.bytes,
+            "compositor": compositor,
+        });
+
+        let sidecar = sidecar_path(path);
+        let text = match serde_json::to_string_pretty(&doc) {
+            Ok(t) => t + "\n",
+            Err(e) => {
+                warn!("sidecar: encode {}: {e}", sidecar.display());
+                continue;
+            }
+        };
+        if let Err(e) = std::fs::write(&sidecar, text) {
+            warn!("sidecar: write {}: {e}", sidecar.display());
+        }
+    }
+}
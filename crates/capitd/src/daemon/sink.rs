@@ -0,0 +1,67 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use std::time::{Duration, Instant};
+
+use capit_core::ErrorCode;
+use capit_ipc::{ClientConn, Event, Request, Response};
+
+use eventline::{debug, info};
+
+/// Where capture handlers push async events.
+pub trait EventSink {
+    fn send_event(&mut self, ev: Event) -> capit_ipc::Result<()>;
+
+    /// Block for `timeout`, returning early with `true` if the capture should
+    /// be abandoned. The default has nobody to ask, so it just sleeps.
+    fn wait_for_cancel(&mut self, timeout: Duration) -> bool {
+        std::thread::sleep(timeout);
+        false
+    }
+}
+
+impl EventSink for ClientConn {
+    fn send_event(&mut self, ev: Event) -> capit_ipc::Result<()> {
+        ClientConn::send_event(self, ev)
+    }
+
+    /// A `Cancel` request or the client hanging up (e.g. Ctrl-C) cancels.
+    fn wait_for_cancel(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            match self.recv_timeout(left) {
+                Ok(None) => return false,
+                Ok(Some(Request::Cancel)) => {
+                    info!("capture cancelled by client during countdown");
+                    return true;
+                }
+                Ok(Some(other)) => {
+                    debug!("busy, rejecting {:?} during countdown", other);
+                    let _ = self.send(Response::Error {
+                        code: ErrorCode::Busy,
+                        message: "busy: a capture countdown is running".into(),
+                    });
+                }
+                Err(e) => {
+                    info!("client gone during countdown ({e}); cancelling capture");
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+/// For captures with no client attached (scheduled): events only reach the log.
+/// The user still gets the usual desktop notification from the handlers.
+pub struct LogSink;
+
+impl EventSink for LogSink {
+    fn send_event(&mut self, ev: Event) -> capit_ipc::Result<()> {
+        info!("event (no client): {:?}", ev);
+        Ok(())
+    }
+}
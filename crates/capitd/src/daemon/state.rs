@@ -1,14 +1,20 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
 use capit_core::{Mode, OutputInfo};
 use crate::config::CapitConfig;
+use super::scheduler::Scheduler;
 use capit_ipc::protocol::UiConfig;
 
 #[derive(Debug, Clone, Copy)]
 pub struct UiCfg {
     pub accent_colour: u32,        // ARGB 0xAARRGGBB
-    pub bar_background_colour: u32 // ARGB 0xAARRGGBB
+    pub bar_background_colour: u32, // ARGB 0xAARRGGBB
+    pub bar_margins: [i32; 4],      // top, right, bottom, left
+    pub window_supported: bool,
 }
 
 impl Default for UiCfg {
@@ -16,6 +22,8 @@ impl Default for UiCfg {
         Self {
             accent_colour: 0xFF0A_84FF,
             bar_background_colour: 0xFF0F_1115,
+            bar_margins: [0; 4],
+            window_supported: false,
         }
     }
 }
@@ -26,6 +34,20 @@ pub struct DaemonState {
     pub outputs: Vec<OutputInfo>,
     pub cfg: CapitConfig,
     pub ui: UiCfg,
+
+    // Reported by Status
+    pub last_saved: Option<String>,
+    pub last_error: Option<String>,
+    pub captures_this_session: u64,
+
+    pub scheduler: Scheduler,
+
+    // Reported by GetPaths
+    pub socket_path: PathBuf,
+    pub log_path: PathBuf,
+
+    // Uptime reported by Ping
+    pub started: Instant,
 }
 
 impl Default for DaemonState {
@@ -35,15 +57,46 @@ impl Default for DaemonState {
             outputs: Vec::new(),
             cfg: CapitConfig::default(),
             ui: UiCfg::default(),
+            last_saved: None,
+            last_error: None,
+            captures_this_session: 0,
+            scheduler: Scheduler::default(),
+            socket_path: PathBuf::new(),
+            log_path: PathBuf::new(),
+            started: Instant::now(),
         }
     }
 }
 
+impl DaemonState {
+    pub fn record_saved(&mut self, path: &Path) {
+        self.last_saved = Some(path.display().to_string());
+        self.captures_this_session += 1;
+    }
+
+    pub fn record_error(&mut self, message: &str) {
+        self.last_error = Some(message.to_string());
+    }
+}
+
 impl UiCfg {
+    /// The bar's share of `cfg`. Window support comes from the compositor,
+    /// not the config, so the caller passes it in.
+    pub fn from_cfg(cfg: &CapitConfig, window_supported: bool) -> Self {
+        Self {
+            accent_colour: cfg.accent_colour,
+            bar_background_colour: cfg.bar_background_colour,
+            bar_margins: cfg.bar_margins,
+            window_supported,
+        }
+    }
+
     pub fn to_ipc(self) -> UiConfig {
         UiConfig {
             accent_colour: self.accent_colour,
             bar_background_colour: self.bar_background_colour,
+            bar_margins: self.bar_margins,
+            window_supported: self.window_supported,
         }
     }
 }
@@ -0,0 +1,84 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Golden-image helpers for the headless overlay render tests.
+// Run with CAPIT_UPDATE_GOLDEN=1 to (re)write the reference PNGs.
+
+use std::path::PathBuf;
+
+use image::{Rgba, RgbaImage};
+
+/// Convert a premultiplied ARGB8888 (wl_shm, little-endian) buffer to straight RGBA.
+pub fn argb_to_image(buf: &[u8], w: u32, h: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(w, h);
+
+    for (px, chunk) in img.pixels_mut().zip(buf.chunks_exact(4)) {
+        let (b, g, r, a) = (chunk[0], chunk[1], chunk[2], chunk[3]);
+        let unpremul = |c: u8| {
+            if a == 0 {
+                0
+            } else {
+                ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8
+            }
+        };
+        *px = Rgba([unpremul(r), unpremul(g), unpremul(b), a]);
+    }
+
+    img
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata/golden")
+        .join(format!("{name}.png"))
+}
+
+/// Scratch path for a rendered frame (outside the source tree).
+pub fn scratch_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("capit-golden-{}-{name}.png", std::process::id()))
+}
+
+/// Compare the PNG at `rendered` against `testdata/golden/<name>.png`.
+pub fn assert_golden(name: &str, rendered: &std::path::Path) {
+    let actual = image::open(rendered)
+        .unwrap_or_else(|e| panic!("read rendered {}: {e}", rendered.display()))
+        .to_rgba8();
+    let golden = golden_path(name);
+
+    if std::env::var_os("CAPIT_UPDATE_GOLDEN").is_some() {
+        if let Some(dir) = golden.parent() {
+            std::fs::create_dir_all(dir).expect("create golden dir");
+        }
+        actual
+            .save(&golden)
+            .unwrap_or_else(|e| panic!("write golden {}: {e}", golden.display()));
+        return;
+    }
+
+    let expected = image::open(&golden)
+        .unwrap_or_else(|e| {
+            panic!(
+                "read golden {}: {e} (run with CAPIT_UPDATE_GOLDEN=1 to create it)",
+                golden.display()
+            )
+        })
+        .to_rgba8();
+
+    assert_eq!(
+        actual.dimensions(),
+        expected.dimensions(),
+        "{name}: size differs from golden"
+    );
+
+    let diff = actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, e)| a != e)
+        .count();
+
+    assert!(
+        diff == 0,
+        "{name}: {diff} pixel(s) differ from golden; rendered frame kept at {}",
+        rendered.display()
+    );
+}
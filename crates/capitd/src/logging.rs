@@ -3,12 +3,36 @@
 
 use std::future::Future;
 use std::path::Path;
-use std::pin::Pin;
-use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
 
 use eventline::runtime::{self, LogLevel};
 
-pub fn init_logging(log_path: &Path, verbose: bool) -> Result<(), String> {
+const PARK_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Set by `-vv`/`--trace`: log overlay Wayland events (see `wl_trace!`).
+static WAYLAND_TRACE: AtomicBool = AtomicBool::new(false);
+
+pub fn wayland_trace_enabled() -> bool {
+    WAYLAND_TRACE.load(Ordering::Relaxed)
+}
+
+/// Debug-level log of a Wayland event, only when Wayland tracing is on.
+/// Kept off plain `-v` so configures/focus changes don't drown normal debug output.
+macro_rules! wl_trace {
+    ($($arg:tt)*) => {
+        if $crate::logging::wayland_trace_enabled() {
+            eventline::debug!("wayland: {}", format_args!($($arg)*));
+        }
+    };
+}
+pub(crate) use wl_trace;
+
+pub fn init_logging(log_path: &Path, verbose: bool, wayland_trace: bool) -> Result<(), String> {
     if let Some(parent) = log_path.parent() {
         std::fs::create_dir_all(parent).map_err(|e| format!("create log dir: {e}"))?;
     }
@@ -27,37 +51,45 @@ pub fn init_logging(log_path: &Path, verbose: bool) -> Result<(), String> {
         runtime::set_log_level(LogLevel::Info);
     }
 
+    WAYLAND_TRACE.store(wayland_trace, Ordering::Relaxed);
+
     // Optional: log after init so it respects the console setting.
     // (When verbose=false, this goes to file only.)
     eventline::debug!(
-        "logging initialized: console={} level={:?} file={}",
+        "logging initialized: console={} level={:?} wayland_trace={} file={}",
         verbose,
         if verbose { LogLevel::Debug } else { LogLevel::Info },
+        wayland_trace,
         log_path.display()
     );
 
     Ok(())
 }
 
-fn block_on<F: Future>(mut fut: F) -> F::Output {
-    unsafe fn clone(_: *const ()) -> RawWaker {
-        RawWaker::new(std::ptr::null(), &VTABLE)
+/// Waker that unparks the thread blocked in `block_on`.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
     }
-    unsafe fn wake(_: *const ()) {}
-    unsafe fn wake_by_ref(_: *const ()) {}
-    unsafe fn drop(_: *const ()) {}
 
-    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
 
-    let waker = unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) };
+/// Run `fut` to completion on this thread, parking while it is pending.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
     let mut cx = Context::from_waker(&waker);
-
-    let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+    let mut fut = pin!(fut);
 
     loop {
         match fut.as_mut().poll(&mut cx) {
             Poll::Ready(v) => return v,
-            Poll::Pending => std::thread::yield_now(),
+            // Timeout only guards against a future that never wakes us.
+            Poll::Pending => thread::park_timeout(PARK_TIMEOUT),
         }
     }
 }
@@ -1,11 +1,19 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+mod bus;
 mod capture;
+mod clipboard;
 mod config;
+mod font;
+#[cfg(test)]
+mod golden;
 mod overlay_region;
 mod overlay_screen;
+mod portal;
 mod selection;
+mod shortcuts;
+mod wallpaper;
 mod wayland_outputs;
 mod daemon;
 mod logging;
@@ -16,9 +24,13 @@ use clap::Parser;
 #[derive(Debug, Parser)]
 #[command(name = "capitd", version, about = "Capit daemon — screenshot capture daemon")]
 struct Args {
-    /// Log to stderr (in addition to the log file)
-    #[arg(short = 'v', long)]
-    verbose: bool,
+    /// Log to stderr (in addition to the log file); repeat (-vv) to also trace overlay Wayland events
+    #[arg(short = 'v', long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Same as -vv: debug logging plus overlay Wayland event tracing
+    #[arg(long)]
+    trace: bool,
 
     /// Override log file path (default: $XDG_STATE_HOME/capit/capitd.log)
     #[arg(long)]
@@ -57,12 +69,14 @@ fn main() {
     let log_path = args
         .log_file
         .unwrap_or_else(|| daemon::default_log_path("capitd.log"));
+    let wayland_trace = args.trace || args.verbose >= 2;
+    let verbose = wayland_trace || args.verbose > 0;
 
     // Init logging FIRST. This decides whether console is on.
     // NOTE: logging::init_logging must explicitly disable console when verbose=false:
     //   runtime::enable_console_output(verbose);
     //   runtime::enable_console_color(verbose);
-    if let Err(e) = logging::init_logging(&log_path, args.verbose) {
+    if let Err(e) = logging::init_logging(&log_path, verbose, wayland_trace) {
         // At this point logging may not be initialized; last-resort stderr is acceptable.
         // If you truly want "never stderr", you can just exit(1) silently here.
         eprintln!("capitd: failed to init logging: {e}");
@@ -71,7 +85,7 @@ fn main() {
 
     // From here on: eventline only.
     eventline::info!("capitd starting");
-    eventline::debug!("verbose={}", args.verbose);
+    eventline::debug!("verbose={} wayland_trace={}", verbose, wayland_trace);
     eventline::debug!("log_path={}", log_path.display());
 
     let cap_dir_env = std::env::var_os("CAPIT_DIR")
@@ -86,7 +100,7 @@ fn main() {
         eventline::warn!("failed to create output dir '{}': {e}", out_dir.display());
     }
 
-    if let Err(e) = daemon::run(args.verbose) {
+    if let Err(e) = daemon::run(verbose, &log_path) {
         // Keep it eventline-only, then exit.
         eventline::error!("fatal error: {e}");
         std::process::exit(1);
@@ -1,7 +1,8 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use capit_core::{OutputInfo, Rect};
+use capit_core::{desktop_bounds, OutputInfo, Rect};
+use capit_shm::premultiply;
 
 use smithay_client_toolkit::{
     output::{OutputHandler, OutputState},
@@ -11,7 +12,7 @@ use smithay_client_toolkit::{
 
 use wayland_client::{
     protocol::{
-        wl_buffer, wl_compositor, wl_keyboard, wl_output, wl_pointer, wl_seat, wl_shm, wl_shm_pool,
+        wl_buffer, wl_callback, wl_compositor, wl_keyboard, wl_output, wl_pointer, wl_seat, wl_shm, wl_shm_pool,
         wl_surface,
     },
     Connection, Dispatch, QueueHandle, WEnum, Proxy,
@@ -21,12 +22,33 @@ use wayland_cursor::CursorTheme;
 
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
-use super::model::{self, DragMode, RectLocal};
+use crate::config::BorderStyle;
+use crate::logging::wl_trace;
+
+use super::model::{self, DragMode, RectLocal, Snap, SnapGuides};
+use super::render::{Frozen, Loupe, Theme, DASH_PERIOD, DEFAULT_DIM_A};
 use super::surfaces::OutputSurface;
 
 const BTN_LEFT: u32 = 272;
 const KEY_ESC: u32 = 1;
 const KEY_ENTER: u32 = 28;
+const KEY_LEFTCTRL: u32 = 29;
+const KEY_G: u32 = 34;
+const KEY_LEFTSHIFT: u32 = 42;
+const KEY_Z: u32 = 44;
+const KEY_RIGHTSHIFT: u32 = 54;
+const KEY_RIGHTCTRL: u32 = 97;
+const KEY_UP: u32 = 103;
+const KEY_LEFT: u32 = 105;
+const KEY_RIGHT: u32 = 106;
+const KEY_DOWN: u32 = 108;
+
+// Arrow-key step in pixels (Shift for the coarse one)
+const NUDGE_STEP: i32 = 1;
+const NUDGE_STEP_COARSE: i32 = 10;
+
+// Marching ants speed: one pixel of dash offset per this many ms
+const MARCH_MS_PER_PX: u32 = 40;
 
 // Same default you use elsewhere (bar, etc.)
 const DEFAULT_ACCENT: u32 = 0xFF0A_84FF;
@@ -47,6 +69,17 @@ pub struct App {
 
     // Theme
     pub accent_colour: u32,
+    pub handle_outer_colour: u32,
+    pub handle_inner_colour: u32,
+    pub border_style: BorderStyle,
+    pub dash_phase: i32,
+    pub dim_alpha: u8,
+    pub border_thickness: Option<i32>,
+    pub handle_size: Option<i32>,
+
+    // Frame callback in flight (marching ants animation)
+    pub frame_pending: bool,
+    pub qh: Option<QueueHandle<App>>,
 
     // Wayland objects
     pub compositor: Option<wl_compositor::WlCompositor>,
@@ -73,6 +106,41 @@ pub struct App {
     pub drag_mode: DragMode,
     pub grab_cursor: (i32, i32),
     pub grab_rect: RectLocal,
+    // Last press inside the selection, for double-click to confirm
+    pub last_click: Option<model::Click>,
+
+    // Holding Shift disables centre/thirds snapping while moving, and
+    // toggles the aspect-ratio lock while resizing.
+    pub shift_down: bool,
+    // Ctrl turns arrow-key nudges into resizes, and disables all snapping
+    // while dragging.
+    pub ctrl_down: bool,
+    /// `capit.snap_threshold` / `capit.grid_size` in px; 0 turns either off.
+    pub snap_threshold: i32,
+    pub grid_size: i32,
+    /// `capit.region_default_ratio` (w / h): resizes keep it unless Shift is held.
+    pub default_ratio: Option<f64>,
+    pub snap_guides: SnapGuides,
+
+    // Desktop still taken before the overlay opened: drawn behind the
+    // overlay and/or sampled by the loupe. 'z' toggles the loupe.
+    pub frozen: Option<Frozen>,
+    pub loupe_visible: bool,
+
+    // Rule-of-thirds lines inside the selection; 'g' toggles them.
+    pub show_grid: bool,
+
+    // Colour picker (`capit pick`): no selection, a click confirms the
+    // 1x1 rect under the cursor.
+    pub pick: bool,
+
+    // Keyboard focus tracking (some panels steal it from the overlay)
+    pub cancel_on_focus_loss: bool,
+    pub has_keyboard_focus: bool,
+    pub had_keyboard_focus: bool,
+
+    // Crop the confirmed rect to what is actually on an output
+    pub crop_to_outputs: bool,
 
     pub pending_redraw: bool,
     pub result: Option<Option<Rect>>,
@@ -84,19 +152,11 @@ impl App {
         output_state: OutputState,
         outputs: Vec<OutputInfo>,
         target_output_idx: usize,
-        accent_colour: u32,
+        theme: Theme,
+        cancel_on_focus_loss: bool,
+        crop_to_outputs: bool,
     ) -> Self {
-        let (min_x, min_y, max_x, max_y) = outputs.iter().fold(
-            (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
-            |(min_x, min_y, max_x, max_y), o| {
-                (
-                    min_x.min(o.x),
-                    min_y.min(o.y),
-                    max_x.max(o.x + o.width),
-                    max_y.max(o.y + o.height),
-                )
-            },
-        );
+        let bounds = desktop_bounds(&outputs);
 
         let target_output = &outputs[target_output_idx];
         let init_w = (target_output.width / 2).clamp(260, target_output.width.max(1));
@@ -104,19 +164,33 @@ impl App {
         let init_x = target_output.x + (target_output.width - init_w) / 2;
         let init_y = target_output.y + (target_output.height - init_h) / 2;
 
-        let accent = if accent_colour == 0 { DEFAULT_ACCENT } else { accent_colour };
+        // Config colours are straight alpha; the buffers want premultiplied.
+        let accent = if theme.accent_colour == 0 { DEFAULT_ACCENT } else { theme.accent_colour };
+        let accent = premultiply(accent);
+        let handle_outer = theme.handle_outer_colour.map_or(accent, premultiply);
+        let handle_inner = theme.handle_inner_colour.map_or(handle_outer, premultiply);
 
         Self {
             registry_state,
             output_state,
             outputs,
             target_output_idx,
-            desktop_min_x: min_x,
-            desktop_min_y: min_y,
-            desktop_max_x: max_x,
-            desktop_max_y: max_y,
+            desktop_min_x: bounds.x,
+            desktop_min_y: bounds.y,
+            desktop_max_x: bounds.x + bounds.w,
+            desktop_max_y: bounds.y + bounds.h,
 
             accent_colour: accent,
+            handle_outer_colour: handle_outer,
+            handle_inner_colour: handle_inner,
+            border_style: theme.border_style,
+            dash_phase: 0,
+            dim_alpha: theme.dim_alpha.unwrap_or(DEFAULT_DIM_A),
+            border_thickness: theme.border_thickness,
+            handle_size: theme.handle_size,
+
+            frame_pending: false,
+            qh: None,
 
             compositor: None,
             shm: None,
@@ -145,6 +219,26 @@ impl App {
             drag_mode: DragMode::None,
             grab_cursor: (0, 0),
             grab_rect: RectLocal::default(),
+            last_click: None,
+
+            shift_down: false,
+            ctrl_down: false,
+            default_ratio: None,
+            snap_threshold: model::EDGE_SNAP_THRESHOLD,
+            grid_size: 0,
+            snap_guides: SnapGuides::default(),
+
+            frozen: None,
+            loupe_visible: true,
+            show_grid: false,
+
+            pick: false,
+
+            cancel_on_focus_loss,
+            has_keyboard_focus: false,
+            had_keyboard_focus: false,
+
+            crop_to_outputs,
 
             pending_redraw: true,
             result: None,
@@ -192,13 +286,151 @@ impl App {
         surf.commit();
     }
 
+    /// Start from `saved` (the last confirmed region) instead of the centred
+    /// default, fitted to the current outputs. Kept as is if it no longer fits.
+    pub fn restore_selection(&mut self, saved: Rect) {
+        let desktop = RectLocal {
+            x: self.desktop_min_x,
+            y: self.desktop_min_y,
+            w: self.desktop_max_x - self.desktop_min_x,
+            h: self.desktop_max_y - self.desktop_min_y,
+        };
+        let saved = RectLocal { x: saved.x, y: saved.y, w: saved.w, h: saved.h };
+
+        if let Some(r) = model::restored_selection(saved, desktop, &self.output_rects()) {
+            self.selection = r;
+            self.cursor = (r.x + r.w / 2, r.y + r.h / 2);
+        }
+    }
+
+    /// Output rects in global coords.
+    fn output_rects(&self) -> Vec<RectLocal> {
+        self.outputs
+            .iter()
+            .map(|o| RectLocal { x: o.x, y: o.y, w: o.width, h: o.height })
+            .collect()
+    }
+
+    /// Update the selection from the current drag (cursor vs grab point).
+    pub fn apply_drag_to_cursor(&mut self) {
+        let outputs = self.output_rects();
+        let snap = if self.ctrl_down {
+            Snap::default()
+        } else {
+            Snap {
+                outputs: &outputs,
+                guides: !self.shift_down,
+                threshold: self.snap_threshold,
+                grid: self.grid_size,
+            }
+        };
+
+        let (selection, guides) = model::apply_drag(
+            self.drag_mode,
+            self.cursor,
+            self.grab_cursor,
+            self.grab_rect,
+            self.desktop_min_x,
+            self.desktop_min_y,
+            self.desktop_max_x,
+            self.desktop_max_y,
+            snap,
+            self.locked_ratio(),
+        );
+
+        // Clamping uses the whole desktop box, which in non-contiguous layouts
+        // includes dead space. Keep the last position that was on an output
+        // rather than parking the selection where nothing can be captured.
+        if model::visible_part(selection, &outputs).is_none() {
+            return;
+        }
+
+        self.selection = selection;
+        self.snap_guides = guides;
+    }
+
+    /// Aspect ratio a resize must keep, if any. A configured default ratio
+    /// applies unless Shift is held; otherwise Shift on a corner keeps the
+    /// ratio the selection had when the drag started.
+    fn locked_ratio(&self) -> Option<f64> {
+        let DragMode::Resize(dir) = self.drag_mode else {
+            return None;
+        };
+        match self.default_ratio {
+            Some(r) if !self.shift_down => Some(r),
+            Some(_) => None,
+            None if self.shift_down && dir.is_corner() && self.grab_rect.h > 0 => {
+                Some(self.grab_rect.w as f64 / self.grab_rect.h as f64)
+            }
+            None => None,
+        }
+    }
+
+    /// Arrow-key adjustment: move by one step, or resize with Ctrl held.
+    /// Ignored mid-drag, where the pointer owns the selection.
+    pub fn nudge(&mut self, dx: i32, dy: i32) {
+        if !matches!(self.drag_mode, DragMode::None) {
+            return;
+        }
+        let step = if self.shift_down { NUDGE_STEP_COARSE } else { NUDGE_STEP };
+        let desktop = RectLocal {
+            x: self.desktop_min_x,
+            y: self.desktop_min_y,
+            w: self.desktop_max_x - self.desktop_min_x,
+            h: self.desktop_max_y - self.desktop_min_y,
+        };
+        let selection = model::nudge(self.selection, dx * step, dy * step, self.ctrl_down, desktop);
+        if model::visible_part(selection, &self.output_rects()).is_none() {
+            return;
+        }
+        self.selection = selection;
+        self.snap_guides = SnapGuides::default();
+        self.request_redraw();
+    }
+
     pub fn cancel(&mut self) {
         self.result = Some(None);
     }
 
+    /// Called after each dispatch batch, so a Leave immediately followed by an
+    /// Enter on another of our surfaces doesn't count as losing focus.
+    pub fn check_focus_loss(&mut self) {
+        if self.had_keyboard_focus && !self.has_keyboard_focus && !self.is_finished() {
+            if self.cancel_on_focus_loss {
+                eprintln!("Region overlay lost keyboard focus; cancelling (capit.cancel_on_focus_loss)");
+                self.cancel();
+            } else {
+                eprintln!("Warning: region overlay lost keyboard focus; Esc/Enter won't work until it returns");
+                // Only warn once per loss.
+                self.had_keyboard_focus = false;
+            }
+        }
+    }
+
     pub fn confirm(&mut self) {
+        if self.pick {
+            let (x, y) = self.cursor;
+            self.result = Some(Some(Rect { x, y, w: 1, h: 1 }));
+            return;
+        }
+
         let mut r = self.selection;
         r.clamp_to(self.desktop_min_x, self.desktop_min_y, self.desktop_max_x, self.desktop_max_y);
+
+        if self.crop_to_outputs {
+            match model::visible_part(r, &self.output_rects()) {
+                Some(v) if (v.x, v.y, v.w, v.h) != (r.x, r.y, r.w, r.h) => {
+                    eprintln!(
+                        "Warning: selection {}x{}+{}+{} extends past the outputs; cropped to {}x{}+{}+{}",
+                        r.w, r.h, r.x, r.y, v.w, v.h, v.x, v.y
+                    );
+                    r = v;
+                }
+                Some(_) => {}
+                None => eprintln!("Warning: selection lies entirely off-screen; saving it unchanged"),
+            }
+        }
+
         self.result = Some(Some(Rect {
             x: r.x,
             y: r.y,
@@ -207,6 +439,41 @@ impl App {
         }));
     }
 
+    /// Magnifier contents around the cursor, if the loupe should show:
+    /// needs a frozen image, not toggled off, and no drag in progress.
+    pub fn loupe(&self) -> Option<Loupe> {
+        let frozen = self.frozen.as_ref().filter(|f| f.loupe)?;
+        if !self.loupe_visible || !matches!(self.drag_mode, DragMode::None) {
+            return None;
+        }
+
+        let desktop = RectLocal {
+            x: self.desktop_min_x,
+            y: self.desktop_min_y,
+            w: self.desktop_max_x - self.desktop_min_x,
+            h: self.desktop_max_y - self.desktop_min_y,
+        };
+        let (px, py) = frozen.image_px(desktop, self.cursor.0, self.cursor.1);
+
+        Some(Loupe::sample(&frozen.image, self.cursor, px, py))
+    }
+
+    pub fn toggle_loupe(&mut self) {
+        if !self.frozen.as_ref().is_some_and(|f| f.loupe) {
+            return;
+        }
+        self.loupe_visible = !self.loupe_visible;
+        self.request_redraw();
+    }
+
+    pub fn toggle_grid(&mut self) {
+        if self.pick {
+            return;
+        }
+        self.show_grid = !self.show_grid;
+        self.request_redraw();
+    }
+
     pub fn is_finished(&self) -> bool {
         self.result.is_some()
     }
@@ -267,6 +534,25 @@ impl Dispatch<wl_surface::WlSurface, ()> for App {
     fn event(_: &mut Self, _: &wl_surface::WlSurface, _: wl_surface::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
 }
 
+impl Dispatch<wl_callback::WlCallback, ()> for App {
+    fn event(
+        state: &mut Self,
+        _: &wl_callback::WlCallback,
+        event: wl_callback::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { callback_data } = event {
+            // callback_data is a timestamp in ms; derive the offset from it so
+            // the speed doesn't depend on the refresh rate.
+            state.frame_pending = false;
+            state.dash_phase = ((callback_data / MARCH_MS_PER_PX) % DASH_PERIOD as u32) as i32;
+            state.request_redraw();
+        }
+    }
+}
+
 impl Dispatch<zwlr_layer_shell_v1::ZwlrLayerShellV1, ()> for App {
     fn event(_: &mut Self, _: &zwlr_layer_shell_v1::ZwlrLayerShellV1, _: zwlr_layer_shell_v1::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
 }
@@ -282,6 +568,7 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for App {
     ) {
         match event {
             zwlr_layer_surface_v1::Event::Configure { serial, width, height } => {
+                wl_trace!("layer_surface {} configure serial={serial} size={width}x{height}", proxy.id());
                 proxy.ack_configure(serial);
 
                 super::surfaces::handle_layer_configure(state, proxy, width, height, qh);
@@ -296,7 +583,10 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for App {
                 state.pending_redraw = true;
                 state.request_redraw();
             }
-            zwlr_layer_surface_v1::Event::Closed => state.cancel(),
+            zwlr_layer_surface_v1::Event::Closed => {
+                wl_trace!("layer_surface {} closed by compositor", proxy.id());
+                state.cancel();
+            }
             _ => {}
         }
     }
@@ -312,6 +602,7 @@ impl Dispatch<wl_seat::WlSeat, ()> for App {
         qh: &QueueHandle<Self>,
     ) {
         if let wl_seat::Event::Capabilities { capabilities } = event {
+            wl_trace!("seat {} capabilities {capabilities:?}", seat.id());
             if let WEnum::Value(caps) = capabilities {
                 if caps.contains(wl_seat::Capability::Pointer) && state.pointer.is_none() {
                     state.pointer = Some(seat.get_pointer(qh, ()));
@@ -339,6 +630,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for App {
     ) {
         match event {
             wl_pointer::Event::Enter { serial, surface_x, surface_y, surface, .. } => {
+                wl_trace!("pointer enter surface {} at {surface_x:.0},{surface_y:.0}", surface.id());
                 state.set_cursor_image(pointer, serial);
 
                 if let Some((idx, os)) = state
@@ -363,16 +655,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for App {
                         state.cursor = (global_x, global_y);
 
                         if !matches!(state.drag_mode, DragMode::None) {
-                            state.selection = model::apply_drag(
-                                state.drag_mode,
-                                state.cursor,
-                                state.grab_cursor,
-                                state.grab_rect,
-                                state.desktop_min_x,
-                                state.desktop_min_y,
-                                state.desktop_max_x,
-                                state.desktop_max_y,
-                            );
+                            state.apply_drag_to_cursor();
                         }
 
                         state.request_redraw();
@@ -380,16 +663,35 @@ impl Dispatch<wl_pointer::WlPointer, ()> for App {
                 }
             }
 
-            wl_pointer::Event::Button { button, state: btn_state, .. } => {
+            wl_pointer::Event::Button { time, button, state: btn_state, .. } => {
                 if button != BTN_LEFT {
                     return;
                 }
 
                 match btn_state {
+                    WEnum::Value(wl_pointer::ButtonState::Pressed) if state.pick => state.confirm(),
+
                     WEnum::Value(wl_pointer::ButtonState::Pressed) => {
+                        let handle_size = state.handle_size.unwrap_or(model::HANDLE_SIZE);
+                        let mode = model::hit_test(state.selection, state.cursor.0, state.cursor.1, handle_size);
+
+                        // Double-click inside the selection confirms, like Enter. Only
+                        // the body counts: a press on a handle or outside resizes.
+                        let click = model::Click { time, x: state.cursor.0, y: state.cursor.1 };
+                        let on_body = matches!(mode, DragMode::Move);
+                        let double = on_body
+                            && state.last_click.is_some_and(|prev| model::is_double_click(prev, click));
+                        if double && state.selection.w > 0 && state.selection.h > 0 {
+                            state.last_click = None;
+                            state.drag_mode = DragMode::None;
+                            state.confirm();
+                            return;
+                        }
+                        state.last_click = on_body.then_some(click);
+
                         state.grab_cursor = state.cursor;
                         state.grab_rect = state.selection;
-                        state.drag_mode = model::hit_test(state.selection, state.cursor.0, state.cursor.1);
+                        state.drag_mode = mode;
 
                         // If click started outside selection but we are resizing, anchor grab cursor
                         // to the handle we're effectively dragging.
@@ -415,19 +717,12 @@ impl Dispatch<wl_pointer::WlPointer, ()> for App {
                     WEnum::Value(wl_pointer::ButtonState::Released) => {
                         // Apply once on release too, so a click-without-drag still moves that corner/edge.
                         if !matches!(state.drag_mode, DragMode::None) {
-                            state.selection = model::apply_drag(
-                                state.drag_mode,
-                                state.cursor,
-                                state.grab_cursor,
-                                state.grab_rect,
-                                state.desktop_min_x,
-                                state.desktop_min_y,
-                                state.desktop_max_x,
-                                state.desktop_max_y,
-                            );
+                            state.apply_drag_to_cursor();
                         }
 
+                        // Guides are only shown while dragging.
                         state.drag_mode = DragMode::None;
+                        state.snap_guides = SnapGuides::default();
                         state.request_redraw();
                     }
 
@@ -450,14 +745,52 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for App {
         _: &QueueHandle<Self>,
     ) {
         match event {
+            wl_keyboard::Event::Enter { surface, .. } => {
+                wl_trace!("keyboard enter surface {}", surface.id());
+                state.has_keyboard_focus = true;
+                state.had_keyboard_focus = true;
+
+                // Re-assert exclusive keyboard interactivity on the surface that
+                // got focus, so a panel can't quietly keep it on the next configure.
+                if let Some(os) = state.output_surfaces.iter().find(|os| os.surface == surface) {
+                    os.layer_surface.set_keyboard_interactivity(
+                        zwlr_layer_surface_v1::KeyboardInteractivity::Exclusive,
+                    );
+                    os.surface.commit();
+                }
+            }
+
+            wl_keyboard::Event::Leave { surface, .. } => {
+                wl_trace!("keyboard leave surface {}", surface.id());
+                state.has_keyboard_focus = false;
+                state.shift_down = false;
+                state.ctrl_down = false;
+            }
+
             wl_keyboard::Event::Key { key, state: key_state, .. } => {
+                // Track shift state (no keymap needed)
+                if key == KEY_LEFTSHIFT || key == KEY_RIGHTSHIFT {
+                    state.shift_down = key_state == WEnum::Value(wl_keyboard::KeyState::Pressed);
+                    return;
+                }
+                if key == KEY_LEFTCTRL || key == KEY_RIGHTCTRL {
+                    state.ctrl_down = key_state == WEnum::Value(wl_keyboard::KeyState::Pressed);
+                    return;
+                }
+
                 if key_state != WEnum::Value(wl_keyboard::KeyState::Pressed) {
                     return;
                 }
-                if key == KEY_ESC {
-                    state.cancel();
-                } else if key == KEY_ENTER {
-                    state.confirm();
+                match key {
+                    KEY_ESC => state.cancel(),
+                    KEY_ENTER => state.confirm(),
+                    KEY_LEFT => state.nudge(-1, 0),
+                    KEY_RIGHT => state.nudge(1, 0),
+                    KEY_UP => state.nudge(0, -1),
+                    KEY_DOWN => state.nudge(0, 1),
+                    KEY_Z => state.toggle_loupe(),
+                    KEY_G => state.toggle_grid(),
+                    _ => {}
                 }
             }
             _ => {}
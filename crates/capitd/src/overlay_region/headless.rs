@@ -0,0 +1,201 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Headless rendering of the region overlay (no compositor, no wl_surface).
+// Used by the golden-image tests to catch border/handle/dim regressions.
+
+use std::path::Path;
+
+use super::model::RectLocal;
+use super::render::{draw_output, Frame};
+
+/// Render `frame` for a synthetic output (global rect) and write it as a PNG,
+/// over `backdrop` (a frozen desktop, `output.w` x `output.h`) if given.
+pub fn render_to_png(
+    frame: &Frame,
+    output: RectLocal,
+    backdrop: Option<&[u32]>,
+    path: &Path,
+) -> Result<(), String> {
+    if output.w <= 0 || output.h <= 0 {
+        return Err(format!("invalid output size {}x{}", output.w, output.h));
+    }
+
+    let mut buf = vec![0u8; (output.w * output.h * 4) as usize];
+    draw_output(&mut buf, output.w, output.h, output.x, output.y, frame, backdrop);
+
+    crate::golden::argb_to_image(&buf, output.w as u32, output.h as u32)
+        .save(path)
+        .map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::{assert_golden, scratch_path};
+    use crate::config::BorderStyle;
+    use crate::overlay_region::model::{SnapGuides, BORDER_THICKNESS, HANDLE_SIZE};
+    use crate::overlay_region::render::{Loupe, DEFAULT_DIM_A, LOUPE_SRC};
+
+    const ACCENT: u32 = 0xFF0A_84FF;
+
+    const OUTPUT: RectLocal = RectLocal { x: 0, y: 0, w: 320, h: 200 };
+
+    /// The default look around `selection`; tests override what they exercise.
+    fn base_frame(selection: RectLocal) -> Frame {
+        Frame {
+            selection,
+            accent_colour: ACCENT,
+            handle_outer_colour: ACCENT,
+            handle_inner_colour: ACCENT,
+            snap_guides: SnapGuides::default(),
+            border_style: BorderStyle::Dashed,
+            dash_phase: 0,
+            dim_alpha: DEFAULT_DIM_A,
+            loupe: None,
+            grid: false,
+            border_thickness: BORDER_THICKNESS,
+            handle_size: HANDLE_SIZE,
+        }
+    }
+
+    fn check(name: &str, frame: Frame, output: RectLocal) {
+        check_over(name, frame, output, None);
+    }
+
+    fn check_over(name: &str, frame: Frame, output: RectLocal, backdrop: Option<&[u32]>) {
+        let path = scratch_path(name);
+        render_to_png(&frame, output, backdrop, &path).unwrap();
+        assert_golden(name, &path);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn region_selection() {
+        let frame = base_frame(RectLocal { x: 60, y: 40, w: 160, h: 100 });
+        check("region_selection", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_thick_border_and_handles() {
+        // capit.region_border_thickness / region_handle_size, e.g. for HiDPI.
+        let frame = Frame {
+            border_style: BorderStyle::Solid,
+            border_thickness: 5,
+            handle_size: 24,
+            ..base_frame(RectLocal { x: 60, y: 40, w: 160, h: 100 })
+        };
+        check("region_thick", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_thirds_grid() {
+        // 'g': rule-of-thirds lines, clipped to the selection.
+        let frame = Frame {
+            grid: true,
+            ..base_frame(RectLocal { x: 60, y: 40, w: 180, h: 120 })
+        };
+        check("region_grid", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_no_selection_on_output() {
+        // Selection lives on another output: this one is fully dimmed.
+        let frame = base_frame(RectLocal { x: 400, y: 40, w: 100, h: 80 });
+        check("region_dim_only", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_selection_spanning_outputs() {
+        // Second output to the right; selection straddles the seam.
+        let frame = base_frame(RectLocal { x: 260, y: 50, w: 140, h: 90 });
+        check("region_span_left", frame, OUTPUT);
+        check(
+            "region_span_right",
+            frame,
+            RectLocal { x: 320, y: 0, w: 320, h: 200 },
+        );
+    }
+
+    #[test]
+    fn region_snap_guides() {
+        let frame = Frame {
+            snap_guides: SnapGuides { x: Some(160), y: Some(100) },
+            ..base_frame(RectLocal { x: 100, y: 60, w: 120, h: 80 })
+        };
+        check("region_snap_guides", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_solid_border() {
+        let frame = Frame {
+            border_style: BorderStyle::Solid,
+            ..base_frame(RectLocal { x: 60, y: 40, w: 160, h: 100 })
+        };
+        check("region_solid", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_marching_phase() {
+        let frame = Frame {
+            border_style: BorderStyle::Marching,
+            dash_phase: 5,
+            ..base_frame(RectLocal { x: 60, y: 40, w: 160, h: 100 })
+        };
+        check("region_marching", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_themed_handles() {
+        let frame = Frame {
+            handle_outer_colour: 0xFF20_2020,
+            handle_inner_colour: 0xFFFF_FFFF,
+            ..base_frame(RectLocal { x: 60, y: 40, w: 160, h: 100 })
+        };
+        check("region_themed_handles", frame, OUTPUT);
+    }
+
+    #[test]
+    fn region_loupe() {
+        // Checkerboard source; the cursor sits right of the loupe's default
+        // corner, so it moves to the top-left.
+        let mut pixels = [0xFFFF_FFFF; LOUPE_SRC * LOUPE_SRC];
+        for (i, px) in pixels.iter_mut().enumerate() {
+            if (i % LOUPE_SRC + i / LOUPE_SRC) % 2 == 1 {
+                *px = 0xFF30_3030;
+            }
+        }
+        let frame = Frame {
+            loupe: Some(Loupe { cursor: (300, 20), pixels }),
+            ..base_frame(RectLocal { x: 180, y: 110, w: 100, h: 60 })
+        };
+        check("region_loupe", frame, OUTPUT);
+        // Other output: the loupe stays with the cursor.
+        check(
+            "region_dim_only",
+            Frame { selection: RectLocal { x: 400, y: 40, w: 100, h: 80 }, ..frame },
+            RectLocal { x: -320, y: 0, w: 320, h: 200 },
+        );
+    }
+
+    #[test]
+    fn region_frozen_backdrop() {
+        // Horizontal gradient standing in for the frozen desktop: dimmed
+        // outside the selection, untouched inside it.
+        let backdrop: Vec<u32> = (0..OUTPUT.w * OUTPUT.h)
+            .map(|i| {
+                let v = (i % OUTPUT.w * 255 / OUTPUT.w) as u32;
+                0xFF00_0000 | v << 16 | 0x80 << 8 | (255 - v)
+            })
+            .collect();
+        let frame = base_frame(RectLocal { x: 60, y: 40, w: 160, h: 100 });
+        check_over("region_frozen", frame, OUTPUT, Some(&backdrop));
+    }
+
+    #[test]
+    fn rejects_empty_output() {
+        let frame = base_frame(RectLocal::default());
+        let out = RectLocal { x: 0, y: 0, w: 0, h: 10 };
+        assert!(render_to_png(&frame, out, None, &scratch_path("empty")).is_err());
+    }
+}
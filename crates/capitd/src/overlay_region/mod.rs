@@ -2,11 +2,14 @@
 // License: MIT
 
 mod app;
+#[cfg(test)]
+mod headless;
 mod model;
 mod pixels;
 mod render;
 mod run;
-mod shm;
 mod surfaces;
 
-pub use run::run_region_overlay;
+pub use model::EDGE_SNAP_THRESHOLD;
+pub use render::{Frozen, Theme};
+pub use run::{layer_shell_available, run_pick_overlay, run_region_overlay, RegionOptions, NO_LAYER_SHELL};
@@ -10,6 +10,17 @@ pub const HANDLE_HIT: i32 = 14;
 pub const MIN_W: i32 = 8;
 pub const MIN_H: i32 = 8;
 
+// How close (px) the selection centre must be to a guide before it snaps.
+pub const GUIDE_SNAP_THRESHOLD: i32 = 8;
+
+// Default distance (px) at which a dragged edge snaps to an output edge or
+// grid line (`capit.snap_threshold`).
+pub const EDGE_SNAP_THRESHOLD: i32 = 8;
+
+// Two presses this close in time (ms) and space (px) make a double-click.
+pub const DOUBLE_CLICK_MS: u32 = 350;
+pub const DOUBLE_CLICK_SLOP: i32 = 4;
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct RectLocal {
     pub x: i32,
@@ -39,6 +50,53 @@ impl RectLocal {
     pub fn contains(&self, px: i32, py: i32) -> bool {
         px >= self.x && py >= self.y && px < (self.x + self.w) && py < (self.y + self.h)
     }
+
+    /// Overlap with `other`, or None if they don't touch.
+    pub fn intersect(&self, other: &RectLocal) -> Option<RectLocal> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.w).min(other.x + other.w);
+        let y1 = (self.y + self.h).min(other.y + other.h);
+        (x1 > x0 && y1 > y0).then_some(RectLocal { x: x0, y: y0, w: x1 - x0, h: y1 - y0 })
+    }
+}
+
+/// Part of `r` that is actually on an output: the bounding box of its overlap
+/// with each output. Differs from `r` when it pokes past the desktop or into
+/// gaps between differently sized outputs. None if entirely off-screen.
+pub fn visible_part(r: RectLocal, outputs: &[RectLocal]) -> Option<RectLocal> {
+    outputs
+        .iter()
+        .filter_map(|o| r.intersect(o))
+        .reduce(|a, b| {
+            let x0 = a.x.min(b.x);
+            let y0 = a.y.min(b.y);
+            let x1 = (a.x + a.w).max(b.x + b.w);
+            let y1 = (a.y + a.h).max(b.y + b.h);
+            RectLocal { x: x0, y: y0, w: x1 - x0, h: y1 - y0 }
+        })
+}
+
+/// A saved selection fitted to the current layout: moved inside the desktop
+/// box, then cut to what lies on an output. None if too little of it is
+/// left on screen (outputs were unplugged or rearranged).
+pub fn restored_selection(
+    saved: RectLocal,
+    desktop: RectLocal,
+    outputs: &[RectLocal],
+) -> Option<RectLocal> {
+    let mut r = saved;
+    r.clamp_to(desktop.x, desktop.y, desktop.x + desktop.w, desktop.y + desktop.h);
+    let v = visible_part(r, outputs)?;
+    (v.w >= MIN_W && v.h >= MIN_H).then_some(v)
+}
+
+/// Alignment guides the selection is currently snapped to (global coords).
+/// `x` is a vertical line, `y` a horizontal one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SnapGuides {
+    pub x: Option<i32>,
+    pub y: Option<i32>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -60,6 +118,27 @@ impl ResizeDir {
     pub fn any(&self) -> bool {
         self.left || self.right || self.top || self.bottom
     }
+
+    /// Dragging a corner (one horizontal and one vertical edge).
+    pub fn is_corner(&self) -> bool {
+        (self.left || self.right) && (self.top || self.bottom)
+    }
+}
+
+/// A left-button press: compositor timestamp (ms) and global position.
+#[derive(Clone, Copy, Debug)]
+pub struct Click {
+    pub time: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// True if `next` completes a double-click started by `prev`.
+pub fn is_double_click(prev: Click, next: Click) -> bool {
+    // Timestamps wrap around; a press "before" prev is just a very late one.
+    next.time.wrapping_sub(prev.time) <= DOUBLE_CLICK_MS
+        && (next.x - prev.x).abs() <= DOUBLE_CLICK_SLOP
+        && (next.y - prev.y).abs() <= DOUBLE_CLICK_SLOP
 }
 
 fn dist2(ax: i32, ay: i32, bx: i32, by: i32) -> i64 {
@@ -68,11 +147,11 @@ fn dist2(ax: i32, ay: i32, bx: i32, by: i32) -> i64 {
     dx * dx + dy * dy
 }
 
-fn corner_hit(selection: RectLocal, px: i32, py: i32) -> Option<ResizeDir> {
+fn corner_hit(selection: RectLocal, px: i32, py: i32, handle_size: i32) -> Option<ResizeDir> {
     // Corner handles are centered on the rectangle corners.
     // Use a circular hit region so grab feels "round".
     let r = selection;
-    let rad = HANDLE_HIT.max(HANDLE_SIZE / 2);
+    let rad = HANDLE_HIT.max(handle_size / 2);
     let rad2 = (rad as i64) * (rad as i64);
 
     let tl = dist2(px, py, r.x, r.y);
@@ -162,11 +241,12 @@ fn nearest_corner_dir(selection: RectLocal, px: i32, py: i32) -> ResizeDir {
     }
 }
 
-pub fn hit_test(selection: RectLocal, px: i32, py: i32) -> DragMode {
+/// `handle_size` is the drawn handle diameter; big handles grab as far as they reach.
+pub fn hit_test(selection: RectLocal, px: i32, py: i32, handle_size: i32) -> DragMode {
     let r = selection;
 
     // 1) Corners first (circular grab zones)
-    if let Some(dir) = corner_hit(r, px, py) {
+    if let Some(dir) = corner_hit(r, px, py, handle_size) {
         return DragMode::Resize(dir);
     }
 
@@ -202,6 +282,169 @@ pub fn hit_test(selection: RectLocal, px: i32, py: i32) -> DragMode {
     }
 }
 
+/// Nearest guide (centre or thirds line of `start..start+len`) within the threshold.
+fn nearest_guide(centre: i32, start: i32, len: i32) -> Option<i32> {
+    [start + len / 3, start + len / 2, start + (2 * len) / 3]
+        .into_iter()
+        .map(|g| (g, (g - centre).abs()))
+        .filter(|&(_, d)| d <= GUIDE_SNAP_THRESHOLD)
+        .min_by_key(|&(_, d)| d)
+        .map(|(g, _)| g)
+}
+
+/// Snap the centre of `r` to the centre/thirds lines of the output containing it.
+fn snap_centre_to_guides(r: &mut RectLocal, outputs: &[RectLocal]) -> SnapGuides {
+    let cx = r.x + r.w / 2;
+    let cy = r.y + r.h / 2;
+
+    let Some(out) = outputs.iter().find(|o| o.contains(cx, cy)) else {
+        return SnapGuides::default();
+    };
+
+    let mut guides = SnapGuides::default();
+    if let Some(gx) = nearest_guide(cx, out.x, out.w) {
+        r.x += gx - cx;
+        guides.x = Some(gx);
+    }
+    if let Some(gy) = nearest_guide(cy, out.y, out.h) {
+        r.y += gy - cy;
+        guides.y = Some(gy);
+    }
+    guides
+}
+
+/// What a drag may snap to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Snap<'a> {
+    /// Output rects: their edges attract the selection's edges, and their
+    /// centre/thirds lines its centre while moving.
+    pub outputs: &'a [RectLocal],
+    /// Centre/thirds guide snapping (off while Shift is held).
+    pub guides: bool,
+    /// How close (px) an edge must be to snap; 0 disables edge/grid snapping.
+    pub threshold: i32,
+    /// Grid pitch in px (global coords); 0 = no grid.
+    pub grid: i32,
+}
+
+impl Snap<'_> {
+    /// Nearest output edge or grid line to `v` within the threshold.
+    /// `edges` picks the output edges on the axis in question.
+    fn edge(&self, v: i32, edges: impl Fn(&RectLocal) -> [i32; 2]) -> Option<i32> {
+        if self.threshold <= 0 {
+            return None;
+        }
+        let grid = (self.grid > 0).then(|| {
+            let below = v.div_euclid(self.grid) * self.grid;
+            [below, below + self.grid]
+        });
+        self.outputs
+            .iter()
+            .flat_map(edges)
+            .chain(grid.into_iter().flatten())
+            .map(|e| (e, (e - v).abs()))
+            .filter(|&(_, d)| d <= self.threshold)
+            .min_by_key(|&(_, d)| d)
+            .map(|(e, _)| e)
+    }
+
+    fn edge_x(&self, x: i32) -> Option<i32> {
+        self.edge(x, |o| [o.x, o.x + o.w])
+    }
+
+    fn edge_y(&self, y: i32) -> Option<i32> {
+        self.edge(y, |o| [o.y, o.y + o.h])
+    }
+}
+
+/// Shift a span `start..start+len` so whichever end is closer to a snap
+/// line sits on it. Returns the shift, if any.
+fn snap_span(start: i32, len: i32, snap: impl Fn(i32) -> Option<i32>) -> Option<i32> {
+    let lo = snap(start).map(|e| e - start);
+    let hi = snap(start + len).map(|e| e - (start + len));
+    match (lo, hi) {
+        (Some(a), Some(b)) => Some(if a.abs() <= b.abs() { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Resize keeping `w / h == ratio`, anchored at the corner (or edge) opposite
+/// the dragged one. The size is fitted to the desktop here, before `clamp_to`,
+/// because `clamp_to` only shifts the rect and would otherwise have to pick
+/// between the edge and the ratio. An edge drag sizes the other axis from
+/// the ratio, growing right/down from the grab rect.
+fn resize_locked(dir: ResizeDir, cursor: (i32, i32), grab: RectLocal, ratio: f64, desktop: RectLocal) -> RectLocal {
+    let (cx, cy) = cursor;
+    let horiz = dir.left || dir.right;
+    let vert = dir.top || dir.bottom;
+
+    let ax = if dir.left { grab.x + grab.w } else { grab.x };
+    let ay = if dir.top { grab.y + grab.h } else { grab.y };
+
+    // Growth direction from the anchor (the cursor may cross it).
+    let left = horiz && cx < ax;
+    let up = vert && cy < ay;
+
+    let mut w = if horiz { (cx - ax).abs() as f64 } else { 0.0 };
+    let mut h = if vert { (cy - ay).abs() as f64 } else { 0.0 };
+    if w >= h * ratio {
+        h = w / ratio;
+    } else {
+        w = h * ratio;
+    }
+
+    let room_w = if left { ax - desktop.x } else { desktop.x + desktop.w - ax } as f64;
+    let room_h = if up { ay - desktop.y } else { desktop.y + desktop.h - ay } as f64;
+    if w > room_w {
+        w = room_w;
+        h = w / ratio;
+    }
+    if h > room_h {
+        h = room_h;
+        w = h * ratio;
+    }
+
+    // The minimum size wins over the desktop edge, like `clamp_to`.
+    if w < MIN_W as f64 {
+        w = MIN_W as f64;
+        h = w / ratio;
+    }
+    if h < MIN_H as f64 {
+        h = MIN_H as f64;
+        w = h * ratio;
+    }
+
+    let (w, h) = (w.round() as i32, h.round() as i32);
+    RectLocal {
+        x: if left { ax - w } else { ax },
+        y: if up { ay - h } else { ay },
+        w,
+        h,
+    }
+}
+
+/// Move `selection` by (dx, dy), or with `resize` grow/shrink it from the
+/// bottom-right corner instead, keeping it on the desktop.
+pub fn nudge(selection: RectLocal, dx: i32, dy: i32, resize: bool, desktop: RectLocal) -> RectLocal {
+    let mut r = selection;
+    if resize {
+        // Cap growth at the desktop edge; `clamp_to` would shift the rect instead.
+        r.w = (r.w + dx).min(desktop.x + desktop.w - r.x);
+        r.h = (r.h + dy).min(desktop.y + desktop.h - r.y);
+    } else {
+        r.x += dx;
+        r.y += dy;
+    }
+    r.clamp_to(desktop.x, desktop.y, desktop.x + desktop.w, desktop.y + desktop.h);
+    r
+}
+
+/// Compute the selection for the current drag.
+///
+/// `snap` says what the selection's edges and centre are pulled to; pass
+/// `Snap::default()` to disable snapping (e.g. Ctrl held).
+/// `ratio` (w / h) locks the aspect ratio while resizing.
+/// Returns the new rect and the guides it snapped to.
 pub fn apply_drag(
     drag_mode: DragMode,
     cursor: (i32, i32),
@@ -211,39 +454,77 @@ pub fn apply_drag(
     desktop_min_y: i32,
     desktop_max_x: i32,
     desktop_max_y: i32,
-) -> RectLocal {
+    snap: Snap<'_>,
+    ratio: Option<f64>,
+) -> (RectLocal, SnapGuides) {
     let (cx, cy) = cursor;
     let dx = cx - grab_cursor.0;
     let dy = cy - grab_cursor.1;
 
     match drag_mode {
-        DragMode::None => grab_rect,
+        DragMode::None => (grab_rect, SnapGuides::default()),
 
         DragMode::Move => {
             let mut r = grab_rect;
             r.x += dx;
             r.y += dy;
+            let mut guides = if snap.guides {
+                snap_centre_to_guides(&mut r, snap.outputs)
+            } else {
+                SnapGuides::default()
+            };
+            // Flush against an output edge or grid line beats a centre guide.
+            if let Some(shift) = snap_span(r.x, r.w, |v| snap.edge_x(v)).filter(|&s| s != 0) {
+                r.x += shift;
+                guides.x = None;
+            }
+            if let Some(shift) = snap_span(r.y, r.h, |v| snap.edge_y(v)).filter(|&s| s != 0) {
+                r.y += shift;
+                guides.y = None;
+            }
+            let (sx, sy) = (r.x, r.y);
             r.clamp_to(desktop_min_x, desktop_min_y, desktop_max_x, desktop_max_y);
-            r
+            // Clamping against the desktop edge wins over a guide.
+            if r.x != sx {
+                guides.x = None;
+            }
+            if r.y != sy {
+                guides.y = None;
+            }
+            (r, guides)
         }
 
         DragMode::Resize(dir) => {
+            if let Some(ratio) = ratio.filter(|r| r.is_finite() && *r > 0.0) {
+                let desktop = RectLocal {
+                    x: desktop_min_x,
+                    y: desktop_min_y,
+                    w: desktop_max_x - desktop_min_x,
+                    h: desktop_max_y - desktop_min_y,
+                };
+                let mut r = resize_locked(dir, cursor, grab_rect, ratio, desktop);
+                r.clamp_to(desktop_min_x, desktop_min_y, desktop_max_x, desktop_max_y);
+                return (r, SnapGuides::default());
+            }
+
             let mut left = grab_rect.x;
             let mut right = grab_rect.x + grab_rect.w;
             let mut top = grab_rect.y;
             let mut bottom = grab_rect.y + grab_rect.h;
 
+            let sx = snap.edge_x(cx).unwrap_or(cx);
+            let sy = snap.edge_y(cy).unwrap_or(cy);
             if dir.left {
-                left = cx;
+                left = sx;
             }
             if dir.right {
-                right = cx;
+                right = sx;
             }
             if dir.top {
-                top = cy;
+                top = sy;
             }
             if dir.bottom {
-                bottom = cy;
+                bottom = sy;
             }
 
             if left > right {
@@ -261,7 +542,245 @@ pub fn apply_drag(
             };
 
             r.clamp_to(desktop_min_x, desktop_min_y, desktop_max_x, desktop_max_y);
-            r
+            (r, SnapGuides::default())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> RectLocal {
+        RectLocal { x, y, w, h }
+    }
+
+    fn same(a: RectLocal, b: RectLocal) -> bool {
+        (a.x, a.y, a.w, a.h) == (b.x, b.y, b.w, b.h)
+    }
+
+    #[test]
+    fn restored_selection_fits_current_layout() {
+        let one = [rect(0, 0, 1920, 1080)];
+        let desk = rect(0, 0, 1920, 1080);
+
+        // Still on screen: unchanged.
+        let saved = rect(100, 100, 400, 300);
+        assert!(same(restored_selection(saved, desk, &one).unwrap(), saved));
+
+        // Saved on a second output that is gone: pulled back in.
+        let got = restored_selection(rect(2000, 100, 400, 300), desk, &one).unwrap();
+        assert!(same(got, rect(1520, 100, 400, 300)), "{got:?}");
+
+        // Larger than the desktop now: cut to it.
+        let got = restored_selection(rect(0, 0, 3840, 1080), desk, &one).unwrap();
+        assert!(same(got, desk), "{got:?}");
+
+        // Only a sliver on an output (gap between mismatched outputs): dropped.
+        let two = [rect(0, 0, 1920, 1080), rect(1920, 0, 1280, 720)];
+        let desk = rect(0, 0, 3200, 1080);
+        assert!(restored_selection(rect(1922, 718, 300, 300), desk, &two).is_none());
+    }
+
+    /// Drag `grab_rect` by (dx, dy) on the desktop box of `outputs`.
+    fn moved(grab_rect: RectLocal, dx: i32, dy: i32, outputs: &[RectLocal]) -> RectLocal {
+        let desktop = outputs.iter().copied().reduce(|a, b| {
+            let x0 = a.x.min(b.x);
+            let y0 = a.y.min(b.y);
+            rect(x0, y0, (a.x + a.w).max(b.x + b.w) - x0, (a.y + a.h).max(b.y + b.h) - y0)
+        });
+        let d = desktop.unwrap();
+        let (r, _) = apply_drag(
+            DragMode::Move,
+            (500 + dx, 500 + dy),
+            (500, 500),
+            grab_rect,
+            d.x,
+            d.y,
+            d.x + d.w,
+            d.y + d.h,
+            Snap::default(),
+            None,
+        );
+        r
+    }
+
+    // 1080p on the left, a taller 1440p output to its right, lower down.
+    const SIDE_BY_SIDE: [RectLocal; 2] = [
+        RectLocal { x: 0, y: 0, w: 1920, h: 1080 },
+        RectLocal { x: 1920, y: 300, w: 2560, h: 1440 },
+    ];
+
+    // Stacked vertically with 120px of dead space between them.
+    const STACKED_WITH_GAP: [RectLocal; 2] = [
+        RectLocal { x: 0, y: 0, w: 1920, h: 1080 },
+        RectLocal { x: 0, y: 1200, w: 1920, h: 1080 },
+    ];
+
+    #[test]
+    fn clamp_uses_the_whole_desktop() {
+        // Crosses from the left output into the lower right one.
+        let mut r = rect(1800, 900, 400, 300);
+        r.clamp_to(0, 0, 4480, 1740);
+        assert!(same(r, rect(1800, 900, 400, 300)));
+    }
+
+    #[test]
+    fn move_crosses_outputs_with_different_origins() {
+        let r = moved(rect(100, 100, 400, 300), 1700, 800, &SIDE_BY_SIDE);
+        assert!(same(r, rect(1800, 900, 400, 300)));
+        // Wholly on screen, so nothing is cropped at confirm.
+        assert!(same(visible_part(r, &SIDE_BY_SIDE).unwrap(), r));
+    }
+
+    #[test]
+    fn move_clamps_at_the_far_desktop_edge_only() {
+        let r = moved(rect(100, 100, 400, 300), 9000, 9000, &SIDE_BY_SIDE);
+        assert!(same(r, rect(4080, 1440, 400, 300)));
+    }
+
+    #[test]
+    fn selection_spanning_a_gap_keeps_both_parts() {
+        let r = rect(100, 1000, 200, 300);
+        assert!(same(visible_part(r, &STACKED_WITH_GAP).unwrap(), r));
+    }
+
+    #[test]
+    fn selection_inside_a_gap_is_not_on_any_output() {
+        let r = moved(rect(100, 100, 200, 100), 0, 990, &STACKED_WITH_GAP);
+        assert!(same(r, rect(100, 1090, 200, 100)));
+        // The overlay refuses this position and keeps the previous one.
+        assert!(visible_part(r, &STACKED_WITH_GAP).is_none());
+    }
+
+    #[test]
+    fn dead_space_beside_a_shorter_output_is_off_screen() {
+        // Below y=1080 on the left is dead space: the right output starts at x=1920.
+        assert!(visible_part(rect(1500, 1100, 300, 200), &SIDE_BY_SIDE).is_none());
+        // Reaching onto the right output makes it capturable again.
+        let r = rect(1700, 1100, 400, 200);
+        assert!(same(visible_part(r, &SIDE_BY_SIDE).unwrap(), rect(1920, 1100, 180, 200)));
+    }
+
+    #[test]
+    fn double_click_needs_two_quick_presses_in_place() {
+        let click = |time, x, y| Click { time, x, y };
+        let first = click(1000, 500, 500);
+        assert!(is_double_click(first, click(1300, 502, 497)));
+        // Too slow, or moved too far in between.
+        assert!(!is_double_click(first, click(1400, 500, 500)));
+        assert!(!is_double_click(first, click(1100, 520, 500)));
+        // Across the timestamp wrap.
+        assert!(is_double_click(click(u32::MAX - 50, 0, 0), click(100, 0, 0)));
+    }
+
+    /// Move `grab_rect` by `dx` with edge snapping on a single 1920x1080 output.
+    fn snapped_move(grab_rect: RectLocal, dx: i32, grid: i32) -> RectLocal {
+        let out = [rect(0, 0, 1920, 1080)];
+        let snap = Snap { outputs: &out, guides: false, threshold: EDGE_SNAP_THRESHOLD, grid };
+        let (r, _) = apply_drag(DragMode::Move, (500 + dx, 500), (500, 500), grab_rect, 0, 0, 1920, 1080, snap, None);
+        r
+    }
+
+    #[test]
+    fn move_snaps_flush_to_output_edges() {
+        // 6px from the left edge: pulled to 0.
+        assert!(same(snapped_move(rect(100, 100, 400, 300), -94, 0), rect(0, 100, 400, 300)));
+        // Right edge 5px short of 1920: pulled to the max edge.
+        assert!(same(snapped_move(rect(100, 100, 400, 300), 1415, 0), rect(1520, 100, 400, 300)));
+        // Outside the threshold: left alone.
+        assert!(same(snapped_move(rect(100, 100, 400, 300), -80, 0), rect(20, 100, 400, 300)));
+    }
+
+    #[test]
+    fn move_snaps_to_the_grid() {
+        // x 203 is 3px past the 200 line of a 50px grid.
+        assert!(same(snapped_move(rect(100, 100, 400, 300), 103, 50), rect(200, 100, 400, 300)));
+    }
+
+    #[test]
+    fn resize_snaps_the_dragged_edge() {
+        let out = [rect(0, 0, 1920, 1080)];
+        let snap = Snap { outputs: &out, guides: false, threshold: EDGE_SNAP_THRESHOLD, grid: 0 };
+        let dir = ResizeDir { left: false, right: true, top: false, bottom: true };
+        let grab = rect(100, 100, 400, 300);
+        let (r, _) =
+            apply_drag(DragMode::Resize(dir), (1914, 1075), (500, 400), grab, 0, 0, 1920, 1080, snap, None);
+        assert!(same(r, rect(100, 100, 1820, 980)), "{r:?}");
+
+        // Snapping off (Ctrl held): the cursor position is used as is.
+        let (r, _) = apply_drag(
+            DragMode::Resize(dir),
+            (1914, 1075),
+            (500, 400),
+            grab,
+            0,
+            0,
+            1920,
+            1080,
+            Snap::default(),
+            None,
+        );
+        assert!(same(r, rect(100, 100, 1814, 975)), "{r:?}");
+    }
+
+    #[test]
+    fn big_handles_grab_as_far_as_they_are_drawn() {
+        let sel = rect(100, 100, 400, 300);
+        // Inside, ~28px from the top-left corner: a move with default handles.
+        assert!(matches!(hit_test(sel, 120, 120, HANDLE_SIZE), DragMode::Move));
+        assert!(matches!(hit_test(sel, 120, 120, 64), DragMode::Resize(d) if d.left && d.top));
+    }
+
+    #[test]
+    fn locked_resize_keeps_the_opposite_corner() {
+        let desktop = rect(0, 0, 1920, 1080);
+        let dir = ResizeDir { left: true, right: false, top: true, bottom: false };
+        let r = resize_locked(dir, (20, 150), rect(100, 100, 160, 90), 16.0 / 9.0, desktop);
+        // Width leads; the height follows the ratio. Bottom-right stays put.
+        assert!(same(r, rect(20, 55, 240, 135)), "{r:?}");
+    }
+
+    #[test]
+    fn locked_resize_fits_the_desktop() {
+        let desktop = rect(0, 0, 1920, 1080);
+        let dir = ResizeDir { left: false, right: true, top: false, bottom: true };
+        let r = resize_locked(dir, (3000, 200), rect(100, 100, 160, 90), 16.0 / 9.0, desktop);
+        // Too tall for the room below: the height caps it, the ratio survives.
+        assert!(same(r, rect(100, 100, 1742, 980)), "{r:?}");
+
+        // Dragged onto the anchor: the minimum size, still at the ratio.
+        let r = resize_locked(dir, (101, 101), rect(100, 100, 160, 90), 16.0 / 9.0, desktop);
+        assert!(same(r, rect(100, 100, 14, 8)), "{r:?}");
+    }
+
+    #[test]
+    fn nudge_moves_by_the_step() {
+        let desktop = rect(0, 0, 1920, 1080);
+        let sel = rect(100, 100, 200, 100);
+        assert!(same(nudge(sel, 1, 0, false, desktop), rect(101, 100, 200, 100)));
+        assert!(same(nudge(sel, 0, -10, false, desktop), rect(100, 90, 200, 100)));
+    }
+
+    #[test]
+    fn nudge_resize_stops_at_the_minimum() {
+        let desktop = rect(0, 0, 1920, 1080);
+        let sel = rect(100, 100, MIN_W, MIN_H);
+        assert!(same(nudge(sel, -10, -1, true, desktop), rect(100, 100, MIN_W, MIN_H)));
+        assert!(same(nudge(sel, 10, 1, true, desktop), rect(100, 100, MIN_W + 10, MIN_H + 1)));
+    }
+
+    #[test]
+    fn nudge_stays_on_the_desktop() {
+        let desktop = rect(-1920, 0, 3840, 1080);
+        // Moves stop flush with the edge instead of overshooting it.
+        let sel = rect(1715, 975, 200, 100);
+        assert!(same(nudge(sel, 10, 10, false, desktop), rect(1720, 980, 200, 100)));
+        let sel = rect(-1915, 0, 200, 100);
+        assert!(same(nudge(sel, -10, 0, false, desktop), rect(-1920, 0, 200, 100)));
+
+        // Growing caps at the edge rather than shifting the rect.
+        let sel = rect(1715, 975, 200, 100);
+        assert!(same(nudge(sel, 10, 10, true, desktop), rect(1715, 975, 205, 105)));
+    }
+}
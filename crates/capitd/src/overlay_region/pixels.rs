@@ -1,13 +1,33 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use super::model::{RectLocal, HANDLE_SIZE};
+use super::model::RectLocal;
 
 pub fn fill_u32(buf: &mut [u8], argb: u32) {
     let (_, body, _) = unsafe { buf.align_to_mut::<u32>() };
     body.fill(argb);
 }
 
+/// Composite premultiplied `buf` over opaque `under` in place ("over"):
+/// the dim wash darkens it, cleared pixels show it unchanged.
+pub fn composite_over(buf: &mut [u8], under: &[u32]) {
+    let (_, body, _) = unsafe { buf.align_to_mut::<u32>() };
+
+    for (px, &bg) in body.iter_mut().zip(under) {
+        let a = *px >> 24;
+        if a == 0xFF {
+            continue;
+        }
+        let inv = 255 - a;
+        let ch = |shift: u32| {
+            let top = (*px >> shift) & 0xFF;
+            let bot = (bg >> shift) & 0xFF;
+            (top + (bot * inv + 127) / 255).min(255)
+        };
+        *px = 0xFF00_0000 | (ch(16) << 16) | (ch(8) << 8) | ch(0);
+    }
+}
+
 pub fn fill_rect_u32(
     buf: &mut [u8],
     w: i32,
@@ -268,6 +288,7 @@ pub fn draw_corner_handles(
     w: i32,
     h: i32,
     r: RectLocal,
+    size: i32,
     outer: u32,
     inner: u32,
 ) {
@@ -279,13 +300,16 @@ pub fn draw_corner_handles(
     ];
 
     for &(cx, cy) in &handles {
-        draw_handle(buf, w, h, cx, cy, outer, inner);
+        draw_handle(buf, w, h, (cx, cy), size, outer, inner);
     }
 }
 
-// Smooth circular handle centered at (cx, cy).
-// Kept signature stable; we intentionally draw solid (inner unused).
-pub fn draw_handle(buf: &mut [u8], w: i32, h: i32, cx: i32, cy: i32, outer: u32, _inner: u32) {
-    let rad = (HANDLE_SIZE / 2).max(2);
+// Smooth circular handle `size` across, centered at (cx, cy).
+// Solid when inner == outer, otherwise with an inner dot.
+pub fn draw_handle(buf: &mut [u8], w: i32, h: i32, (cx, cy): (i32, i32), size: i32, outer: u32, inner: u32) {
+    let rad = (size / 2).max(2);
     fill_circle_aa_u32(buf, w, h, cx, cy, rad, outer);
+    if inner != outer {
+        fill_circle_aa_u32(buf, w, h, cx, cy, rad / 2, inner);
+    }
 }
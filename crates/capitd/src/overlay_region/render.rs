@@ -1,12 +1,18 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::rc::Rc;
+
+use image::RgbaImage;
+
+use crate::config::BorderStyle;
+
 use super::app::App;
-use super::model::{RectLocal, BORDER_THICKNESS};
+use super::model::{RectLocal, SnapGuides, BORDER_THICKNESS, HANDLE_SIZE};
 use super::pixels::*;
 
-const DIM_A: u8 = 0x66;
-const BG_DIM_ARGB: u32 = (DIM_A as u32) << 24;
+/// Background dim alpha when the capture doesn't override it.
+pub const DEFAULT_DIM_A: u8 = 0x66;
 const CLEAR_ARGB: u32 = 0x0000_0000;
 const SHADOW_ARGB_1: u32 = 0x2A00_0000;
 const SHADOW_ARGB_2: u32 = 0x1600_0000;
@@ -14,13 +20,184 @@ const SHADOW_ARGB_2: u32 = 0x1600_0000;
 // Dash styling (tweak to taste)
 const DASH_LEN: i32 = 10; // pixels "on"
 const GAP_LEN: i32 = 6;   // pixels "off"
-const DASH_PHASE: i32 = 0;
+pub const DASH_PERIOD: i32 = DASH_LEN + GAP_LEN;
+
+// Snap guide lines: accent colour at reduced alpha
+const GUIDE_A: u8 = 0xA0;
+// Rule-of-thirds grid: fainter still, so it doesn't compete with the guides
+const GRID_A: u8 = 0x60;
+
+// Magnifier loupe: LOUPE_SRC x LOUPE_SRC frozen pixels around the cursor,
+// drawn LOUPE_ZOOM times larger in a corner of the cursor's output.
+pub const LOUPE_SRC: usize = 16;
+const LOUPE_ZOOM: i32 = 8;
+const LOUPE_MARGIN: i32 = 16;
+const LOUPE_BORDER: i32 = 2;
+// Anything outside the frozen image shows as black.
+const OFF_IMAGE_ARGB: u32 = 0xFF00_0000;
+
+/// Fade a premultiplied ARGB colour by `a`: its alpha becomes alpha * a / 255,
+/// RGB scaled alike so it stays premultiplied (wl_shm ARGB8888).
+fn premultiply_argb(argb: u32, a: u8) -> u32 {
+    let ch = |shift: u32| ((argb >> shift) & 0xFF) * a as u32 / 255;
+    (ch(24) << 24) | (ch(16) << 16) | (ch(8) << 8) | ch(0)
+}
+
+/// Screenshot RGBA to opaque ARGB (the desktop has no transparency).
+fn opaque_argb([r, g, b, _]: [u8; 4]) -> u32 {
+    0xFF00_0000 | (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+/// Colours and border style for the region overlay.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Border colour: `capit.selection_colour`, else the shared accent.
+    pub accent_colour: u32,
+    pub border_style: BorderStyle,
+    /// None = accent colour.
+    pub handle_outer_colour: Option<u32>,
+    /// None = same as outer (solid handle, no centre dot).
+    pub handle_inner_colour: Option<u32>,
+    /// Background dim alpha. None = `DEFAULT_DIM_A`.
+    pub dim_alpha: Option<u8>,
+    /// Border width and handle diameter in px. None = `BORDER_THICKNESS` /
+    /// `HANDLE_SIZE`.
+    pub border_thickness: Option<i32>,
+    pub handle_size: Option<i32>,
+}
+
+/// Desktop still taken before the overlay opened.
+#[derive(Debug, Clone)]
+pub struct Frozen {
+    /// Whole desktop at device scale.
+    pub image: Rc<RgbaImage>,
+    /// Draw it behind the overlay instead of the live desktop (`capit.freeze_on_select`).
+    pub backdrop: bool,
+    /// Feed the magnifier loupe (`capit.region_loupe`).
+    pub loupe: bool,
+}
+
+impl Frozen {
+    /// Pixel of the still under global point (`gx`, `gy`); `desktop` is the
+    /// global bounding box the still covers.
+    pub fn image_px(&self, desktop: RectLocal, gx: i32, gy: i32) -> (i32, i32) {
+        let px = (gx - desktop.x) as i64 * self.image.width() as i64 / desktop.w.max(1) as i64;
+        let py = (gy - desktop.y) as i64 * self.image.height() as i64 / desktop.h.max(1) as i64;
+        (px as i32, py as i32)
+    }
+
+    /// Colour (0xRRGGBB) of the still under global point (`gx`, `gy`).
+    pub fn colour_at(&self, desktop: RectLocal, gx: i32, gy: i32) -> Option<u32> {
+        let (px, py) = self.image_px(desktop, gx, gy);
+        if px < 0 || py < 0 || px >= self.image.width() as i32 || py >= self.image.height() as i32 {
+            return None;
+        }
+        Some(opaque_argb(self.image.get_pixel(px as u32, py as u32).0) & 0x00FF_FFFF)
+    }
+
+    /// Nearest-neighbour copy of the still under `output` (global rect), as
+    /// opaque ARGB rows of `output.w` pixels.
+    pub fn backdrop_for(&self, desktop: RectLocal, output: RectLocal) -> Vec<u32> {
+        let (iw, ih) = (self.image.width() as i32, self.image.height() as i32);
+        let mut out = Vec::with_capacity((output.w.max(0) * output.h.max(0)) as usize);
+
+        for y in 0..output.h {
+            for x in 0..output.w {
+                let (px, py) = self.image_px(desktop, output.x + x, output.y + y);
+                if px < 0 || py < 0 || px >= iw || py >= ih {
+                    out.push(OFF_IMAGE_ARGB);
+                    continue;
+                }
+                out.push(opaque_argb(self.image.get_pixel(px as u32, py as u32).0));
+            }
+        }
+        out
+    }
+}
+
+/// Everything the region overlay draws, independent of Wayland state.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub selection: RectLocal,
+    pub accent_colour: u32,
+    pub handle_outer_colour: u32,
+    pub handle_inner_colour: u32,
+    pub snap_guides: SnapGuides,
+    pub border_style: BorderStyle,
+    /// Dash offset along the perimeter (animated for `Marching`).
+    pub dash_phase: i32,
+    /// Alpha of the black wash outside the selection.
+    pub dim_alpha: u8,
+    /// Magnifier contents; None = no loupe this frame.
+    pub loupe: Option<Loupe>,
+    /// Rule-of-thirds lines inside the selection.
+    pub grid: bool,
+    /// Selection border width and corner handle diameter, in px.
+    pub border_thickness: i32,
+    pub handle_size: i32,
+}
+
+/// What the magnifier shows: frozen desktop pixels centred on the cursor.
+#[derive(Clone, Copy, Debug)]
+pub struct Loupe {
+    /// Cursor in global coords; only the output under it draws the loupe.
+    pub cursor: (i32, i32),
+    /// Row-major opaque ARGB, the cursor's pixel at (LOUPE_SRC / 2, LOUPE_SRC / 2).
+    pub pixels: [u32; LOUPE_SRC * LOUPE_SRC],
+}
+
+impl Loupe {
+    /// Sample `image` around pixel (`px`, `py`) of the image.
+    pub fn sample(image: &RgbaImage, cursor: (i32, i32), px: i32, py: i32) -> Self {
+        let half = (LOUPE_SRC / 2) as i32;
+        let mut pixels = [OFF_IMAGE_ARGB; LOUPE_SRC * LOUPE_SRC];
+
+        for (i, argb) in pixels.iter_mut().enumerate() {
+            let x = px - half + (i % LOUPE_SRC) as i32;
+            let y = py - half + (i / LOUPE_SRC) as i32;
+            if x < 0 || y < 0 || x >= image.width() as i32 || y >= image.height() as i32 {
+                continue;
+            }
+            *argb = opaque_argb(image.get_pixel(x as u32, y as u32).0);
+        }
+
+        Self { cursor, pixels }
+    }
+}
+
+impl Frame {
+    pub fn from_app(app: &App) -> Self {
+        Self {
+            // The colour picker has no selection to show.
+            selection: if app.pick { RectLocal::default() } else { app.selection },
+            accent_colour: app.accent_colour,
+            handle_outer_colour: app.handle_outer_colour,
+            handle_inner_colour: app.handle_inner_colour,
+            snap_guides: app.snap_guides,
+            border_style: app.border_style,
+            dash_phase: app.dash_phase,
+            dim_alpha: app.dim_alpha,
+            loupe: app.loupe(),
+            grid: app.show_grid,
+            border_thickness: app.border_thickness.unwrap_or(BORDER_THICKNESS),
+            handle_size: app.handle_size.unwrap_or(HANDLE_SIZE),
+        }
+    }
+}
 
 pub fn redraw_all(app: &mut App) -> Result<(), String> {
-    // Use daemon-provided accent colour for border + handles
-    let border_argb: u32 = app.accent_colour;
-    let handle_outer_argb: u32 = border_argb;
-    let handle_inner_argb: u32 = border_argb; // no white center dot
+    let frame = Frame::from_app(app);
+
+    // Marching ants: ask for one frame callback per redraw to drive the animation.
+    let qh = app.qh.clone();
+    let backdrop = app.frozen.as_ref().filter(|f| f.backdrop);
+    let desktop = RectLocal {
+        x: app.desktop_min_x,
+        y: app.desktop_min_y,
+        w: app.desktop_max_x - app.desktop_min_x,
+        h: app.desktop_max_y - app.desktop_min_y,
+    };
+    let mut want_frame = frame.border_style == BorderStyle::Marching && !app.frame_pending;
 
     for output_surface in &mut app.output_surfaces {
         if !output_surface.configured {
@@ -35,111 +212,32 @@ pub fn redraw_all(app: &mut App) -> Result<(), String> {
 
         let buf_w = sb.width;
         let buf_h = sb.height;
-        let buf = sb.pixels_mut();
-
-        let output_info = &output_surface.output_info;
-
-        // Convert selection to output-local coords
-        let sel_local = RectLocal {
-            x: app.selection.x - output_info.x,
-            y: app.selection.y - output_info.y,
-            w: app.selection.w,
-            h: app.selection.h,
-        };
-
-        let sel_right = sel_local.x + sel_local.w;
-        let sel_bottom = sel_local.y + sel_local.h;
-
-        let intersects =
-            sel_right > 0 && sel_local.x < buf_w && sel_bottom > 0 && sel_local.y < buf_h;
-
-        if intersects {
-            fill_u32(buf, BG_DIM_ARGB);
-
-            let sel = sel_local;
-            let clip_x = sel.x.max(0);
-            let clip_y = sel.y.max(0);
-            let clip_w = (sel.x + sel.w).min(buf_w) - clip_x;
-            let clip_h = (sel.y + sel.h).min(buf_h) - clip_y;
-
-            if clip_w > 0 && clip_h > 0 {
-                let mostly_visible = sel.x >= -20
-                    && sel.y >= -20
-                    && sel.x + sel.w <= buf_w + 20
-                    && sel.y + sel.h <= buf_h + 20;
-
-                if mostly_visible {
-                    // Shadows remain solid for readability; border becomes dashed.
-                    draw_border_u32(
-                        buf,
-                        buf_w,
-                        buf_h,
-                        sel.x + 2,
-                        sel.y + 2,
-                        sel.w,
-                        sel.h,
-                        BORDER_THICKNESS + 2,
-                        SHADOW_ARGB_2,
-                    );
-                    draw_border_u32(
-                        buf,
-                        buf_w,
-                        buf_h,
-                        sel.x + 1,
-                        sel.y + 1,
-                        sel.w,
-                        sel.h,
-                        BORDER_THICKNESS + 1,
-                        SHADOW_ARGB_1,
-                    );
-
-                    fill_rect_u32(buf, buf_w, buf_h, clip_x, clip_y, clip_w, clip_h, CLEAR_ARGB);
-
-                    draw_dashed_border_u32(
-                        buf,
-                        buf_w,
-                        buf_h,
-                        sel.x,
-                        sel.y,
-                        sel.w,
-                        sel.h,
-                        BORDER_THICKNESS,
-                        border_argb,
-                        DASH_LEN,
-                        GAP_LEN,
-                        DASH_PHASE,
-                    );
-
-                    soften_corners(buf, buf_w, buf_h, sel, BG_DIM_ARGB);
-                    draw_corner_handles(
-                        buf,
-                        buf_w,
-                        buf_h,
-                        sel,
-                        handle_outer_argb,
-                        handle_inner_argb,
-                    );
-                } else {
-                    fill_rect_u32(buf, buf_w, buf_h, clip_x, clip_y, clip_w, clip_h, CLEAR_ARGB);
-
-                    draw_dashed_border_u32(
-                        buf,
-                        buf_w,
-                        buf_h,
-                        sel.x,
-                        sel.y,
-                        sel.w,
-                        sel.h,
-                        BORDER_THICKNESS,
-                        border_argb,
-                        DASH_LEN,
-                        GAP_LEN,
-                        DASH_PHASE,
-                    );
-                }
+        let origin_x = output_surface.output_info.x;
+        let origin_y = output_surface.output_info.y;
+
+        // Built once per buffer size; the still never changes.
+        if let Some(frozen) = backdrop {
+            let len = (buf_w * buf_h) as usize;
+            if output_surface.backdrop.as_ref().is_none_or(|b| b.len() != len) {
+                let output = RectLocal { x: origin_x, y: origin_y, w: buf_w, h: buf_h };
+                output_surface.backdrop = Some(frozen.backdrop_for(desktop, output));
             }
-        } else {
-            fill_u32(buf, BG_DIM_ARGB);
+        }
+
+        draw_output(
+            sb.pixels_mut(),
+            buf_w,
+            buf_h,
+            origin_x,
+            origin_y,
+            &frame,
+            output_surface.backdrop.as_deref(),
+        );
+
+        if want_frame && let Some(qh) = qh.as_ref() {
+            output_surface.surface.frame(qh, ());
+            app.frame_pending = true;
+            want_frame = false;
         }
 
         output_surface.surface.attach(Some(&sb.buffer), 0, 0);
@@ -151,3 +249,249 @@ pub fn redraw_all(app: &mut App) -> Result<(), String> {
     app.pending_redraw = false;
     Ok(())
 }
+
+fn draw_selection_border(
+    buf: &mut [u8],
+    buf_w: i32,
+    buf_h: i32,
+    sel: RectLocal,
+    argb: u32,
+    frame: &Frame,
+) {
+    match frame.border_style {
+        BorderStyle::Solid => draw_border_u32(
+            buf,
+            buf_w,
+            buf_h,
+            sel.x,
+            sel.y,
+            sel.w,
+            sel.h,
+            frame.border_thickness,
+            argb,
+        ),
+        BorderStyle::Dashed | BorderStyle::Marching => draw_dashed_border_u32(
+            buf,
+            buf_w,
+            buf_h,
+            sel.x,
+            sel.y,
+            sel.w,
+            sel.h,
+            frame.border_thickness,
+            argb,
+            DASH_LEN,
+            GAP_LEN,
+            frame.dash_phase,
+        ),
+    }
+}
+
+/// Draw one output's overlay into an ARGB8888 buffer of `buf_w` x `buf_h`.
+/// `origin_x`/`origin_y` is the output's position in global coords.
+/// With a `backdrop` (opaque, `buf_w` x `buf_h`) the overlay is composited
+/// onto it, so the output shows the frozen desktop instead of the live one.
+pub fn draw_output(
+    buf: &mut [u8],
+    buf_w: i32,
+    buf_h: i32,
+    origin_x: i32,
+    origin_y: i32,
+    frame: &Frame,
+    backdrop: Option<&[u32]>,
+) {
+    // Daemon-provided accent colour for the border; handles are themed separately
+    let border_argb: u32 = frame.accent_colour;
+    let handle_outer_argb: u32 = frame.handle_outer_colour;
+    let handle_inner_argb: u32 = frame.handle_inner_colour;
+    let dim_argb: u32 = (frame.dim_alpha as u32) << 24;
+
+    // Convert selection to output-local coords
+    let sel_local = RectLocal {
+        x: frame.selection.x - origin_x,
+        y: frame.selection.y - origin_y,
+        w: frame.selection.w,
+        h: frame.selection.h,
+    };
+
+    let sel_right = sel_local.x + sel_local.w;
+    let sel_bottom = sel_local.y + sel_local.h;
+
+    let intersects =
+        sel_right > 0 && sel_local.x < buf_w && sel_bottom > 0 && sel_local.y < buf_h;
+
+    if intersects {
+        fill_u32(buf, dim_argb);
+
+        let sel = sel_local;
+        let clip_x = sel.x.max(0);
+        let clip_y = sel.y.max(0);
+        let clip_w = (sel.x + sel.w).min(buf_w) - clip_x;
+        let clip_h = (sel.y + sel.h).min(buf_h) - clip_y;
+
+        if clip_w > 0 && clip_h > 0 {
+            let mostly_visible = sel.x >= -20
+                && sel.y >= -20
+                && sel.x + sel.w <= buf_w + 20
+                && sel.y + sel.h <= buf_h + 20;
+
+            if mostly_visible {
+                // Shadows remain solid for readability; border becomes dashed.
+                draw_border_u32(
+                    buf,
+                    buf_w,
+                    buf_h,
+                    sel.x + 2,
+                    sel.y + 2,
+                    sel.w,
+                    sel.h,
+                    frame.border_thickness + 2,
+                    SHADOW_ARGB_2,
+                );
+                draw_border_u32(
+                    buf,
+                    buf_w,
+                    buf_h,
+                    sel.x + 1,
+                    sel.y + 1,
+                    sel.w,
+                    sel.h,
+                    frame.border_thickness + 1,
+                    SHADOW_ARGB_1,
+                );
+
+                fill_rect_u32(buf, buf_w, buf_h, clip_x, clip_y, clip_w, clip_h, CLEAR_ARGB);
+
+                draw_selection_border(buf, buf_w, buf_h, sel, border_argb, frame);
+
+                soften_corners(buf, buf_w, buf_h, sel, dim_argb);
+                draw_corner_handles(
+                    buf,
+                    buf_w,
+                    buf_h,
+                    sel,
+                    frame.handle_size,
+                    handle_outer_argb,
+                    handle_inner_argb,
+                );
+            } else {
+                fill_rect_u32(buf, buf_w, buf_h, clip_x, clip_y, clip_w, clip_h, CLEAR_ARGB);
+
+                draw_selection_border(buf, buf_w, buf_h, sel, border_argb, frame);
+            }
+
+            if frame.grid {
+                let clip = RectLocal { x: clip_x, y: clip_y, w: clip_w, h: clip_h };
+                draw_thirds(buf, buf_w, buf_h, sel, clip, premultiply_argb(border_argb, GRID_A));
+            }
+        }
+    } else {
+        fill_u32(buf, dim_argb);
+    }
+
+    // Snap guides (only set while moving and a guide is engaged)
+    let guide_argb = premultiply_argb(border_argb, GUIDE_A);
+    if let Some(gx) = frame.snap_guides.x {
+        let lx = gx - origin_x;
+        if lx >= 0 && lx < buf_w {
+            fill_rect_u32(buf, buf_w, buf_h, lx, 0, 1, buf_h, guide_argb);
+        }
+    }
+    if let Some(gy) = frame.snap_guides.y {
+        let ly = gy - origin_y;
+        if ly >= 0 && ly < buf_h {
+            fill_rect_u32(buf, buf_w, buf_h, 0, ly, buf_w, 1, guide_argb);
+        }
+    }
+
+    if let Some(backdrop) = backdrop {
+        composite_over(buf, backdrop);
+    }
+
+    if let Some(loupe) = &frame.loupe {
+        draw_loupe(buf, buf_w, buf_h, origin_x, origin_y, loupe, border_argb);
+    }
+}
+
+/// Two vertical and two horizontal lines splitting `sel` into thirds,
+/// limited to `clip` (the part of the selection on this output).
+fn draw_thirds(buf: &mut [u8], buf_w: i32, buf_h: i32, sel: RectLocal, clip: RectLocal, argb: u32) {
+    for i in 1..3 {
+        let x = sel.x + sel.w * i / 3;
+        if x >= clip.x && x < clip.x + clip.w {
+            fill_rect_u32(buf, buf_w, buf_h, x, clip.y, 1, clip.h, argb);
+        }
+        let y = sel.y + sel.h * i / 3;
+        if y >= clip.y && y < clip.y + clip.h {
+            fill_rect_u32(buf, buf_w, buf_h, clip.x, y, clip.w, 1, argb);
+        }
+    }
+}
+
+/// Draw the magnifier in the top-right corner of the output under the
+/// cursor, or the top-left when the cursor is in the way.
+fn draw_loupe(
+    buf: &mut [u8],
+    buf_w: i32,
+    buf_h: i32,
+    origin_x: i32,
+    origin_y: i32,
+    loupe: &Loupe,
+    border_argb: u32,
+) {
+    let cx = loupe.cursor.0 - origin_x;
+    let cy = loupe.cursor.1 - origin_y;
+    if cx < 0 || cy < 0 || cx >= buf_w || cy >= buf_h {
+        return;
+    }
+
+    let size = LOUPE_SRC as i32 * LOUPE_ZOOM;
+    let y = LOUPE_MARGIN;
+    let mut x = buf_w - size - LOUPE_MARGIN;
+    if cx >= x - LOUPE_MARGIN && cy < y + size + LOUPE_MARGIN {
+        x = LOUPE_MARGIN;
+    }
+
+    let outer = size + 2 * LOUPE_BORDER;
+    draw_border_u32(
+        buf,
+        buf_w,
+        buf_h,
+        x - LOUPE_BORDER + 2,
+        y - LOUPE_BORDER + 2,
+        outer,
+        outer,
+        LOUPE_BORDER,
+        SHADOW_ARGB_1,
+    );
+    draw_border_u32(
+        buf,
+        buf_w,
+        buf_h,
+        x - LOUPE_BORDER,
+        y - LOUPE_BORDER,
+        outer,
+        outer,
+        LOUPE_BORDER,
+        border_argb,
+    );
+
+    for (i, &argb) in loupe.pixels.iter().enumerate() {
+        let col = (i % LOUPE_SRC) as i32;
+        let row = (i / LOUPE_SRC) as i32;
+        fill_rect_u32(
+            buf,
+            buf_w,
+            buf_h,
+            x + col * LOUPE_ZOOM,
+            y + row * LOUPE_ZOOM,
+            LOUPE_ZOOM,
+            LOUPE_ZOOM,
+            argb,
+        );
+    }
+
+    // Outline the pixel under the cursor.
+    let mid = (LOUPE_SRC / 2) as i32 * LOUPE_ZOOM;
+    draw_border_u32(buf, buf_w, buf_h, x + mid, y + mid, LOUPE_ZOOM, LOUPE_ZOOM, 1, border_argb);
+}
@@ -1,11 +1,11 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use capit_core::{OutputInfo, Rect};
+use capit_core::{desktop_bounds, OutputInfo, Rect};
 
 use wayland_client::globals::registry_queue_init;
 use wayland_client::protocol::{wl_compositor, wl_seat, wl_shm};
-use wayland_client::Connection;
+use wayland_client::{Connection, Proxy};
 
 use smithay_client_toolkit::output::OutputState;
 use smithay_client_toolkit::registry::RegistryState;
@@ -13,11 +13,107 @@ use smithay_client_toolkit::registry::RegistryState;
 use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1;
 
 use super::app::App;
+use super::model::RectLocal;
+use super::render::{Frozen, Theme};
+
+/// Error from the region overlay when the compositor has no wlr-layer-shell
+/// (GNOME/Mutter); callers match on it to fall back.
+pub const NO_LAYER_SHELL: &str = "zwlr_layer_shell_v1 not available";
+
+/// Whether the compositor offers wlr-layer-shell, checked before any work
+/// (like the freeze still) that only the overlay needs. A failed connection
+/// counts as yes: the overlay then reports the real error.
+pub fn layer_shell_available() -> bool {
+    let Ok(conn) = Connection::connect_to_env() else {
+        return true;
+    };
+    let Ok((globals, _queue)) = registry_queue_init::<App>(&conn) else {
+        return true;
+    };
+    let name = zwlr_layer_shell_v1::ZwlrLayerShellV1::interface().name;
+    globals.contents().with_list(|list| list.iter().any(|g| g.interface == name))
+}
+
+/// How one region overlay behaves, apart from its colours.
+#[derive(Debug, Clone, Default)]
+pub struct RegionOptions {
+    /// Cancel if another surface takes keyboard focus.
+    pub cancel_on_focus_loss: bool,
+    /// Crop the confirmed rect to what is actually on an output.
+    pub crop_to_outputs: bool,
+    /// Aspect ratio (w / h) resizes keep unless Shift is held.
+    pub default_ratio: Option<f64>,
+    /// Edge snap distance and grid pitch in px; 0 turns either off.
+    pub snap_threshold: i32,
+    pub grid_size: i32,
+    /// Selection to start from (the last confirmed region), fitted to the
+    /// current outputs. None = centred on the target output.
+    pub initial: Option<Rect>,
+    /// Desktop still for the backdrop and loupe.
+    pub frozen: Option<Frozen>,
+}
 
 pub fn run_region_overlay(
     all_outputs: Vec<OutputInfo>,
     target_output_idx: usize,
-    accent_colour: u32,
+    theme: Theme,
+    opts: RegionOptions,
+) -> Result<Option<Rect>, String> {
+    let RegionOptions {
+        cancel_on_focus_loss,
+        crop_to_outputs,
+        default_ratio,
+        snap_threshold,
+        grid_size,
+        initial,
+        frozen,
+    } = opts;
+    run_overlay(all_outputs, target_output_idx, theme, cancel_on_focus_loss, crop_to_outputs, |app| {
+        app.default_ratio = default_ratio;
+        app.snap_threshold = snap_threshold;
+        app.grid_size = grid_size;
+        app.frozen = frozen;
+        if let Some(rect) = initial {
+            app.restore_selection(rect);
+        }
+    })
+}
+
+/// Colour picker: the same overlay without a selection, over the frozen
+/// desktop with the loupe shown. Returns the clicked pixel's colour
+/// (0xRRGGBB) from the still, None if cancelled.
+pub fn run_pick_overlay(
+    all_outputs: Vec<OutputInfo>,
+    target_output_idx: usize,
+    theme: Theme,
+    cancel_on_focus_loss: bool,
+    frozen: Frozen,
+) -> Result<Option<u32>, String> {
+    let b = desktop_bounds(&all_outputs);
+    let desktop = RectLocal { x: b.x, y: b.y, w: b.w, h: b.h };
+    let still = frozen.clone();
+
+    let picked = run_overlay(all_outputs, target_output_idx, theme, cancel_on_focus_loss, false, |app| {
+        app.pick = true;
+        app.frozen = Some(frozen);
+    })?;
+
+    match picked {
+        Some(p) => still
+            .colour_at(desktop, p.x, p.y)
+            .map(Some)
+            .ok_or_else(|| format!("picked point {},{} is outside the screenshot", p.x, p.y)),
+        None => Ok(None),
+    }
+}
+
+fn run_overlay(
+    all_outputs: Vec<OutputInfo>,
+    target_output_idx: usize,
+    theme: Theme,
+    cancel_on_focus_loss: bool,
+    crop_to_outputs: bool,
+    configure: impl FnOnce(&mut App),
 ) -> Result<Option<Rect>, String> {
     if all_outputs.is_empty() {
         return Err("no outputs available".into());
@@ -42,8 +138,12 @@ pub fn run_region_overlay(
         output_state,
         all_outputs,
         target_output_idx,
-        accent_colour,
+        theme,
+        cancel_on_focus_loss,
+        crop_to_outputs,
     );
+    app.qh = Some(qh.clone());
+    configure(&mut app);
 
     app.compositor = globals
         .bind::<wl_compositor::WlCompositor, _, _>(&qh, 1..=6, ())
@@ -60,7 +160,7 @@ pub fn run_region_overlay(
         return Err("wl_compositor not available".into());
     }
     if app.layer_shell.is_none() {
-        return Err("zwlr_layer_shell_v1 not available".into());
+        return Err(NO_LAYER_SHELL.into());
     }
     if app.shm.is_none() {
         return Err("wl_shm not available".into());
@@ -85,6 +185,7 @@ pub fn run_region_overlay(
 
     while !app.is_finished() {
         queue.blocking_dispatch(&mut app).map_err(|e| format!("dispatch: {e}"))?;
+        app.check_focus_loss();
         let _ = conn.flush();
     }
 
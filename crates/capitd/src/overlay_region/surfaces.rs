@@ -12,7 +12,7 @@ use wayland_protocols_wlr::layer_shell::v1::client::{
 };
 
 use super::app::App;
-use super::shm::ShmBuffer;
+use capit_shm::ShmBuffer;
 
 pub struct OutputSurface {
     pub output_info: OutputInfo,
@@ -20,6 +20,8 @@ pub struct OutputSurface {
     pub layer_surface: zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
     pub shm_buf: Option<ShmBuffer>,
     pub configured: bool,
+    /// Frozen desktop under this output (`capit.freeze_on_select`).
+    pub backdrop: Option<Vec<u32>>,
 }
 
 pub fn try_create_surfaces(app: &mut App, qh: &QueueHandle<App>) -> Result<(), String> {
@@ -94,6 +96,7 @@ pub fn try_create_surfaces(app: &mut App, qh: &QueueHandle<App>) -> Result<(), S
             layer_surface,
             shm_buf: Some(shm_buf),
             configured: false,
+            backdrop: None,
         });
 
         surface.commit();
@@ -115,16 +118,22 @@ pub fn handle_layer_configure(
         .iter_mut()
         .find(|os| &os.layer_surface == proxy)
     {
-        let needs_resize = output_surface
-            .shm_buf
-            .as_ref()
-            .map_or(true, |b| b.width != width as i32 || b.height != height as i32);
-
-        if needs_resize && width > 0 && height > 0 {
-            if let Some(shm) = app.shm.as_ref() {
-                if let Ok(new_buf) = ShmBuffer::new(shm, qh, width as i32, height as i32) {
-                    output_surface.shm_buf = Some(new_buf);
-                }
+        // Some compositors send 0x0 and expect the client to pick a size.
+        // We anchor to all edges, so the output's own size is the right answer.
+        let (width, height) = if width == 0 || height == 0 {
+            let info = &output_surface.output_info;
+            (info.width.max(0) as u32, info.height.max(0) as u32)
+        } else {
+            (width, height)
+        };
+
+        if width > 0 && height > 0 {
+            let ensured = match app.shm.as_ref() {
+                Some(shm) => ShmBuffer::ensure(&mut output_surface.shm_buf, shm, qh, width as i32, height as i32),
+                None => Err("no wl_shm".into()),
+            };
+            if let Err(e) = ensured {
+                eprintln!("Warning: failed to resize overlay buffer: {e}");
             }
         }
 
@@ -134,11 +143,10 @@ pub fn handle_layer_configure(
 
 pub fn handle_buffer_release(app: &mut App, buffer: &wayland_client::protocol::wl_buffer::WlBuffer) {
     for os in &mut app.output_surfaces {
-        if let Some(ref mut sb) = os.shm_buf {
-            if &sb.buffer == buffer {
-                sb.busy = false;
-                break;
-            }
+        if let Some(ref mut sb) = os.shm_buf
+            && sb.release(buffer)
+        {
+            break;
         }
     }
 }
@@ -21,6 +21,8 @@ use wayland_cursor::CursorTheme;
 
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
+use crate::logging::wl_trace;
+
 use super::surfaces::OutputSurface;
 
 const BTN_LEFT: u32 = 272;
@@ -47,6 +49,7 @@ pub struct App {
 
     // Theme
     pub accent_colour: u32,
+    pub dim_alpha: u8,
 
     pub compositor: Option<wl_compositor::WlCompositor>,
     pub shm: Option<wl_shm::WlShm>,
@@ -86,6 +89,7 @@ impl App {
             hovered_output_idx: initial_output_idx,
 
             accent_colour,
+            dim_alpha: super::render::DEFAULT_DIM_A,
 
             compositor: None,
             shm: None,
@@ -276,12 +280,16 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for App {
     ) {
         match event {
             zwlr_layer_surface_v1::Event::Configure { serial, width, height } => {
+                wl_trace!("layer_surface {} configure serial={serial} size={width}x{height}", proxy.id());
                 proxy.ack_configure(serial);
                 super::surfaces::handle_layer_configure(state, proxy, width, height, qh);
                 state.pending_redraw = true;
                 state.request_redraw();
             }
-            zwlr_layer_surface_v1::Event::Closed => state.cancel(),
+            zwlr_layer_surface_v1::Event::Closed => {
+                wl_trace!("layer_surface {} closed by compositor", proxy.id());
+                state.cancel();
+            }
             _ => {}
         }
     }
@@ -298,6 +306,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for App {
     ) {
         match event {
             wl_pointer::Event::Enter { serial, surface, .. } => {
+                wl_trace!("pointer enter surface {}", surface.id());
                 state.set_cursor_image(pointer, serial);
 
                 if let Some((idx, os)) = state
@@ -440,6 +449,7 @@ impl Dispatch<wl_seat::WlSeat, ()> for App {
         qh: &QueueHandle<Self>,
     ) {
         if let wl_seat::Event::Capabilities { capabilities } = event {
+            wl_trace!("seat {} capabilities {capabilities:?}", seat.id());
             if let WEnum::Value(caps) = capabilities {
                 if caps.contains(wl_seat::Capability::Pointer) && state.pointer.is_none() {
                     state.pointer = Some(seat.get_pointer(qh, ()));
@@ -0,0 +1,54 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Headless rendering of the screen picker overlay (no compositor, no wl_surface).
+// Used by the golden-image tests to catch border/dim regressions.
+
+use std::path::Path;
+
+use super::render::{draw_output, DEFAULT_DIM_A};
+
+/// Render one output of the picker (hovered or not) and write it as a PNG.
+pub fn render_to_png(
+    width: i32,
+    height: i32,
+    hovered: bool,
+    accent_colour: u32,
+    path: &Path,
+) -> Result<(), String> {
+    if width <= 0 || height <= 0 {
+        return Err(format!("invalid output size {width}x{height}"));
+    }
+
+    let mut buf = vec![0u8; (width * height * 4) as usize];
+    draw_output(&mut buf, width, height, hovered, accent_colour, DEFAULT_DIM_A);
+
+    crate::golden::argb_to_image(&buf, width as u32, height as u32)
+        .save(path)
+        .map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden::{assert_golden, scratch_path};
+
+    const ACCENT: u32 = 0xFF0A_84FF;
+
+    fn check(name: &str, hovered: bool) {
+        let path = scratch_path(name);
+        render_to_png(320, 200, hovered, ACCENT, &path).unwrap();
+        assert_golden(name, &path);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn screen_hovered() {
+        check("screen_hovered", true);
+    }
+
+    #[test]
+    fn screen_idle() {
+        check("screen_idle", false);
+    }
+}
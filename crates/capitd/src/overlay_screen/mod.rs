@@ -2,8 +2,9 @@
 // License: MIT
 
 mod app;
+#[cfg(test)]
+mod headless;
 mod render;
-mod shm;
 mod surfaces;
 
 use capit_core::{OutputInfo, Target};
@@ -20,10 +21,12 @@ use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1;
 
 const DEFAULT_ACCENT: u32 = 0xFF0A_84FF;
 
+/// `dim_alpha` overrides the default dimming of the outputs (per capture).
 pub fn run_screen_overlay(
     all_outputs: Vec<OutputInfo>,
     initial_output_idx: Option<usize>,
     accent_colour: u32,
+    dim_alpha: Option<u8>,
 ) -> Result<Option<Target>, String> {
     if all_outputs.is_empty() {
         return Err("no outputs available".into());
@@ -41,9 +44,12 @@ pub fn run_screen_overlay(
     let registry_state = RegistryState::new(&globals);
     let output_state = OutputState::new(&globals, &qh);
 
-    let accent = if accent_colour == 0 { DEFAULT_ACCENT } else { accent_colour };
+    let accent = capit_shm::premultiply(if accent_colour == 0 { DEFAULT_ACCENT } else { accent_colour });
 
     let mut app = app::App::new(registry_state, output_state, all_outputs, initial_output_idx, accent);
+    if let Some(a) = dim_alpha {
+        app.dim_alpha = a;
+    }
 
     app.compositor = globals.bind::<wl_compositor::WlCompositor, _, _>(&qh, 1..=6, ()).ok();
     app.shm        = globals.bind::<wl_shm::WlShm, _, _>(&qh, 1..=1, ()).ok();
@@ -3,10 +3,9 @@
 
 use super::app::App;
 
-const DIM_A: u8 = 0x88;
-const HOVER_DIM_A: u8 = 0x44;
-const BG_DIM_ARGB: u32 = (DIM_A as u32) << 24;
-const HOVER_DIM_ARGB: u32 = (HOVER_DIM_A as u32) << 24;
+/// Dim alpha for outputs not under the pointer, unless the capture overrides
+/// it. The hovered output gets half as much.
+pub const DEFAULT_DIM_A: u8 = 0x88;
 
 const BORDER_THICKNESS: i32 = 2;
 
@@ -17,8 +16,8 @@ pub fn redraw_all(app: &mut App) -> Result<(), String> {
         .and_then(|o| o.name.as_ref())
         .cloned();
 
-    let border_argb: u32 = app.accent_colour;
-    let border_glow_argb: u32 = (border_argb & 0x00FF_FFFF) | (0x34u32 << 24);
+    let accent_colour = app.accent_colour;
+    let dim_alpha = app.dim_alpha;
 
     for (si, os) in app.output_surfaces.iter_mut().enumerate() {
         if !os.configured {
@@ -33,40 +32,13 @@ pub fn redraw_all(app: &mut App) -> Result<(), String> {
 
         let buf_w = sb.width;
         let buf_h = sb.height;
-        let buf = sb.pixels_mut();
 
         let is_hovered = match (&hovered_name, os.output_info.name.as_ref()) {
             (Some(h), Some(n)) => h == n,
             _ => app.current_surface_idx == Some(si),
         };
 
-        if is_hovered {
-            fill_u32(buf, HOVER_DIM_ARGB);
-            draw_border_u32(
-                buf,
-                buf_w,
-                buf_h,
-                1,
-                1,
-                buf_w - 2,
-                buf_h - 2,
-                BORDER_THICKNESS + 2,
-                border_glow_argb,
-            );
-            draw_border_u32(
-                buf,
-                buf_w,
-                buf_h,
-                2,
-                2,
-                buf_w - 4,
-                buf_h - 4,
-                BORDER_THICKNESS,
-                border_argb,
-            );
-        } else {
-            fill_u32(buf, BG_DIM_ARGB);
-        }
+        draw_output(sb.pixels_mut(), buf_w, buf_h, is_hovered, accent_colour, dim_alpha);
 
         os.surface.attach(Some(&sb.buffer), 0, 0);
         os.surface.damage_buffer(0, 0, buf_w, buf_h);
@@ -78,6 +50,49 @@ pub fn redraw_all(app: &mut App) -> Result<(), String> {
     Ok(())
 }
 
+/// Draw one output's picker overlay into an ARGB8888 buffer of `buf_w` x `buf_h`.
+pub fn draw_output(
+    buf: &mut [u8],
+    buf_w: i32,
+    buf_h: i32,
+    hovered: bool,
+    accent_colour: u32,
+    dim_alpha: u8,
+) {
+    let border_argb: u32 = accent_colour;
+    let dim_argb: u32 = (dim_alpha as u32) << 24;
+    let hover_dim_argb: u32 = ((dim_alpha / 2) as u32) << 24;
+    let border_glow_argb: u32 = (border_argb & 0x00FF_FFFF) | (0x34u32 << 24);
+
+    if hovered {
+        fill_u32(buf, hover_dim_argb);
+        draw_border_u32(
+            buf,
+            buf_w,
+            buf_h,
+            1,
+            1,
+            buf_w - 2,
+            buf_h - 2,
+            BORDER_THICKNESS + 2,
+            border_glow_argb,
+        );
+        draw_border_u32(
+            buf,
+            buf_w,
+            buf_h,
+            2,
+            2,
+            buf_w - 4,
+            buf_h - 4,
+            BORDER_THICKNESS,
+            border_argb,
+        );
+    } else {
+        fill_u32(buf, dim_argb);
+    }
+}
+
 // pixel helpers
 fn fill_u32(buf: &mut [u8], argb: u32) {
     let (_, body, _) = unsafe { buf.align_to_mut::<u32>() };
@@ -12,7 +12,7 @@ use wayland_protocols_wlr::layer_shell::v1::client::{
 };
 
 use super::app::App;
-use super::shm::ShmBuffer;
+use capit_shm::ShmBuffer;
 
 pub struct OutputSurface {
     pub output_info: OutputInfo,
@@ -102,16 +102,22 @@ pub fn handle_layer_configure(
     qh: &QueueHandle<App>,
 ) {
     if let Some(os) = app.output_surfaces.iter_mut().find(|os| &os.layer_surface == proxy) {
-        let needs_resize = os
-            .shm_buf
-            .as_ref()
-            .map_or(true, |b| b.width != width as i32 || b.height != height as i32);
-
-        if needs_resize && width > 0 && height > 0 {
-            if let Some(shm) = app.shm.as_ref() {
-                if let Ok(new_buf) = ShmBuffer::new(shm, qh, width as i32, height as i32) {
-                    os.shm_buf = Some(new_buf);
-                }
+        // Some compositors send 0x0 and expect the client to pick a size.
+        // We anchor to all edges, so the output's own size is the right answer.
+        let (width, height) = if width == 0 || height == 0 {
+            let info = &os.output_info;
+            (info.width.max(0) as u32, info.height.max(0) as u32)
+        } else {
+            (width, height)
+        };
+
+        if width > 0 && height > 0 {
+            let ensured = match app.shm.as_ref() {
+                Some(shm) => ShmBuffer::ensure(&mut os.shm_buf, shm, qh, width as i32, height as i32),
+                None => Err("no wl_shm".into()),
+            };
+            if let Err(e) = ensured {
+                eprintln!("Warning: failed to resize overlay buffer: {e}");
             }
         }
         os.configured = true;
@@ -120,11 +126,10 @@ pub fn handle_layer_configure(
 
 pub fn handle_buffer_release(app: &mut App, buffer: &wl_buffer::WlBuffer) {
     for os in &mut app.output_surfaces {
-        if let Some(ref mut sb) = os.shm_buf {
-            if &sb.buffer == buffer {
-                sb.busy = false;
-                break;
-            }
+        if let Some(ref mut sb) = os.shm_buf
+            && sb.release(buffer)
+        {
+            break;
         }
     }
 }
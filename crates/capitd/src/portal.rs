@@ -0,0 +1,60 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Shared xdg-desktop-portal plumbing. Portal methods return a Request object
+// path right away and deliver the outcome later as its Response signal;
+// screenshots, wallpaper and global shortcuts all wait on it the same way.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_io::Timer;
+use futures_util::{future::select, future::Either, pin_mut, StreamExt};
+
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::{Connection, Proxy};
+
+pub const PORTAL_DEST: &str = "org.freedesktop.portal.Desktop";
+pub const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const REQUEST_IFACE: &str = "org.freedesktop.portal.Request";
+
+/// Unique `handle_token` for a portal call's options.
+pub fn new_handle_token() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("capit_{now}")
+}
+
+/// Wait up to `timeout` for the Response signal of the Request at
+/// `request_path`. Returns the response code (0 success, 1 cancelled by the
+/// user, 2 other failure) with its results; callers decide what non-zero means.
+pub async fn wait_for_response(
+    conn: &Connection,
+    request_path: OwnedObjectPath,
+    timeout: Duration,
+) -> Result<(u32, HashMap<String, OwnedValue>), String> {
+    let request = Proxy::new(conn, PORTAL_DEST, request_path, REQUEST_IFACE)
+        .await
+        .map_err(|e| format!("proxy request: {e}"))?;
+
+    let mut stream = request
+        .receive_signal("Response")
+        .await
+        .map_err(|e| format!("receive Response signal: {e}"))?;
+
+    let next_signal = stream.next();
+    let timeout = Timer::after(timeout);
+    pin_mut!(next_signal, timeout);
+
+    let msg = match select(next_signal, timeout).await {
+        Either::Left((Some(msg), _)) => msg,
+        Either::Left((None, _)) => return Err("portal signal stream ended unexpectedly".into()),
+        Either::Right((_, _)) => return Err("portal request timed out".into()),
+    };
+
+    msg.body()
+        .deserialize()
+        .map_err(|e| format!("signal decode: {e}"))
+}
@@ -1,14 +1,15 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use capit_core::{Mode, Rect, Target};
-use capit_ipc::{Event, Request, Response};
+use capit_core::{ErrorCode, Mode, Rect, Target};
+use capit_ipc::{CaptureOptions, Event, Request, Response};
 
 #[derive(Debug, Clone)]
 pub struct ActiveSelection {
     pub mode: Mode,
     pub target: Option<Target>,
     pub rect: Option<Rect>,
+    pub options: CaptureOptions,
 }
 
 #[derive(Debug, Default)]
@@ -39,12 +40,13 @@ impl SelectionState {
         F: FnMut(Event),
     {
         match req {
-            Request::StartCapture { mode, target, with_ui } => {
+            Request::StartCapture { mode, target, with_ui, options } => {
                 if *with_ui && *mode == Mode::Region {
                     self.active = Some(ActiveSelection {
                         mode: *mode,
                         target: target.clone(),
                         rect: None,
+                        options: options.clone(),
                     });
 
                     emit(Event::CaptureStarted { mode: *mode });
@@ -64,6 +66,7 @@ impl SelectionState {
                 }
 
                 Some(Response::Error {
+                    code: ErrorCode::InvalidRequest,
                     message: "no active selection session".into(),
                 })
             }
@@ -72,6 +75,7 @@ impl SelectionState {
                 if let Some(sel) = self.active.as_ref() {
                     if sel.rect.is_none() {
                         return Some(Response::Error {
+                            code: ErrorCode::InvalidRequest,
                             message: "no selection rect set".into(),
                         });
                     }
@@ -83,6 +87,7 @@ impl SelectionState {
                 }
 
                 Some(Response::Error {
+                    code: ErrorCode::InvalidRequest,
                     message: "no active selection session".into(),
                 })
             }
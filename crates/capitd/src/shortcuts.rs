@@ -0,0 +1,171 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Global shortcuts via xdg-desktop-portal (org.freedesktop.portal.GlobalShortcuts)
+// for `capit.enable_global_shortcuts`: bind `capit.region` and `capit.screen`
+// once at startup, then hand each Activated signal to the daemon loop, which
+// starts the capture as if a client had asked. The keys themselves are chosen
+// in the portal's dialog or the desktop's settings.
+// Runs on its own thread and bus connection: the portal drops the bindings
+// when the session's connection closes, so it lives as long as the daemon.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, Proxy};
+
+use capit_core::Mode;
+use eventline::{debug, info, warn};
+
+use crate::portal::{self, new_handle_token, PORTAL_DEST, PORTAL_PATH};
+
+const SHORTCUTS_IFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+
+/// Binding can wait on the user in a portal dialog.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Shortcut id, description shown by the portal, capture it starts.
+const SHORTCUTS: [(&str, &str, Mode); 2] = [
+    ("capit.region", "Capture a region", Mode::Region),
+    ("capit.screen", "Capture a screen", Mode::Screen),
+];
+
+/// Bind the shortcuts on a worker thread. Captures to start arrive on the
+/// returned channel; without a usable GlobalShortcuts portal the thread
+/// logs why and exits, and the channel simply stays empty.
+pub fn spawn() -> Receiver<Mode> {
+    let (tx, rx) = mpsc::channel();
+
+    let spawned = std::thread::Builder::new()
+        .name("capit-shortcuts".into())
+        .spawn(move || {
+            if let Err(e) = zbus::block_on(run(tx)) {
+                warn!("global shortcuts unavailable: {e}");
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("spawn global shortcuts thread: {e}");
+    }
+
+    rx
+}
+
+async fn run(tx: Sender<Mode>) -> Result<(), String> {
+    let conn = Connection::session()
+        .await
+        .map_err(|e| format!("dbus session connect: {e}"))?;
+
+    let portal = Proxy::new(&conn, PORTAL_DEST, PORTAL_PATH, SHORTCUTS_IFACE)
+        .await
+        .map_err(|e| format!("proxy global shortcuts: {e}"))?;
+
+    // Portals without the interface fail here rather than mid-bind.
+    let version: u32 = portal
+        .get_property("version")
+        .await
+        .map_err(|e| format!("portal has no GlobalShortcuts interface ({e})"))?;
+    debug!("GlobalShortcuts portal version {version}");
+
+    let session = create_session(&conn, &portal).await?;
+    bind_shortcuts(&conn, &portal, &session).await?;
+
+    let mut activated = portal
+        .receive_signal("Activated")
+        .await
+        .map_err(|e| format!("receive Activated signal: {e}"))?;
+
+    while let Some(msg) = activated.next().await {
+        let body: Result<(OwnedObjectPath, String, u64, HashMap<String, OwnedValue>), _> =
+            msg.body().deserialize();
+        let (session_handle, id) = match body {
+            Ok((s, id, _, _)) => (s, id),
+            Err(e) => {
+                warn!("global shortcuts: Activated decode: {e}");
+                continue;
+            }
+        };
+        if session_handle != session {
+            continue;
+        }
+
+        match SHORTCUTS.iter().find(|(sid, ..)| *sid == id) {
+            Some((_, _, mode)) => {
+                info!("global shortcut {id} activated");
+                if tx.send(*mode).is_err() {
+                    // The daemon loop is gone; nothing left to trigger.
+                    return Ok(());
+                }
+            }
+            None => debug!("global shortcuts: ignoring unknown id {id}"),
+        }
+    }
+
+    Err("portal signal stream ended".into())
+}
+
+async fn create_session(conn: &Connection, portal: &Proxy<'_>) -> Result<OwnedObjectPath, String> {
+    let token = new_handle_token();
+    let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+    options.insert("session_handle_token", Value::from(token.as_str()));
+
+    let request: OwnedObjectPath = portal
+        .call("CreateSession", &(options,))
+        .await
+        .map_err(|e| format!("CreateSession() failed: {e}"))?;
+
+    let results = wait_for_response(conn, request).await?;
+    let handle: &str = results
+        .get("session_handle")
+        .ok_or("CreateSession response missing 'session_handle'")?
+        .downcast_ref::<&str>()
+        .map_err(|e| format!("'session_handle' had unexpected type: {e}"))?;
+
+    OwnedObjectPath::try_from(handle).map_err(|e| format!("bad session handle {handle:?}: {e}"))
+}
+
+async fn bind_shortcuts(conn: &Connection, portal: &Proxy<'_>, session: &OwnedObjectPath) -> Result<(), String> {
+    let shortcuts: Vec<(&str, HashMap<&str, Value<'_>>)> = SHORTCUTS
+        .iter()
+        .map(|(id, description, _)| {
+            let mut props: HashMap<&str, Value<'_>> = HashMap::new();
+            props.insert("description", Value::from(*description));
+            (*id, props)
+        })
+        .collect();
+
+    let token = new_handle_token();
+    let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+
+    let parent_window = "";
+
+    let request: OwnedObjectPath = portal
+        .call("BindShortcuts", &(session, shortcuts, parent_window, options))
+        .await
+        .map_err(|e| format!("BindShortcuts() failed: {e}"))?;
+
+    wait_for_response(conn, request).await?;
+    info!(
+        "global shortcuts bound: {}",
+        SHORTCUTS.iter().map(|(id, ..)| *id).collect::<Vec<_>>().join(", ")
+    );
+    Ok(())
+}
+
+/// Wait for the Request's Response signal; non-zero responses are errors.
+async fn wait_for_response(
+    conn: &Connection,
+    request_path: OwnedObjectPath,
+) -> Result<HashMap<String, OwnedValue>, String> {
+    let (response, results) = portal::wait_for_response(conn, request_path, RESPONSE_TIMEOUT).await?;
+    match response {
+        0 => Ok(results),
+        1 => Err("cancelled by the user".into()),
+        _ => Err(format!("portal refused (response={response})")),
+    }
+}
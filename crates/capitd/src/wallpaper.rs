@@ -0,0 +1,65 @@
+// Author: Dustin Pilgrim
+// License: MIT
+//
+// Set a saved capture as the desktop wallpaper via xdg-desktop-portal
+// (org.freedesktop.portal.Wallpaper.SetWallpaperURI), so it works on any
+// desktop whose portal backend implements it rather than per compositor.
+// Blocking like the rest of the daemon; waits for the Request::Response signal.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use zbus::Proxy;
+use zbus::zvariant::{OwnedObjectPath, Value};
+
+use crate::bus;
+use crate::portal::{self, PORTAL_DEST, PORTAL_PATH};
+
+const WALLPAPER_IFACE: &str = "org.freedesktop.portal.Wallpaper";
+
+/// The portal may show its own confirmation; give the user time to answer.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Set `path` (absolute) as the background wallpaper.
+pub fn set_wallpaper(path: &Path) -> Result<(), String> {
+    let uri = url::Url::from_file_path(path)
+        .map_err(|_| format!("wallpaper: not an absolute path: {}", path.display()))?;
+
+    let res = zbus::block_on(set_wallpaper_uri(uri.as_str()));
+    if res.is_err() {
+        bus::invalidate();
+    }
+    res
+}
+
+async fn set_wallpaper_uri(uri: &str) -> Result<(), String> {
+    let conn = bus::session()
+        .await
+        .map_err(|e| format!("wallpaper: dbus session connect: {e}"))?;
+
+    let proxy = Proxy::new(&conn, PORTAL_DEST, PORTAL_PATH, WALLPAPER_IFACE)
+        .await
+        .map_err(|e| format!("wallpaper: portal proxy: {e}"))?;
+
+    let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+    options.insert("show-preview", Value::from(false));
+    options.insert("set-on", Value::from("background"));
+
+    let parent_window = "";
+
+    let request_path: OwnedObjectPath = proxy
+        .call("SetWallpaperURI", &(parent_window, uri, options))
+        .await
+        .map_err(|e| format!("wallpaper: SetWallpaperURI() failed: {e}"))?;
+
+    let (response, _results) = portal::wait_for_response(&conn, request_path, RESPONSE_TIMEOUT)
+        .await
+        .map_err(|e| format!("wallpaper: {e}"))?;
+
+    match response {
+        0 => Ok(()),
+        1 => Err("wallpaper: cancelled".into()),
+        _ => Err(format!("wallpaper: portal refused (response={response})")),
+    }
+}
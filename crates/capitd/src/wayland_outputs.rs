@@ -3,20 +3,29 @@
 // Using SCTK for proper xdg-output support
 
 use capit_core::OutputInfo;
+use eventline::{debug, warn};
 use smithay_client_toolkit::{
-    output::{OutputHandler, OutputState},
+    output::{self, OutputHandler, OutputState},
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
 };
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
 use wayland_client::{
     globals::registry_queue_init,
     protocol::wl_output,
-    Connection, QueueHandle,
+    Connection, EventQueue, QueueHandle,
 };
 
+/// Pause between re-checks while waiting for outputs to appear.
+const SETTLE_STEP: Duration = Duration::from_millis(250);
+
 struct AppData {
     registry_state: RegistryState,
     output_state: OutputState,
+    /// An output was added, changed or removed since the watcher last sent.
+    changed: bool,
 }
 
 impl ProvidesRegistryState for AppData {
@@ -38,6 +47,7 @@ impl OutputHandler for AppData {
         _qh: &QueueHandle<Self>,
         _output: wl_output::WlOutput,
     ) {
+        self.changed = true;
     }
 
     fn update_output(
@@ -46,6 +56,7 @@ impl OutputHandler for AppData {
         _qh: &QueueHandle<Self>,
         _output: wl_output::WlOutput,
     ) {
+        self.changed = true;
     }
 
     fn output_destroyed(
@@ -54,10 +65,86 @@ impl OutputHandler for AppData {
         _qh: &QueueHandle<Self>,
         _output: wl_output::WlOutput,
     ) {
+        self.changed = true;
     }
 }
 
 pub fn query_outputs() -> Result<Vec<OutputInfo>, String> {
+    query_outputs_within(Duration::ZERO)
+}
+
+/// Like `query_outputs`, but if no output has shown up yet keep the
+/// connection open and roundtrip again every `SETTLE_STEP` for up to
+/// `settle`, so a monitor that is still coming up (slow dock, login race)
+/// is picked up instead of the daemon starting with none.
+pub fn query_outputs_within(settle: Duration) -> Result<Vec<OutputInfo>, String> {
+    let (mut event_queue, mut app_data) = connect()?;
+
+    let deadline = Instant::now() + settle;
+    let mut waited = 0u32;
+    while app_data.output_state.outputs().next().is_none() && Instant::now() < deadline {
+        std::thread::sleep(SETTLE_STEP);
+        waited += 1;
+        // New wl_output globals arrive on the first, their details on the second.
+        for _ in 0..2 {
+            event_queue
+                .roundtrip(&mut app_data)
+                .map_err(|e| format!("settle roundtrip: {e}"))?;
+        }
+    }
+    if waited > 0 {
+        debug!("waited {waited} x {SETTLE_STEP:?} for outputs to appear");
+    }
+
+    Ok(collect_outputs(&app_data.output_state))
+}
+
+/// Keep a Wayland connection open on a thread and send the full output list
+/// whenever a monitor is plugged in, unplugged or reconfigured. The first
+/// message is the list as it stands when the watcher connects.
+pub fn spawn_watcher() -> Receiver<Vec<OutputInfo>> {
+    let (tx, rx) = mpsc::channel();
+
+    let spawned = std::thread::Builder::new()
+        .name("capit-outputs".into())
+        .spawn(move || {
+            if let Err(e) = watch(tx) {
+                warn!("output watcher stopped (hotplugged outputs won't be seen): {e}");
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("spawn output watcher thread: {e}");
+    }
+
+    rx
+}
+
+fn watch(tx: Sender<Vec<OutputInfo>>) -> Result<(), String> {
+    let (mut event_queue, mut app_data) = connect()?;
+    app_data.changed = true;
+
+    loop {
+        if app_data.changed {
+            // One more roundtrip so a new output's xdg-output details are in.
+            event_queue
+                .roundtrip(&mut app_data)
+                .map_err(|e| format!("roundtrip: {e}"))?;
+            app_data.changed = false;
+
+            if tx.send(collect_outputs(&app_data.output_state)).is_err() {
+                // The daemon is shutting down.
+                return Ok(());
+            }
+        }
+
+        event_queue
+            .blocking_dispatch(&mut app_data)
+            .map_err(|e| format!("dispatch: {e}"))?;
+    }
+}
+
+/// Connect and roundtrip until the outputs present now are fully described.
+fn connect() -> Result<(EventQueue<AppData>, AppData), String> {
     let conn = Connection::connect_to_env().map_err(|e| format!("wayland connect: {e}"))?;
 
     let (globals, mut event_queue) = registry_queue_init(&conn)
@@ -70,6 +157,7 @@ pub fn query_outputs() -> Result<Vec<OutputInfo>, String> {
     let mut app_data = AppData {
         registry_state,
         output_state,
+        changed: false,
     };
 
     // Process initial events
@@ -82,36 +170,187 @@ pub fn query_outputs() -> Result<Vec<OutputInfo>, String> {
         .roundtrip(&mut app_data)
         .map_err(|e| format!("roundtrip 2: {e}"))?;
 
-    // Collect output info
+    Ok((event_queue, app_data))
+}
+
+/// Every output SCTK has info for, sorted by position, with stable ids.
+fn collect_outputs(output_state: &OutputState) -> Vec<OutputInfo> {
     let mut infos: Vec<OutputInfo> = Vec::new();
+    let mut ids: Vec<String> = Vec::new();
+
+    let mut used_fallback = false;
 
-    for output in app_data.output_state.outputs() {
-        let info_opt = app_data.output_state.info(&output);
+    for output in output_state.outputs() {
+        let info_opt = output_state.info(&output);
         
         if let Some(info) = info_opt {
             // SCTK provides logical geometry via xdg-output when available
-            let logical_pos = info.logical_position;
-            let logical_size = info.logical_size;
-            
+            let ((x, y), (width, height), source) = match (info.logical_position, info.logical_size) {
+                (Some(pos), Some(size)) => (pos, size, "xdg-output"),
+                _ => {
+                    used_fallback = true;
+                    let (pos, size) = wl_output_geometry(&info);
+                    (pos, size, "wl_output")
+                }
+            };
+            // xdg-output's logical size already reflects fractional scaling;
+            // the mode over it gives the real factor.
+            let fractional_scale = match (source, current_mode_size(&info)) {
+                ("xdg-output", (mw, _)) if mw > 0 && width > 0 => Some(mw as f64 / width as f64),
+                _ => None,
+            };
+            debug!(
+                "output {}: {width}x{height}+{x}+{y} (scale {}, fractional {:?}, from {source})",
+                info.name.as_deref().unwrap_or("?"),
+                info.scale_factor,
+                fractional_scale
+            );
+
             let output_info = OutputInfo {
                 name: info.name.clone(),
-                x: logical_pos.map(|(x, _)| x).unwrap_or(0),
-                y: logical_pos.map(|(_, y)| y).unwrap_or(0),
-                width: logical_size.map(|(w, _)| w as i32).unwrap_or(0),
-                height: logical_size.map(|(_, h)| h as i32).unwrap_or(0),
+                x,
+                y,
+                width,
+                height,
                 scale: info.scale_factor,
+                fractional_scale,
+                id: None,
+                physical_width_mm: positive(info.physical_size.0),
+                physical_height_mm: positive(info.physical_size.1),
+                refresh_mhz: current_mode(&info).map(|m| m.refresh_rate).and_then(positive),
+                make: known_str(&info.make),
+                model: known_str(&info.model),
             };
-            
+
             infos.push(output_info);
+            ids.push(base_id(&info.make, &info.model, info.name.as_deref()));
         }
     }
 
+    if used_fallback {
+        warn!("compositor did not report xdg-output geometry; output layout derived from wl_output");
+    }
+
+    let names: Vec<Option<String>> = infos.iter().map(|o| o.name.clone()).collect();
+    for (info, id) in infos.iter_mut().zip(disambiguate(ids, &names)) {
+        info.id = Some(id);
+    }
+
     // Sort by position for consistent ordering
     infos.sort_by_key(|info| (info.y, info.x));
 
-    Ok(infos)
+    infos
+}
+
+/// Layout position and logical size without xdg-output: wl_output's position,
+/// plus the current mode rotated by the output transform and divided by the
+/// integer scale. Exact for integer scales; fractional scales aren't
+/// reported by wl_output at all.
+fn wl_output_geometry(info: &output::OutputInfo) -> ((i32, i32), (i32, i32)) {
+    let (w, h) = current_mode_size(info);
+    let scale = info.scale_factor.max(1);
+    (info.location, (w / scale, h / scale))
+}
+
+/// The mode flagged current, else the first one advertised.
+fn current_mode(info: &output::OutputInfo) -> Option<&output::Mode> {
+    info.modes.iter().find(|m| m.current).or(info.modes.first())
+}
+
+/// Sizes and rates of 0 mean "unknown" on the wire.
+fn positive(v: i32) -> Option<i32> {
+    (v > 0).then_some(v)
+}
+
+/// wl_output's make/model, unless empty or the "Unknown" placeholder.
+fn known_str(s: &str) -> Option<String> {
+    let s = s.trim();
+    (!s.is_empty() && !s.eq_ignore_ascii_case("unknown")).then(|| s.to_string())
+}
+
+/// The current mode in physical pixels, rotated by the output transform so
+/// it lines up with the logical size. (0, 0) when no mode was reported.
+fn current_mode_size(info: &output::OutputInfo) -> (i32, i32) {
+    let (mw, mh) = current_mode(info).map(|m| m.dimensions).unwrap_or((0, 0));
+
+    match info.transform {
+        wl_output::Transform::_90
+        | wl_output::Transform::_270
+        | wl_output::Transform::Flipped90
+        | wl_output::Transform::Flipped270 => (mh, mw),
+        _ => (mw, mh),
+    }
+}
+
+/// Stable id before disambiguation: make and model as a slug
+/// ("Dell Inc." + "DELL U2720Q" -> "dell-inc-dell-u2720q"), else the
+/// connector name. wl_output carries no serial number.
+fn base_id(make: &str, model: &str, name: Option<&str>) -> String {
+    let known = |s: &str| !s.trim().is_empty() && !s.trim().eq_ignore_ascii_case("unknown");
+    let raw = match (known(make), known(model)) {
+        (true, true) => format!("{make} {model}"),
+        (true, false) => make.to_string(),
+        (false, true) => model.to_string(),
+        (false, false) => name.unwrap_or("output").to_string(),
+    };
+    slug(&raw)
+}
+
+/// Identical monitors share a base id; tell them apart by connector name,
+/// or by position in the list when there isn't one.
+fn disambiguate(ids: Vec<String>, names: &[Option<String>]) -> Vec<String> {
+    ids.iter()
+        .enumerate()
+        .map(|(i, id)| {
+            if ids.iter().filter(|other| *other == id).count() == 1 {
+                return id.clone();
+            }
+            match names[i].as_deref() {
+                Some(name) => format!("{id}-{}", slug(name)),
+                None => format!("{id}-{}", i + 1),
+            }
+        })
+        .collect()
+}
+
+/// Lowercase ASCII alphanumerics, runs of anything else become one '-'.
+fn slug(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    if out.ends_with('-') {
+        out.pop();
+    }
+    out
 }
 
 // Required trait implementations
 smithay_client_toolkit::delegate_output!(AppData);
 smithay_client_toolkit::delegate_registry!(AppData);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_comes_from_make_and_model() {
+        assert_eq!(base_id("Dell Inc.", "DELL U2720Q", Some("DP-1")), "dell-inc-dell-u2720q");
+        assert_eq!(base_id("Unknown", "Unknown", Some("HDMI-A-1")), "hdmi-a-1");
+        assert_eq!(base_id("", "", None), "output");
+    }
+
+    #[test]
+    fn identical_monitors_get_their_connector() {
+        let ids = vec!["lg-27gl850".to_string(), "lg-27gl850".to_string(), "dell-u2720q".to_string()];
+        let names = [Some("DP-1".to_string()), None, Some("DP-3".to_string())];
+        assert_eq!(
+            disambiguate(ids, &names),
+            ["lg-27gl850-dp-1", "lg-27gl850-2", "dell-u2720q"]
+        );
+    }
+}